@@ -12,13 +12,15 @@ layout(std140) struct LayerData {
     vec4 background_color;
     vec4 outline_color;
     float border_width;
-    uint line_width;
+    float line_width;
     float z_index;
 };
 
 layout(std140, set = 0, binding = 0) uniform Locals {
     vec2 canvas_size;
-    vec2 _unused;
+    // Multiplies all colors, below 1.0 dims the chart at night.
+    float brightness;
+    float _unused;
     LayerData layer_datas[1000];
 };
 
@@ -47,11 +49,10 @@ void main() {
     LayerData layer_data = layer_datas[feature_id];
     TileData tile_data = tile_datas[tile_id];
 
-    // Is the line we are currently handling sized in world coordinates or pixels?
-    bool is_world_scale_line = (layer_data.line_width & 0x02) == 1;
     bool is_line = feature_type == 1;
 
-    float line_width = layer_data.line_width >> 2;
+    // The normal is added on both sides of the line.
+    float line_width = layer_data.line_width / 2;
 
     // Calculate the tile normal normal in [0.0, 1.0] coordinates.
     vec2 local_normal = normal / tile_data.extent;
@@ -72,8 +73,8 @@ void main() {
     // Transform the vertex.
     gl_Position = tile_data.transform * tile_local_position;
 
-    // If we have a pixel scale line, add the normal to the vertex after the world transform.
-    if(is_line && !is_world_scale_line) {
+    // Lines are sized in pixels, so add the normal to the vertex after the world transform.
+    if(is_line) {
         gl_Position.xy += local_normal / canvas_size * line_width;
     }
 
@@ -84,6 +85,7 @@ void main() {
     } else {
         outColor = layer_data.background_color;
     }
+    outColor.rgb *= brightness;
 
     // Feather
     gl_Position.xy += local_normal / canvas_size * 2;
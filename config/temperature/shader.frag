@@ -6,6 +6,10 @@ layout(location = 0) out vec4 outColor;
 
 layout(set = 0, binding = 0) uniform texture2D t_Color;
 layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 2) uniform Viewport {
+    // The visible part of the world in global space: left, top, right, bottom.
+    vec4 bounds;
+};
 
 vec3 hsv2rgb(vec3 c) {
     vec4 K = vec4(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
@@ -14,6 +18,13 @@ vec3 hsv2rgb(vec3 c) {
 }
 
 void main() {
-    float v = (texture(sampler2D(t_Color, s_Color), vertexPos.xy / 2 + 0.5).x + 1) / 2;
+    // Clip space points up, global space points down.
+    vec2 uv = mix(bounds.xy, bounds.zw, vec2(vertexPos.x / 2 + 0.5, 0.5 - vertexPos.y / 2));
+    float value = texture(sampler2D(t_Color, s_Color), uv).x;
+    // Texels without data are -1.
+    if (uv.y < 0 || uv.y > 1 || value <= -1) {
+        discard;
+    }
+    float v = (value + 1) / 2;
     outColor = vec4(hsv2rgb(vec3(mix(0.15, 0, v), 1, 1)), 0.3);
 }
\ No newline at end of file
@@ -4,10 +4,13 @@ use lyon::math::Point;
 use stats::Stats;
 use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 pub struct AppState {
     pub tile_cache: TileCache,
     pub css_cache: RulesCache,
+    /// The index of the shown theme in `CONFIG.renderer.themes`.
+    pub theme: usize,
     pub screen: Screen,
     pub tile_field: TileField,
     pub zoom: f32,
@@ -15,23 +18,72 @@ pub struct AppState {
     pub selected_objects: Vec<EditableObject>,
     pub stats: Stats,
     pub ui: UIState,
+    pub own_ship: OwnShip,
+    pub ais_targets: AisTargetTable,
+    pub collision_monitor: CollisionMonitor,
+    pub alarms: Alarms,
+    pub anchor_watch: AnchorWatch,
+    pub guard_zone: GuardZoneMonitor,
+    /// The active man overboard.
+    pub mob: Option<ManOverboard>,
+    pub track_recorder: TrackRecorder,
+    /// While a track is replayed the own ship data of the NMEA source is ignored.
+    pub replay: Option<TrackReplay>,
+    pub tide_stations: TideStations,
+    pub tidal_streams: TidalStreamAtlas,
+    pub polar: Option<Polar>,
+    pub magnetic_model: Option<MagneticModel>,
+    /// The GRIB file with the weather forecast.
+    pub weather: GribFile,
+    /// The isobars of the selected forecast time and the time they were traced for.
+    isobars: (Option<f64>, Vec<Contour>),
+    pub route_start: Option<GeoPoint>,
+    pub route_destination: Option<GeoPoint>,
+    /// The last route computed by the weather routing.
+    pub route: Option<Route>,
+    /// The mark we are currently sailing to.
+    pub waypoint: Option<GeoPoint>,
+    /// The true wind direction averaged for the laylines.
+    pub wind_smoother: WindSmoother,
+    /// The position under the mouse cursor.
+    pub cursor: Option<GeoPoint>,
+    nmea_source: Option<NmeaSource>,
+    ais_decoder: AisDecoder,
     visible_tiles: BTreeMap<TileId, VisibleTile>,
     feature_collection: Arc<RwLock<FeatureCollection>>,
+    /// Whether the filter expressions of the stylesheet still have to be handed to the tiles.
+    filters_outdated: bool,
 }
 
 impl AppState {
-    pub fn new(
-        style: impl Into<String>,
-        center: Point,
-        width: u32,
-        height: u32,
-        zoom: f32,
-        hidpi_factor: f64,
-    ) -> Self {
+    pub fn new(center: Point, width: u32, height: u32, zoom: f32, hidpi_factor: f64) -> Self {
+        let track_recorder = TrackRecorder::new(
+            CONFIG.navigation.track_directory.clone(),
+            TrackThinning {
+                min_distance: CONFIG.navigation.track_min_distance,
+                min_interval: CONFIG.navigation.track_min_interval,
+                max_interval: CONFIG.navigation.track_max_interval,
+            },
+        );
+
+        let theme = CONFIG
+            .renderer
+            .themes
+            .iter()
+            .position(|theme| theme.name == CONFIG.renderer.theme)
+            .unwrap_or(0);
+        let style = &CONFIG
+            .renderer
+            .themes
+            .get(theme)
+            .expect("No theme is configured.")
+            .css;
+
         Self {
             tile_cache: TileCache::new(CONFIG.general.data_root.clone()),
             css_cache: RulesCache::try_load_from_file(style)
                 .expect("Unable to load the style file. Please consult the log."),
+            theme,
             screen: Screen::new(
                 center,
                 width,
@@ -44,11 +96,68 @@ impl AppState {
             hovered_objects: vec![],
             selected_objects: vec![],
             stats: Stats::new(),
-            ui: UIState::default(),
+            ui: UIState {
+                anchor_radius: CONFIG.navigation.anchor_radius as f32,
+                replay_file: track_recorder.log_path().to_string_lossy().to_string(),
+                show_wind: true,
+                show_isobars: true,
+                magnetic_bearings: CONFIG.navigation.magnetic_bearings,
+                ..Default::default()
+            },
+            own_ship: OwnShip::new(),
+            ais_targets: AisTargetTable::new(Duration::from_secs(CONFIG.navigation.ais_max_age)),
+            collision_monitor: CollisionMonitor::new(CpaLimits {
+                cpa: CONFIG.navigation.cpa_limit,
+                tcpa: CONFIG.navigation.tcpa_limit,
+            }),
+            alarms: Self::create_alarms(),
+            anchor_watch: AnchorWatch::load(
+                CONFIG.navigation.anchor_file.clone(),
+                Duration::from_secs(CONFIG.navigation.anchor_debounce),
+            ),
+            guard_zone: GuardZoneMonitor::new(
+                GuardZone {
+                    distance: CONFIG.navigation.guard_zone_distance,
+                    width: CONFIG.navigation.guard_zone_width,
+                },
+                HazardQuery::parse(&CONFIG.navigation.hazards, CONFIG.navigation.draft)
+                    .unwrap_or_default(),
+            ),
+            mob: None,
+            track_recorder,
+            replay: None,
+            tide_stations: TideStations::load(&CONFIG.navigation.tide_stations),
+            tidal_streams: TidalStreamAtlas::load(&CONFIG.navigation.tidal_streams),
+            polar: Polar::load(&CONFIG.navigation.polar_file),
+            magnetic_model: MagneticModel::load(&CONFIG.navigation.magnetic_model_file),
+            weather: CONFIG
+                .renderer
+                .temperature
+                .grib_file
+                .as_ref()
+                .and_then(|path| GribFile::load(path))
+                .unwrap_or_default(),
+            isobars: (None, vec![]),
+            route_start: None,
+            route_destination: None,
+            route: None,
+            waypoint: None,
+            wind_smoother: WindSmoother::new(Duration::from_secs(
+                CONFIG.navigation.layline_smoothing,
+            )),
+            cursor: None,
+            nmea_source: CONFIG.navigation.nmea_source.as_ref().and_then(|address| {
+                NmeaSource::open(
+                    address,
+                    Duration::from_millis(CONFIG.navigation.nmea_line_delay),
+                )
+            }),
+            ais_decoder: AisDecoder::new(),
             visible_tiles: BTreeMap::new(),
             feature_collection: Arc::new(RwLock::new(FeatureCollection::new(
                 CONFIG.renderer.max_features as u32,
             ))),
+            filters_outdated: true,
         }
     }
 
@@ -60,7 +169,78 @@ impl AppState {
         self.feature_collection.clone()
     }
 
+    /// Returns the shown theme.
+    pub fn theme(&self) -> &'static crate::config::Theme {
+        &CONFIG.renderer.themes[self.theme]
+    }
+
+    /// Switches to the theme at `index` in `CONFIG.renderer.themes` and restyles all features with its stylesheet.
+    ///
+    /// The current theme is kept if the stylesheet can not be loaded.
+    /// Style edits made in the HUD are saved to the stylesheet of the current theme first.
+    pub fn set_theme(&mut self, index: usize) {
+        let theme = match CONFIG.renderer.themes.get(index) {
+            Some(theme) => theme,
+            None => return,
+        };
+        match RulesCache::try_load_from_file(&theme.css) {
+            Some(css_cache) => {
+                if self.css_cache.has_unsaved_edits() {
+                    if !self.css_cache.save() {
+                        log::warn!(
+                            "Keeping the {} theme since its style edits could not be saved.",
+                            self.theme().name
+                        );
+                        return;
+                    }
+                    log::info!("Saved the style edits of the {} theme.", self.theme().name);
+                }
+                self.css_cache = css_cache;
+                self.theme = index;
+                self.filters_outdated = true;
+                self.update_filters();
+                if let Ok(mut feature_collection) = self.feature_collection.write() {
+                    feature_collection.load_styles(self.zoom, &mut self.css_cache);
+                }
+            }
+            None => log::warn!(
+                "Unable to load the stylesheet of the {} theme. Please consult the log.",
+                theme.name
+            ),
+        }
+    }
+
+    /// Switches to the theme after the shown one.
+    pub fn next_theme(&mut self) {
+        self.set_theme((self.theme + 1) % CONFIG.renderer.themes.len());
+    }
+
+    /// Hands the filter expressions of the stylesheet to the tiles and loads all tiles again if they changed.
+    ///
+    /// Does nothing unless the stylesheet was loaded since the last time, retries in the next frame if the feature collection is busy.
+    fn update_filters(&mut self) {
+        if !self.filters_outdated {
+            return;
+        }
+        let changed = match self.feature_collection.try_write() {
+            Ok(mut feature_collection) => {
+                self.filters_outdated = false;
+                feature_collection.set_filters(self.css_cache.filters())
+            }
+            Err(_) => false,
+        };
+        if changed {
+            self.tile_cache.clear();
+            self.visible_tiles.clear();
+        }
+    }
+
     pub fn load_tiles(&mut self) {
+        if self.css_cache.update() {
+            self.filters_outdated = true;
+        }
+        self.update_filters();
+
         let tile_field = self.screen.get_tile_boundaries_for_zoom_level(self.zoom, 1);
 
         // Remove old bigger tiles which are not in the FOV anymore.
@@ -141,6 +321,316 @@ impl AppState {
         }
     }
 
+    /// Works all the NMEA sentences which arrived since the last frame.
+    pub fn update_navigation(&mut self) {
+        let now = Instant::now();
+        if let Some(nmea_source) = &self.nmea_source {
+            let live = self.replay.is_none();
+            for sentence in nmea_source.try_iter() {
+                if let Some(report) = self.ais_decoder.decode(&sentence) {
+                    if live {
+                        self.own_ship.update_from_ais(&report, now);
+                    }
+                    self.ais_targets.update(&report, now);
+                } else if let Some(message) = NmeaMessage::parse(&sentence) {
+                    if live {
+                        self.own_ship.update(&message, now);
+                    }
+                }
+            }
+        }
+
+        if let Some(replay) = &mut self.replay {
+            for message in replay.update(now) {
+                self.own_ship.update(&message, now);
+            }
+        } else {
+            self.track_recorder.update(&self.own_ship);
+        }
+
+        if let Some(position) = self.own_ship.position {
+            if let Some(variation) = self.model_variation(&position) {
+                self.own_ship.set_model_variation(variation);
+            }
+        }
+
+        self.ais_targets.remove_stale(now);
+        self.collision_monitor
+            .update(&self.own_ship, &self.ais_targets, &mut self.alarms, now);
+        self.anchor_watch
+            .update(&self.own_ship, &mut self.alarms, now);
+        if self.guard_zone.zone.distance > 0.0 {
+            self.guard_zone.update(
+                &self.own_ship,
+                self.visible_tiles.values(),
+                &mut self.alarms,
+                now,
+            );
+        }
+        if let Some(wind) = self.own_ship.true_wind_direction() {
+            self.wind_smoother.update(wind, now);
+        }
+        if let Some(mob) = &self.mob {
+            self.alarms.raise(
+                AlarmKind::ManOverboard,
+                mob.summary(&self.own_ship, self.display_variation(), now),
+                now,
+            );
+        }
+    }
+
+    /// Returns the magnetic variation of the model at a position for the time of the last fix.
+    fn model_variation(&self, position: &GeoPoint) -> Option<f64> {
+        let time = self.own_ship.utc.unwrap_or_else(unix_now);
+        Some(self.magnetic_model.as_ref()?.declination(position, time))
+    }
+
+    /// Returns the variation bearings are converted with, `None` if they are shown true.
+    ///
+    /// The variation at our position is used for all bearings since it changes only slowly across the chart.
+    pub fn display_variation(&self) -> Option<f64> {
+        if self.ui.magnetic_bearings {
+            self.own_ship.variation.or_else(|| {
+                let center = GeoPoint::from_global_space(&self.screen.center);
+                self.model_variation(&center)
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Formats a bearing in degrees true as true or magnetic as selected in the HUD.
+    pub fn format_bearing(&self, bearing: f64) -> String {
+        format_bearing(bearing, self.display_variation())
+    }
+
+    /// Marks a man overboard and sets the position as the waypoint to return to.
+    ///
+    /// The position is taken from the track `mob_lookback` seconds ago if configured.
+    /// While a MOB is active it is kept, it has to be recovered first.
+    /// A running replay is stopped first, the MOB is always marked from live data and never from a replayed position.
+    pub fn man_overboard(&mut self) {
+        if self.mob.is_some() {
+            log::warn!("A man overboard is already marked, recover it first (Shift+F9).");
+            return;
+        }
+        if self.replay.is_some() {
+            self.stop_replay();
+        }
+        let now = Instant::now();
+        let utc = self.own_ship.utc.unwrap_or_else(unix_now);
+        match ManOverboard::mark(
+            &self.own_ship,
+            self.track_recorder.track(),
+            CONFIG.navigation.mob_lookback,
+            utc,
+            now,
+        ) {
+            Some(mob) => {
+                self.waypoint = Some(mob.position);
+                self.alarms.raise(
+                    AlarmKind::ManOverboard,
+                    mob.summary(&self.own_ship, self.display_variation(), now),
+                    now,
+                );
+                self.mob = Some(mob);
+            }
+            None => log::error!("Can't mark the man overboard without a live position fix."),
+        }
+    }
+
+    /// Ends the man overboard mode once the person is back on board.
+    pub fn recover_mob(&mut self) {
+        if let Some(mob) = self.mob.take() {
+            if self.waypoint == Some(mob.position) {
+                self.waypoint = None;
+            }
+            self.alarms.clear(&AlarmKind::ManOverboard);
+        }
+    }
+
+    /// Returns the laylines of the active waypoint for the averaged true wind.
+    ///
+    /// The configured tacking angle takes precedence over the optimal upwind angle of the polar.
+    pub fn laylines(&self) -> Option<Laylines> {
+        let waypoint = self.waypoint?;
+        let wind = self.wind_smoother.average()?;
+        let tacking_angle = CONFIG
+            .navigation
+            .tacking_angle
+            .or_else(|| Some(self.polar.as_ref()?.best_upwind(wind.speed).0))?;
+        Some(Laylines::new(
+            &waypoint,
+            wind.angle,
+            tacking_angle,
+            CONFIG.navigation.layline_length,
+        ))
+    }
+
+    /// Sets the active waypoint to the position under the given screen point.
+    pub fn pick_waypoint(&mut self, point: (f32, f32)) {
+        self.waypoint = Some(GeoPoint::from_global_space(
+            &self.screen.screen_to_global(self.zoom, point),
+        ));
+        self.ui.pick_waypoint = false;
+    }
+
+    /// Returns the forecast time of the weather layers selected in the HUD in hours.
+    pub fn weather_hours(&self) -> Option<f64> {
+        self.weather
+            .forecast_hours()
+            .get(self.ui.weather_step)
+            .copied()
+    }
+
+    pub fn isobars(&self) -> &[Contour] {
+        &self.isobars.1
+    }
+
+    /// Traces the isobars again whenever another forecast time is selected.
+    pub fn update_weather(&mut self) {
+        let hours = self.weather_hours();
+        if hours == self.isobars.0 {
+            return;
+        }
+        let isobars = hours
+            .and_then(|hours| self.weather.find(GribParameter::Pressure, hours))
+            .map(|field| field.contours(CONFIG.renderer.isobar_interval * 100.0, 2))
+            .unwrap_or_default();
+        self.isobars = (hours, isobars);
+    }
+
+    /// Sets the start or the destination of the weather routing to the position under the given screen point.
+    pub fn pick_route_point(&mut self, point: (f32, f32)) {
+        let position = GeoPoint::from_global_space(&self.screen.screen_to_global(self.zoom, point));
+        if self.ui.pick_route_start {
+            self.route_start = Some(position);
+        } else {
+            self.route_destination = Some(position);
+        }
+        self.ui.pick_route_start = false;
+        self.ui.pick_route_destination = false;
+    }
+
+    /// Computes the route between the picked points leaving at the forecast time selected in the HUD.
+    ///
+    /// Land is avoided within the currently loaded tiles.
+    pub fn compute_route(&mut self) {
+        self.route = None;
+        let (polar, start, destination) =
+            match (&self.polar, self.route_start, self.route_destination) {
+                (Some(polar), Some(start), Some(destination)) => (polar, start, destination),
+                _ => {
+                    log::warn!("The weather routing needs a polar, a start and a destination.");
+                    return;
+                }
+            };
+        let departure = match (self.weather.fields().first(), self.weather_hours()) {
+            (Some(field), Some(hours)) => field.reference_time + hours * 3600.0,
+            _ => {
+                log::warn!("The weather routing needs a wind forecast.");
+                return;
+            }
+        };
+
+        let mut water = WaterMask::new();
+        for tile in self.visible_tiles.values() {
+            if let Ok(objects) = tile.objects().read() {
+                water.add_tile(&tile.tile_id(), tile.extent(), &objects);
+            }
+        }
+
+        let options = RoutingOptions {
+            time_step: CONFIG.navigation.routing_time_step * 60.0,
+            heading_step: CONFIG.navigation.routing_heading_step,
+            heading_fan: CONFIG.navigation.routing_heading_fan,
+            max_steps: CONFIG.navigation.routing_max_steps,
+            ..Default::default()
+        };
+        self.route = Router::new(polar, &self.weather, &water, options).route(
+            &start,
+            &destination,
+            departure,
+        );
+    }
+
+    /// Creates the alarms and hooks up the configured alarm command.
+    fn create_alarms() -> Alarms {
+        let mut alarms = Alarms::new();
+        if let Some(command) = &CONFIG.navigation.alarm_command {
+            alarms.add_hook(move |alarm| {
+                match std::process::Command::new(command)
+                    .arg(&alarm.message)
+                    .spawn()
+                {
+                    // Wait for the command on its own thread so it doesn't linger as a zombie.
+                    Ok(mut child) => {
+                        std::thread::spawn(move || {
+                            if let Err(err) = child.wait() {
+                                log::error!("Failed to wait for the alarm command:");
+                                log::error!("{}", err);
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        log::error!("Failed to run the alarm command {}:", command);
+                        log::error!("{}", err);
+                    }
+                }
+            });
+        }
+        alarms
+    }
+
+    /// Starts to replay the track log at `path`.
+    ///
+    /// Replaying stops the track recorder, so it is refused while a MOB is active.
+    pub fn start_replay(&mut self, path: &str) {
+        if self.mob.is_some() {
+            log::warn!("Can't replay a track while a man overboard is active.");
+            return;
+        }
+        self.replay = Track::load_csv(path)
+            .and_then(|track| TrackReplay::new(track, CONFIG.navigation.replay_speed));
+        if self.replay.is_none() {
+            log::warn!(
+                "The track log {} does not contain any points to replay.",
+                path
+            );
+        }
+    }
+
+    /// Stops the replay and goes back to live data.
+    pub fn stop_replay(&mut self) {
+        self.replay = None;
+        self.own_ship = OwnShip::new();
+        self.wind_smoother.clear();
+    }
+
+    /// Drops the anchor at our current position.
+    pub fn drop_anchor_at_fix(&mut self) {
+        if let Some(position) = self.own_ship.position {
+            self.anchor_watch
+                .drop_anchor(position, self.ui.anchor_radius as f64);
+        } else {
+            log::warn!("Can't drop the anchor without a position fix.");
+        }
+    }
+
+    /// Drops the anchor at the position under the given screen point.
+    pub fn drop_anchor_at(&mut self, point: (f32, f32)) {
+        let position = GeoPoint::from_global_space(&self.screen.screen_to_global(self.zoom, point));
+        self.anchor_watch
+            .drop_anchor(position, self.ui.anchor_radius as f64);
+        self.ui.pick_anchor = false;
+    }
+
+    pub fn update_cursor(&mut self, point: (f32, f32)) {
+        self.cursor = Some(GeoPoint::from_global_space(
+            &self.screen.screen_to_global(self.zoom, point),
+        ));
+    }
+
     pub fn update_hovered_objects(&mut self, point: (f32, f32)) {
         self.hovered_objects =
             Collider::get_hovered_objects(&self.visible_tiles, &self.screen, self.zoom, point);
@@ -7,7 +7,10 @@ pub static CONFIG: Lazy<Config> = Lazy::new(|| Config::new());
 pub struct Renderer {
     pub vertex_shader: String,
     pub fragment_shader: String,
-    pub css: String,
+    /// The themes which can be switched between at runtime.
+    pub themes: Vec<Theme>,
+    /// The name of the theme used on startup.
+    pub theme: String,
     pub max_tiles: usize,
     pub max_features: u64,
     pub tile_size: u32,
@@ -15,6 +18,10 @@ pub struct Renderer {
     pub selection_tags: Vec<String>,
     pub ui_font: String,
     pub temperature: Temperature,
+    /// The minimum distance between two wind barbs in pixels.
+    pub wind_barb_spacing: f32,
+    /// The pressure difference between two isobars in hPa.
+    pub isobar_interval: f32,
 }
 
 impl Default for Renderer {
@@ -22,7 +29,27 @@ impl Default for Renderer {
         Self {
             vertex_shader: "config/shader.vert".to_string(),
             fragment_shader: "config/shader.frag".to_string(),
-            css: "config/style.css".to_string(),
+            themes: vec![
+                Theme {
+                    name: "Day".to_string(),
+                    css: "config/style.css".to_string(),
+                    brightness: 1.0,
+                    hud_colors: HudColors::Light,
+                },
+                Theme {
+                    name: "Dusk".to_string(),
+                    css: "config/dusk.css".to_string(),
+                    brightness: 0.7,
+                    hud_colors: HudColors::Dark,
+                },
+                Theme {
+                    name: "Night".to_string(),
+                    css: "config/night.css".to_string(),
+                    brightness: 0.4,
+                    hud_colors: HudColors::Red,
+                },
+            ],
+            theme: "Day".to_string(),
             max_tiles: 200,
             max_features: 1000,
             tile_size: 384,
@@ -30,14 +57,44 @@ impl Default for Renderer {
             selection_tags: Default::default(),
             ui_font: "config/Ruda-Bold.ttf".to_string(),
             temperature: Default::default(),
+            wind_barb_spacing: 60.0,
+            isobar_interval: 4.0,
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    /// The stylesheet of the chart.
+    pub css: String,
+    /// The factor all colors of the chart and the HUD are multiplied with, below `1.0` dims them.
+    ///
+    /// The labels are drawn in black, so they need no dimming.
+    pub brightness: f32,
+    pub hud_colors: HudColors,
+}
+
+/// The color scheme of the HUD.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HudColors {
+    Light,
+    Dark,
+    /// Only shades of red, to keep the night vision.
+    Red,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Temperature {
     pub vertex_shader: String,
     pub fragment_shader: String,
+    /// The GRIB2 file with the weather forecast.
+    ///
+    /// The weather layer, the wind and the isobars show the forecast time selected in the HUD.
+    pub grib_file: Option<String>,
+    /// The abbreviation of the displayed parameter, e.g. `TMP`, `UGRD`, `VGRD`, `PRMSL` or `TCDC`.
+    pub parameter: String,
 }
 
 impl Default for Temperature {
@@ -45,6 +102,8 @@ impl Default for Temperature {
         Self {
             vertex_shader: "config/temperature/shader.vert".to_string(),
             fragment_shader: "config/temperature/shader.frag".to_string(),
+            grib_file: None,
+            parameter: "TMP".to_string(),
         }
     }
 }
@@ -65,10 +124,125 @@ impl Default for General {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Navigation {
+    /// A NMEA log file or a `tcp://host:port` multiplexer address.
+    pub nmea_source: Option<String>,
+    /// The delay between two lines when replaying a NMEA log file in milliseconds.
+    pub nmea_line_delay: u64,
+    /// The time after which an AIS target that has not been heard of is dropped in seconds.
+    pub ais_max_age: u64,
+    /// Targets passing closer than this distance in nautical miles raise a collision alarm.
+    pub cpa_limit: f64,
+    /// Only targets reaching their CPA within this time in minutes raise a collision alarm.
+    pub tcpa_limit: f64,
+    /// The length of the predicted course vectors drawn for all vessels in minutes.
+    pub vector_length: f64,
+    /// The file the anchor watch is persisted to.
+    pub anchor_file: String,
+    /// The default swing radius of the anchor watch in meters.
+    pub anchor_radius: f64,
+    /// How long the vessel has to be outside the swing circle before the anchor alarm goes off in seconds.
+    pub anchor_debounce: u64,
+    /// A command which is run with the alarm message as argument whenever an alarm is raised, e.g. to play a sound.
+    pub alarm_command: Option<String>,
+    /// The directory the daily track logs are written to.
+    pub track_directory: String,
+    /// The minimum distance in meters between two logged track points.
+    pub track_min_distance: f64,
+    /// The minimum time in seconds between two logged track points.
+    pub track_min_interval: f64,
+    /// The time in seconds after which a track point is logged even if the vessel did not move.
+    pub track_max_interval: f64,
+    /// How many seconds of a track are replayed per second.
+    pub replay_speed: f64,
+    /// The TOML file with the harmonic constants of the tide stations.
+    pub tide_stations: String,
+    /// The TOML file with the tidal stream atlas.
+    pub tidal_streams: String,
+    /// The polar of the boat as a table or POL file.
+    pub polar_file: String,
+    /// The time between two isochrones of the weather routing in minutes.
+    pub routing_time_step: f64,
+    /// The angle between two headings tried by the weather routing in degrees.
+    pub routing_heading_step: f64,
+    /// The width of the fan of headings around the bearing to the destination in degrees.
+    pub routing_heading_fan: f64,
+    /// The number of isochrones after which the weather routing gives up.
+    pub routing_max_steps: usize,
+    /// The true wind angle sailed close hauled in degrees.
+    /// If not set, the optimal upwind angle of the polar is used.
+    pub tacking_angle: Option<f64>,
+    /// The time window the true wind is averaged over for the laylines in seconds.
+    pub layline_smoothing: u64,
+    /// The length of the laylines in nautical miles.
+    pub layline_length: f64,
+    /// The draft of the vessel in meters.
+    pub draft: f64,
+    /// How far the guard zone reaches ahead along the course over ground in nautical miles.
+    /// A distance of zero disables the guard zone.
+    pub guard_zone_distance: f64,
+    /// The opening angle of the guard zone in degrees.
+    pub guard_zone_width: f64,
+    /// The chart objects which raise the hazard alarm in the guard zone, e.g. `class=rock` or `depth<draft`.
+    pub hazards: Vec<String>,
+    /// How many seconds before pressing the MOB key the position is taken from the track.
+    pub mob_lookback: f64,
+    /// The coefficient file of the World Magnetic Model.
+    pub magnetic_model_file: String,
+    /// Whether bearings are shown magnetic instead of true on startup.
+    pub magnetic_bearings: bool,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self {
+            nmea_source: None,
+            nmea_line_delay: 10,
+            ais_max_age: 360,
+            cpa_limit: 0.5,
+            tcpa_limit: 20.0,
+            vector_length: 6.0,
+            anchor_file: "anchor.toml".to_string(),
+            anchor_radius: 50.0,
+            anchor_debounce: 20,
+            alarm_command: None,
+            track_directory: "tracks".to_string(),
+            track_min_distance: 10.0,
+            track_min_interval: 5.0,
+            track_max_interval: 600.0,
+            replay_speed: 60.0,
+            tide_stations: "data/tides.toml".to_string(),
+            tidal_streams: "data/streams.toml".to_string(),
+            polar_file: "data/polar.csv".to_string(),
+            routing_time_step: 60.0,
+            routing_heading_step: 5.0,
+            routing_heading_fan: 180.0,
+            routing_max_steps: 240,
+            tacking_angle: None,
+            layline_smoothing: 30,
+            layline_length: 5.0,
+            draft: 2.0,
+            guard_zone_distance: 0.5,
+            guard_zone_width: 30.0,
+            hazards: vec![
+                "depth<draft".to_string(),
+                "class=rock".to_string(),
+                "class=wreck".to_string(),
+                "class=obstruction".to_string(),
+            ],
+            mob_lookback: 0.0,
+            magnetic_model_file: "data/WMM.COF".to_string(),
+            magnetic_bearings: false,
+        }
+    }
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct Config {
     pub general: General,
     pub renderer: Renderer,
+    pub navigation: Navigation,
 }
 
 impl Config {
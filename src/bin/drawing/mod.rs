@@ -1,4 +1,5 @@
 pub mod helpers;
+mod overlays;
 mod painter;
 pub mod ui;
 pub mod weather;
@@ -0,0 +1,518 @@
+use lyon::math::{vector, Point};
+use osm::*;
+use std::time::Instant;
+
+use crate::app_state::AppState;
+use crate::config::CONFIG;
+
+/// Builds the overlay with all the navigation data for the current frame.
+pub fn create_overlay(app_state: &AppState) -> Overlay {
+    let mut overlay = Overlay::new(
+        &app_state.screen,
+        app_state.zoom,
+        app_state.feature_collection(),
+    );
+
+    draw_isobars(&mut overlay, app_state);
+    draw_wind(&mut overlay, app_state);
+    draw_tide_stations(&mut overlay, app_state);
+    draw_tidal_streams(&mut overlay, app_state);
+    draw_track(&mut overlay, app_state);
+    draw_route(&mut overlay, app_state);
+    draw_waypoint(&mut overlay, app_state);
+    draw_anchor_watch(&mut overlay, app_state);
+    draw_guard_zone(&mut overlay, app_state);
+    draw_ais_targets(&mut overlay, app_state);
+    draw_own_ship(&mut overlay, &app_state.own_ship);
+    draw_mob(&mut overlay, app_state);
+
+    overlay
+}
+
+fn draw_own_ship(overlay: &mut Overlay, own_ship: &OwnShip) {
+    if let Some(position) = own_ship.position {
+        let selector = Selector::new().with_type("ownship");
+        draw_vector(
+            overlay,
+            &selector.clone().with_class("vector"),
+            &position,
+            own_ship.cog,
+            own_ship.sog,
+        );
+        overlay.add_oriented_triangle(
+            &selector,
+            &position.to_global_space(),
+            own_ship.heading_or_cog().unwrap_or(0.0) as f32,
+            24.0,
+            14.0,
+        );
+    }
+}
+
+/// Draws the isobars of the forecast time chosen in the HUD labelled in hPa.
+fn draw_isobars(overlay: &mut Overlay, app_state: &AppState) {
+    if !app_state.ui.show_isobars {
+        return;
+    }
+
+    let selector = Selector::new().with_type("weather").with_class("isobar");
+    overlay.add_lines(
+        &selector,
+        app_state.isobars().iter().map(|isobar| &isobar.points[..]),
+    );
+    for isobar in app_state.isobars() {
+        if let Some(middle) = isobar.points.get(isobar.points.len() / 2) {
+            overlay.add_label(middle, format!("{:.0}", isobar.level / 100.0));
+        }
+    }
+}
+
+/// Draws wind barbs at the grid points of the forecast time chosen in the HUD.
+///
+/// Only every n-th grid point is drawn so the barbs never get closer than the configured spacing on screen.
+fn draw_wind(overlay: &mut Overlay, app_state: &AppState) {
+    if !app_state.ui.show_wind {
+        return;
+    }
+    let wind = match app_state
+        .weather_hours()
+        .and_then(|hours| WindField::new(&app_state.weather, hours))
+    {
+        Some(wind) => wind,
+        None => return,
+    };
+    let grid = wind.grid();
+
+    // The grid spacing in pixels at the screen center, meridians get stretched towards the poles.
+    let pixels_per_degree = 1.0 / 360.0 / overlay.pixels_to_global(1.0) as f64;
+    let center = GeoPoint::from_global_space(&app_state.screen.center);
+    let spacing = CONFIG.renderer.wind_barb_spacing as f64;
+    let stride_i = (spacing / (grid.di * pixels_per_degree)).ceil().max(1.0) as usize;
+    let stride_j = (spacing * center.lat.to_radians().cos() / (grid.dj * pixels_per_degree))
+        .ceil()
+        .max(1.0) as usize;
+
+    let top_left = GeoPoint::from_global_space(
+        &app_state
+            .screen
+            .screen_to_global(app_state.zoom, (0.0, 0.0)),
+    );
+    let bottom_right = GeoPoint::from_global_space(&app_state.screen.screen_to_global(
+        app_state.zoom,
+        (
+            app_state.screen.width as f32,
+            app_state.screen.height as f32,
+        ),
+    ));
+
+    for j in (0..grid.nj).step_by(stride_j) {
+        let lat = grid.position(0, j).lat;
+        if lat > top_left.lat || lat < bottom_right.lat {
+            continue;
+        }
+        for i in (0..grid.ni).step_by(stride_i) {
+            let position = grid.position(i, j);
+            let global = position.to_global_space();
+            let (x, y) = app_state
+                .screen
+                .global_to_screen_point(app_state.zoom, &global);
+            if x < 0.0
+                || y < 0.0
+                || x > app_state.screen.width as f32
+                || y > app_state.screen.height as f32
+            {
+                continue;
+            }
+            if let Some(wind) = wind.at_grid_point(i, j) {
+                let selector = Selector::new()
+                    .with_type("weather")
+                    .with_class("wind")
+                    .with_class(wind_class(wind.speed));
+                draw_wind_barb(overlay, &selector, &global, &wind, position.lat < 0.0);
+            }
+        }
+    }
+}
+
+/// Returns the class of the colour ramp for a wind speed in knots, roughly following the Beaufort scale.
+fn wind_class(speed: f64) -> &'static str {
+    if speed < 11.0 {
+        "light"
+    } else if speed < 22.0 {
+        "moderate"
+    } else if speed < 34.0 {
+        "strong"
+    } else if speed < 48.0 {
+        "gale"
+    } else {
+        "storm"
+    }
+}
+
+/// Draws a wind barb with its staff pointing into the wind.
+///
+/// The barbs are drawn on the clockwise side of the staff, which flips on the southern hemisphere.
+fn draw_wind_barb(
+    overlay: &mut Overlay,
+    selector: &Selector,
+    position: &Point,
+    wind: &Wind,
+    southern_hemisphere: bool,
+) {
+    let barb = WindBarb::from_speed(wind.speed);
+    if barb.is_calm() {
+        let radius = overlay.pixels_to_global(4.0);
+        overlay.add_circle(selector, position, radius);
+        return;
+    }
+
+    let (sin, cos) = (wind.angle as f32).to_radians().sin_cos();
+    let forward = vector(sin, -cos);
+    let side = if southern_hemisphere {
+        vector(-cos, -sin)
+    } else {
+        vector(cos, sin)
+    };
+    let length = overlay.pixels_to_global(30.0);
+    let barb_length = overlay.pixels_to_global(12.0);
+    let gap = overlay.pixels_to_global(4.0);
+    let along = |distance: f32| *position + forward * distance;
+
+    overlay.add_line(selector, &[*position, along(length)]);
+
+    let mut distance = length;
+    for _ in 0..barb.pennants {
+        overlay.add_polygon(
+            selector,
+            &[
+                along(distance),
+                along(distance - gap * 1.5),
+                along(distance) + side * barb_length,
+            ],
+        );
+        distance -= gap * 2.0;
+    }
+    for _ in 0..barb.barbs {
+        overlay.add_line(
+            selector,
+            &[
+                along(distance),
+                along(distance + barb_length * 0.3) + side * barb_length,
+            ],
+        );
+        distance -= gap;
+    }
+    if barb.half_barb {
+        // A lone half barb is set off from the tip so it isn't mistaken for a full one.
+        if distance == length {
+            distance -= gap;
+        }
+        overlay.add_line(
+            selector,
+            &[
+                along(distance),
+                along(distance + barb_length * 0.15) + side * barb_length * 0.5,
+            ],
+        );
+    }
+}
+
+fn draw_tide_stations(overlay: &mut Overlay, app_state: &AppState) {
+    let selector = Selector::new().with_type("tide").with_class("station");
+    let size = overlay.pixels_to_global(6.0);
+    for station in app_state.tide_stations.iter() {
+        let center = station.position.to_global_space();
+        overlay.add_polygon(
+            &selector,
+            &[
+                center + vector(0.0, -size),
+                center + vector(size, size),
+                center + vector(-size, size),
+            ],
+        );
+    }
+}
+
+/// Draws the tidal streams at the time chosen in the HUD as arrows scaled and classed by their rate.
+fn draw_tidal_streams(overlay: &mut Overlay, app_state: &AppState) {
+    let time = unix_now() + app_state.ui.stream_offset as f64 * 3600.0;
+    let hours = match app_state
+        .tidal_streams
+        .hours_from_high_water(&app_state.tide_stations, time)
+    {
+        Some(hours) => hours,
+        None => return,
+    };
+
+    for point in app_state.tidal_streams.points() {
+        let (direction, rate) = point.stream_at(hours);
+        let class = if rate < 0.5 {
+            "weak"
+        } else if rate < 1.5 {
+            "moderate"
+        } else if rate < 2.5 {
+            "strong"
+        } else {
+            "verystrong"
+        };
+        let selector = Selector::new().with_type("stream").with_class(class);
+        overlay.add_arrow(
+            &selector,
+            &point.position().to_global_space(),
+            direction as f32,
+            10.0 + 20.0 * rate as f32,
+            8.0,
+        );
+    }
+}
+
+/// Draws the replayed track or the track recorded today.
+fn draw_track(overlay: &mut Overlay, app_state: &AppState) {
+    let (track, selector) = if let Some(replay) = &app_state.replay {
+        (
+            replay.track(),
+            Selector::new().with_type("track").with_class("replay"),
+        )
+    } else {
+        (
+            app_state.track_recorder.track(),
+            Selector::new().with_type("track"),
+        )
+    };
+
+    let points: Vec<_> = track
+        .points()
+        .iter()
+        .map(|p| p.position.to_global_space())
+        .collect();
+    overlay.add_line(&selector, &points);
+}
+
+/// Draws the computed weather route with the time every point is reached.
+fn draw_route(overlay: &mut Overlay, app_state: &AppState) {
+    let selector = Selector::new().with_type("route");
+    let radius = overlay.pixels_to_global(6.0);
+    for (point, class) in &[
+        (app_state.route_start, "start"),
+        (app_state.route_destination, "destination"),
+    ] {
+        if let Some(point) = point {
+            overlay.add_circle(
+                &selector.clone().with_class(*class),
+                &point.to_global_space(),
+                radius,
+            );
+        }
+    }
+
+    let route = match &app_state.route {
+        Some(route) => route,
+        None => return,
+    };
+    let points: Vec<Point> = route
+        .points
+        .iter()
+        .map(|p| p.position.to_global_space())
+        .collect();
+    overlay.add_line(&selector, &points);
+
+    let size = overlay.pixels_to_global(3.0);
+    for (point, global) in route.points.iter().zip(&points) {
+        overlay.add_polygon(
+            &selector.clone().with_class("step"),
+            &[
+                *global + vector(-size, -size),
+                *global + vector(size, -size),
+                *global + vector(size, size),
+                *global + vector(-size, size),
+            ],
+        );
+        let time = UtcDateTime::from_unix(point.time);
+        overlay.add_label(
+            &(*global + vector(0.0, -4.0 * size)),
+            format!("{:02} {:02}:{:02}", time.day, time.hour, time.minute),
+        );
+    }
+}
+
+/// Draws the active waypoint and its laylines.
+fn draw_waypoint(overlay: &mut Overlay, app_state: &AppState) {
+    let waypoint = match app_state.waypoint {
+        Some(waypoint) => waypoint.to_global_space(),
+        None => return,
+    };
+    let radius = overlay.pixels_to_global(8.0);
+    overlay.add_circle(&Selector::new().with_type("waypoint"), &waypoint, radius);
+
+    if let Some(laylines) = app_state.laylines() {
+        let selector = Selector::new().with_type("layline");
+        for (end, class) in &[(laylines.port, "port"), (laylines.starboard, "starboard")] {
+            overlay.add_line(
+                &selector.clone().with_class(*class),
+                &[waypoint, end.to_global_space()],
+            );
+        }
+    }
+}
+
+fn draw_anchor_watch(overlay: &mut Overlay, app_state: &AppState) {
+    let anchor = match app_state.anchor_watch.anchor() {
+        Some(anchor) => anchor,
+        None => return,
+    };
+
+    let mut selector = Selector::new().with_type("anchor");
+    if app_state.alarms.is_active(&AlarmKind::AnchorDrag) {
+        selector = selector.with_class("dragging");
+    }
+
+    let track: Vec<_> = app_state
+        .anchor_watch
+        .track()
+        .map(|p| p.to_global_space())
+        .collect();
+    overlay.add_line(&selector.clone().with_class("track"), &track);
+
+    let center = anchor.position.to_global_space();
+    let radius = anchor
+        .position
+        .destination(90.0, anchor.radius / METERS_PER_NM)
+        .to_global_space()
+        .x
+        - center.x;
+    overlay.add_circle(&selector.clone().with_class("circle"), &center, radius);
+
+    let size = overlay.pixels_to_global(5.0);
+    overlay.add_polygon(
+        &selector,
+        &[
+            center + vector(-size, -size),
+            center + vector(size, -size),
+            center + vector(size, size),
+            center + vector(-size, size),
+        ],
+    );
+}
+
+/// Draws the man overboard with the course back to it and shows the course in big letters.
+fn draw_mob(overlay: &mut Overlay, app_state: &AppState) {
+    let mob = match &app_state.mob {
+        Some(mob) => mob,
+        None => return,
+    };
+    let selector = Selector::new().with_type("mob");
+    let position = mob.position.to_global_space();
+    if let Some(own_position) = app_state.own_ship.position {
+        overlay.add_line(
+            &selector.clone().with_class("course"),
+            &[own_position.to_global_space(), position],
+        );
+    }
+    for pixels in &[8.0, 16.0] {
+        let radius = overlay.pixels_to_global(*pixels);
+        overlay.add_circle(&selector, &position, radius);
+    }
+    overlay.add_banner(mob.summary(
+        &app_state.own_ship,
+        app_state.display_variation(),
+        Instant::now(),
+    ));
+}
+
+/// Draws the guard zone ahead of us and circles the hazards within.
+fn draw_guard_zone(overlay: &mut Overlay, app_state: &AppState) {
+    if app_state.guard_zone.zone.distance <= 0.0 {
+        return;
+    }
+    let mut outline = match app_state.guard_zone.outline(&app_state.own_ship) {
+        Some(outline) => outline,
+        None => return,
+    };
+    outline.push(outline[0]);
+
+    let mut selector = Selector::new().with_type("guardzone");
+    if app_state.alarms.is_active(&AlarmKind::Hazard) {
+        selector = selector.with_class("alarm");
+    }
+    overlay.add_line(&selector, &outline);
+
+    let radius = overlay.pixels_to_global(10.0);
+    for hazard in app_state.guard_zone.hazards() {
+        overlay.add_circle(
+            &selector.clone().with_class("hazard"),
+            &hazard.position.to_global_space(),
+            radius,
+        );
+    }
+}
+
+fn draw_ais_targets(overlay: &mut Overlay, app_state: &AppState) {
+    for target in app_state.ais_targets.iter() {
+        let position: GeoPoint = match target.position.lat_lon() {
+            Some(lat_lon) => lat_lon.into(),
+            None => continue,
+        };
+
+        let mut selector = Selector::new()
+            .with_type("ais")
+            .with_class(match target.class {
+                AisTargetClass::ClassA => "classa",
+                AisTargetClass::ClassB => "classb",
+                AisTargetClass::AidToNavigation => "aton",
+                AisTargetClass::Unknown => "unknown",
+            });
+        if app_state.collision_monitor.is_dangerous(target.mmsi) {
+            selector = selector.with_class("dangerous");
+        }
+
+        let global_position = position.to_global_space();
+        if target.class == AisTargetClass::AidToNavigation {
+            let size = overlay.pixels_to_global(6.0);
+            overlay.add_polygon(
+                &selector,
+                &[
+                    global_position + vector(0.0, -size),
+                    global_position + vector(size, 0.0),
+                    global_position + vector(0.0, size),
+                    global_position + vector(-size, 0.0),
+                ],
+            );
+            continue;
+        }
+
+        draw_vector(
+            overlay,
+            &selector.clone().with_class("vector"),
+            &position,
+            target.position.cog,
+            target.position.sog,
+        );
+        let heading = target
+            .position
+            .heading
+            .map(|h| h as f64)
+            .or(target.position.cog)
+            .unwrap_or(0.0);
+        overlay.add_oriented_triangle(&selector, &global_position, heading as f32, 18.0, 10.0);
+    }
+}
+
+/// Draws the position predicted after the configured vector length as a line.
+fn draw_vector(
+    overlay: &mut Overlay,
+    selector: &Selector,
+    position: &GeoPoint,
+    cog: Option<f64>,
+    sog: Option<f64>,
+) {
+    if let (Some(cog), Some(sog)) = (cog, sog) {
+        let distance = sog * CONFIG.navigation.vector_length / 60.0;
+        if distance > 0.0 {
+            let predicted = position.destination(cog, distance);
+            overlay.add_line(
+                selector,
+                &[position.to_global_space(), predicted.to_global_space()],
+            );
+        }
+    }
+}
@@ -10,6 +10,7 @@ use winit::{dpi::LogicalSize, event_loop::EventLoop, window::Window};
 
 use crate::app_state::AppState;
 use crate::drawing::helpers::{load_glsl, ShaderStage};
+use crate::drawing::overlays::create_overlay;
 
 use crate::config::CONFIG;
 
@@ -36,6 +37,9 @@ pub struct Painter {
     temperature: crate::drawing::weather::Temperature,
 }
 
+/// The slot of the overlay in the tile transforms, it is always there even before any tile is loaded.
+const OVERLAY_TILE_SLOT: u32 = 0;
+
 impl Painter {
     /// Initializes the entire draw machinery.
     pub fn init(event_loop: &EventLoop<()>, width: u32, height: u32, app_state: &AppState) -> Self {
@@ -154,7 +158,7 @@ impl Painter {
             &device,
             &app_state.screen,
             app_state.zoom,
-            std::iter::empty::<&VisibleTile>(),
+            std::iter::empty(),
         );
 
         let blend_pipeline = Self::create_layer_render_pipeline(
@@ -201,16 +205,11 @@ impl Painter {
             GlyphBrushBuilder::using_font(font).build(&mut device, TextureFormat::Bgra8Unorm);
         let staging_belt = wgpu::util::StagingBelt::new(1024);
 
-        let mut temperature = crate::drawing::weather::Temperature::init(&mut device, &mut queue);
+        let temperature = crate::drawing::weather::Temperature::init(&mut device, &mut queue);
 
         let init_command_buf = init_encoder.finish();
         queue.submit(vec![init_command_buf]); // TODO this fix is bad
 
-        let width = 64 * 8;
-        let height = 64 * 8;
-
-        temperature.generate_texture(&mut device, &mut queue, width, height);
-
         Self {
             window,
             hidpi_factor: factor,
@@ -328,15 +327,18 @@ impl Painter {
     }
 
     /// Creates a new bind group containing all the relevant uniform buffers.
+    ///
+    /// All colors of the chart are multiplied with `brightness`.
     fn create_uniform_buffers(
         device: &Device,
         screen: &Screen,
+        brightness: f32,
         feature_collection: &FeatureCollection,
     ) -> Vec<(Buffer, usize)> {
         let canvas_size_len = 4 * 4 as usize;
         let canvas_size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            contents: as_byte_slice(&[screen.width as f32, screen.height as f32, 0.0, 0.0]),
+            contents: as_byte_slice(&[screen.width as f32, screen.height as f32, brightness, 0.0]),
             usage: BufferUsage::UNIFORM | BufferUsage::COPY_SRC,
         });
 
@@ -372,20 +374,20 @@ impl Painter {
     /// Creates a new transform buffer from the tile transforms.
     ///
     /// Ensures that the buffer has the size configured in the config, to match the size configured in the shader.
-    fn create_tile_transform_buffer<'a>(
+    fn create_tile_transform_buffer(
         device: &Device,
         screen: &Screen,
         z: f32,
-        visible_tiles: impl Iterator<Item = &'a VisibleTile>,
+        tiles: impl Iterator<Item = (TileId, u16)>,
     ) -> (Buffer, u64) {
         const TILE_DATA_SIZE: usize = 20;
         let tile_data_buffer_byte_size = TILE_DATA_SIZE * 4 * CONFIG.renderer.max_tiles;
         let mut data = vec![0f32; tile_data_buffer_byte_size];
 
         let mut i = 0;
-        for vt in visible_tiles {
-            let extent = vt.extent() as f32;
-            let matrix = screen.tile_to_global_space(z, &vt.tile_id());
+        for (tile_id, extent) in tiles {
+            let extent = extent as f32;
+            let matrix = screen.tile_to_global_space(z, &tile_id);
             for float in matrix.as_slice() {
                 data[i] = *float;
                 i += 1;
@@ -555,19 +557,31 @@ impl Painter {
         &mut self,
         encoder: &mut CommandEncoder,
         app_state: &AppState,
+        overlay: &Overlay,
         feature_collection: &FeatureCollection,
     ) {
         Self::copy_uniform_buffers(
             encoder,
-            &Self::create_uniform_buffers(&self.device, &app_state.screen, feature_collection),
+            &Self::create_uniform_buffers(
+                &self.device,
+                &app_state.screen,
+                app_state.theme().brightness,
+                feature_collection,
+            ),
             &self.uniform_buffer,
         );
 
+        // The overlay is drawn as if it was a tile in the first slot, the visible tiles follow it.
         self.tile_transform_buffer = Self::create_tile_transform_buffer(
             &self.device,
             &app_state.screen,
             app_state.zoom,
-            app_state.visible_tiles().values(),
+            std::iter::once((overlay.tile_id(), overlay.extent())).chain(
+                app_state
+                    .visible_tiles()
+                    .values()
+                    .map(|vt| (vt.tile_id(), vt.extent())),
+            ),
         );
     }
 
@@ -624,17 +638,26 @@ impl Painter {
             .device
             .create_command_encoder(&CommandEncoderDescriptor { label: None });
 
+        // Build the overlay first so all its features are part of the style buffer.
+        let overlay = create_overlay(app_state);
+        let overlay_gpu_tile = LoadedGPUTile::from_mesh(&self.device, overlay.mesh());
+        self.temperature.update_field(
+            &self.device,
+            &self.queue,
+            &app_state.weather,
+            app_state.weather_hours(),
+        );
+
         let feature_collection = app_state.feature_collection().read().unwrap().clone();
-        self.update_uniforms(&mut encoder, &app_state, &feature_collection);
+        self.update_uniforms(&mut encoder, &app_state, &overlay, &feature_collection);
         self.bind_group = Self::create_blend_bind_group(
             &self.device,
             &self.bind_group_layout,
             &self.uniform_buffer,
             &self.tile_transform_buffer,
         );
-        let num_tiles = app_state.visible_tiles().len();
         let features = feature_collection.get_features();
-        if !features.is_empty() && num_tiles > 0 {
+        if !features.is_empty() {
             if let Ok(frame) = self.swap_chain.get_current_frame() {
                 {
                     let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
@@ -741,7 +764,7 @@ impl Painter {
                                 &self.blend_pipeline,
                                 gpu_tile2,
                                 &feature_collection,
-                                i as u32,
+                                i as u32 + 1,
                             );
                         }
 
@@ -755,11 +778,46 @@ impl Painter {
 
                         // TODO put hwd.paint here?
                     }
+
+                    render_pass.set_scissor_rect(
+                        0,
+                        0,
+                        app_state.screen.width,
+                        app_state.screen.height,
+                    );
+                    overlay.paint(
+                        &mut render_pass,
+                        &self.blend_pipeline,
+                        &overlay_gpu_tile,
+                        &feature_collection,
+                        OVERLAY_TILE_SLOT,
+                    );
+                }
+
+                if CONFIG.renderer.msaa_samples > 1 {
+                    self.temperature.paint(
+                        &self.queue,
+                        &mut encoder,
+                        &self.multisampled_framebuffer,
+                        Some(&frame.output.view),
+                        &app_state.screen,
+                        app_state.zoom,
+                    );
+                } else {
+                    self.temperature.paint(
+                        &self.queue,
+                        &mut encoder,
+                        &frame.output.view,
+                        None,
+                        &app_state.screen,
+                        app_state.zoom,
+                    );
                 }
 
                 for (_i, vt) in app_state.visible_tiles().values().enumerate() {
                     vt.queue_text(&mut self.glyph_brush, &app_state.screen, app_state.zoom);
                 }
+                overlay.queue_text(&mut self.glyph_brush, &app_state.screen, app_state.zoom);
 
                 let _ = self.glyph_brush.draw_queued(
                     &self.device,
@@ -770,8 +828,6 @@ impl Painter {
                     app_state.screen.height,
                 );
 
-                // self.temperature.paint(&mut encoder, &frame.view);
-
                 hud.paint(app_state, &self.window, &mut self.device, &self.queue);
                 self.queue.submit(vec![encoder.finish()]);
             }
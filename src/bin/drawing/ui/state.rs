@@ -1,4 +1,28 @@
 #[derive(Default)]
 pub struct UIState {
     pub input: String,
+    /// The swing radius in meters used when dropping the anchor.
+    pub anchor_radius: f32,
+    /// Whether the next click on the chart drops the anchor.
+    pub pick_anchor: bool,
+    /// The track log to replay.
+    pub replay_file: String,
+    /// The time the tidal streams are shown for in hours from now.
+    pub stream_offset: f32,
+    /// Whether the tidal streams are animated.
+    pub animate_streams: bool,
+    /// Whether the wind barbs are drawn.
+    pub show_wind: bool,
+    /// Whether the isobars are drawn.
+    pub show_isobars: bool,
+    /// The index of the displayed forecast time step of the weather layers.
+    pub weather_step: usize,
+    /// Whether the next click on the chart sets the start of the weather routing.
+    pub pick_route_start: bool,
+    /// Whether the next click on the chart sets the destination of the weather routing.
+    pub pick_route_destination: bool,
+    /// Whether the next click on the chart sets the active waypoint.
+    pub pick_waypoint: bool,
+    /// Whether bearings are shown magnetic instead of true.
+    pub magnetic_bearings: bool,
 }
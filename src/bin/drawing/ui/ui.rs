@@ -2,14 +2,17 @@ use crate::app_state::AppState;
 use crate::app_state::EditableObject;
 use crate::*;
 use imgui::*;
+use std::time::Instant;
 
-use crate::config::CONFIG;
+use crate::config::{HudColors, CONFIG};
 
 pub struct HUD {
     platform: imgui_winit_support::WinitPlatform,
     imgui: imgui::Context,
     renderer: imgui_wgpu::Renderer,
     ruda: FontId,
+    /// The theme the imgui style currently has the colors of.
+    theme: Option<usize>,
 }
 
 impl HUD {
@@ -65,6 +68,7 @@ impl HUD {
             imgui,
             renderer,
             ruda,
+            theme: None,
         }
     }
 
@@ -79,6 +83,11 @@ impl HUD {
             .prepare_frame(self.imgui.io_mut(), window) // step 4
             .expect("Failed to prepare frame");
         self.imgui.io_mut().delta_time = app_state.stats.get_last_delta();
+        if self.theme != Some(app_state.theme) {
+            let theme = app_state.theme();
+            set_hud_colors(self.imgui.style_mut(), theme.hud_colors, theme.brightness);
+            self.theme = Some(app_state.theme);
+        }
         let ui = self.imgui.frame();
         let ruda = ui.push_font(self.ruda);
         {
@@ -92,6 +101,23 @@ impl HUD {
                         .build(&ui);
                 });
 
+                ui.menu(im_str!("Theme"), true, || {
+                    for (index, theme) in CONFIG.renderer.themes.iter().enumerate() {
+                        if imgui::MenuItem::new(&im_str!("{}", theme.name))
+                            .selected(index == app_state.theme)
+                            .build(&ui)
+                        {
+                            app_state.set_theme(index);
+                        }
+                    }
+                    if imgui::MenuItem::new(im_str!("Next theme"))
+                        .shortcut(im_str!("F8"))
+                        .build(&ui)
+                    {
+                        app_state.next_theme();
+                    }
+                });
+
                 ui.text(&im_str!(
                     "Mouse Position: ({:.1},{:.1})",
                     mouse_pos[0],
@@ -103,6 +129,20 @@ impl HUD {
                     app_state.stats.get_average(),
                     app_state.zoom
                 ));
+
+                ui.checkbox(im_str!("Magnetic"), &mut app_state.ui.magnetic_bearings);
+                if let Some(heading) = app_state.own_ship.heading {
+                    ui.text(&im_str!("HDG {}", app_state.format_bearing(heading)));
+                }
+                if let (Some(position), Some(cursor)) =
+                    (app_state.own_ship.position, app_state.cursor)
+                {
+                    ui.text(&im_str!(
+                        "Cursor {} {:.2}nm",
+                        app_state.format_bearing(position.bearing_to(&cursor)),
+                        position.distance_to(&cursor)
+                    ));
+                }
             });
 
             // Draw main window.
@@ -161,10 +201,41 @@ impl HUD {
 
                         ui.text(im_str!("{:#?}", object.tags()));
 
+                        ui.separator();
+                        ui.text(im_str!("Computed style"));
+                        ui.separator();
+
+                        let selector = object
+                            .selector()
+                            .clone()
+                            .with_any("zoom", (app_state.zoom.floor() as u32).to_string());
+                        let style = app_state.css_cache.compute_style(&selector);
+                        for (property, computed) in &style.values {
+                            ui.text(im_str!(
+                                "{}: {:?}{} from {}",
+                                property,
+                                computed.value,
+                                if computed.important {
+                                    " !important"
+                                } else {
+                                    ""
+                                },
+                                app_state.css_cache.rules[computed.rule].selector
+                            ));
+                        }
+
                         ui.separator();
                         ui.text(im_str!("Applying rules"));
                         ui.separator();
 
+                        if ui.button(im_str!("Save stylesheet"), [120.0, 25.0]) {
+                            app_state.css_cache.save();
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(im_str!("Revert"), [120.0, 25.0]) {
+                            app_state.css_cache.revert();
+                        }
+
                         let mut rules = app_state
                             .css_cache
                             .get_matching_rules_mut(&object.selector());
@@ -226,6 +297,427 @@ impl HUD {
                         }
                     }
                 });
+            let window = imgui::Window::new(im_str!("AIS Targets"));
+            window
+                .position([960.0, 60.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 300.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    ui.text(im_str!("{} targets", app_state.ais_targets.len()));
+                    ui.separator();
+                    for target in app_state.ais_targets.iter() {
+                        ui.text(im_str!(
+                            "{:<20} {:?} {:>5.1}kn {} {}s",
+                            target.display_name(),
+                            target.class,
+                            target.position.sog.unwrap_or(0.0),
+                            app_state.format_bearing(target.position.cog.unwrap_or(0.0)),
+                            target.last_seen.elapsed().as_secs()
+                        ));
+                        if let Some(cpa) = app_state.collision_monitor.get(target.mmsi) {
+                            let text =
+                                im_str!("    CPA {:.2}nm TCPA {:.1}min", cpa.distance, cpa.tcpa);
+                            if app_state.collision_monitor.is_dangerous(target.mmsi) {
+                                ui.text_colored([1.0, 0.0, 0.0, 1.0], text);
+                            } else {
+                                ui.text(text);
+                            }
+                        }
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Anchor Watch"));
+            window
+                .position([520.0, 180.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 150.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    if imgui::Slider::new(im_str!("Radius [m]"))
+                        .range(10.0..=500.0)
+                        .build(&ui, &mut app_state.ui.anchor_radius)
+                    {
+                        let radius = app_state.ui.anchor_radius as f64;
+                        app_state.anchor_watch.set_radius(radius);
+                    }
+
+                    if let Some(anchor) = app_state.anchor_watch.anchor() {
+                        ui.text(im_str!(
+                            "Anchor at {:.5} {:.5}",
+                            anchor.position.lat,
+                            anchor.position.lon
+                        ));
+                        if let Some(distance) = app_state.anchor_watch.distance() {
+                            ui.text(im_str!("Distance {:.0}m", distance));
+                        }
+                        if ui.button(im_str!("Weigh anchor"), [120.0, 25.0]) {
+                            app_state.anchor_watch.weigh_anchor(&mut app_state.alarms);
+                        }
+                    } else {
+                        if ui.button(im_str!("Drop at fix"), [120.0, 25.0]) {
+                            app_state.drop_anchor_at_fix();
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(im_str!("Pick on chart"), [120.0, 25.0]) {
+                            app_state.ui.pick_anchor = true;
+                        }
+                        if app_state.ui.pick_anchor {
+                            ui.text(im_str!("Click on the chart to drop the anchor."));
+                        }
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Tides"));
+            window
+                .position([960.0, 540.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 260.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    let center = GeoPoint::from_global_space(&app_state.screen.center);
+                    if let Some(station) = app_state.tide_stations.nearest(&center) {
+                        let now = unix_now();
+                        let day_start = (now / 86_400.0).floor() * 86_400.0;
+                        let day_end = day_start + 86_400.0;
+
+                        ui.text(im_str!(
+                            "{} ({:.0}nm): {:.2}m",
+                            station.name,
+                            station.position.distance_to(&center),
+                            station.height(now)
+                        ));
+
+                        let heights: Vec<f32> = station
+                            .heights(day_start, day_end, 600.0)
+                            .into_iter()
+                            .map(|h| h as f32)
+                            .collect();
+                        imgui::PlotLines::new(&ui, im_str!("Height [m]"), &heights)
+                            .graph_size([300.0, 80.0])
+                            .build();
+
+                        for extreme in station.extremes(day_start, day_end) {
+                            let time = UtcDateTime::from_unix(extreme.time);
+                            ui.text(im_str!(
+                                "{} {:02}:{:02} UTC {:.2}m",
+                                if extreme.high { "HW" } else { "LW" },
+                                time.hour,
+                                time.minute,
+                                extreme.height
+                            ));
+                        }
+                    } else {
+                        ui.text(im_str!("No tide stations loaded."));
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Tidal Streams"));
+            window
+                .position([960.0, 820.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 120.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    if app_state.ui.animate_streams {
+                        // One hour of tide per second.
+                        app_state.ui.stream_offset += app_state.stats.get_last_delta();
+                        if app_state.ui.stream_offset > 12.0 {
+                            app_state.ui.stream_offset = -12.0;
+                        }
+                    }
+                    imgui::Slider::new(im_str!("Hours from now"))
+                        .range(-12.0..=12.0)
+                        .build(&ui, &mut app_state.ui.stream_offset);
+                    ui.checkbox(im_str!("Animate"), &mut app_state.ui.animate_streams);
+
+                    let time = unix_now() + app_state.ui.stream_offset as f64 * 3600.0;
+                    match app_state
+                        .tidal_streams
+                        .hours_from_high_water(&app_state.tide_stations, time)
+                    {
+                        Some(hours) => ui.text(im_str!(
+                            "HW {} {:+.1}h",
+                            app_state.tidal_streams.reference_port,
+                            hours
+                        )),
+                        None => ui.text(im_str!(
+                            "No tide prediction for {}.",
+                            app_state.tidal_streams.reference_port
+                        )),
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Weather"));
+            window
+                .position([960.0, 950.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 140.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    ui.checkbox(im_str!("Show wind"), &mut app_state.ui.show_wind);
+                    ui.checkbox(im_str!("Show isobars"), &mut app_state.ui.show_isobars);
+                    let hours = app_state.weather.forecast_hours();
+                    if hours.is_empty() {
+                        ui.text(im_str!("No weather forecast loaded."));
+                        return;
+                    }
+
+                    let mut step = app_state.ui.weather_step.min(hours.len() - 1) as i32;
+                    imgui::Slider::new(im_str!("Time step"))
+                        .range(0..=hours.len() as i32 - 1)
+                        .build(&ui, &mut step);
+                    app_state.ui.weather_step = step as usize;
+
+                    if let Some(field) = app_state.weather.fields().first() {
+                        ui.text(im_str!(
+                            "{} +{}h",
+                            UtcDateTime::from_unix(field.reference_time).to_iso8601(),
+                            hours[step as usize]
+                        ));
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Routing"));
+            window
+                .position([520.0, 710.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 160.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    for (label, point) in &[
+                        ("Start", app_state.route_start),
+                        ("Destination", app_state.route_destination),
+                    ] {
+                        match point {
+                            Some(point) => {
+                                ui.text(im_str!("{} {:.5} {:.5}", label, point.lat, point.lon))
+                            }
+                            None => ui.text(im_str!("{} not set", label)),
+                        }
+                    }
+
+                    if ui.button(im_str!("Start at fix"), [120.0, 25.0]) {
+                        app_state.route_start = app_state.own_ship.position;
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Pick start"), [120.0, 25.0]) {
+                        app_state.ui.pick_route_start = true;
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Pick destination"), [120.0, 25.0]) {
+                        app_state.ui.pick_route_destination = true;
+                    }
+                    if app_state.ui.pick_route_start || app_state.ui.pick_route_destination {
+                        ui.text(im_str!("Click on the chart to set the point."));
+                    }
+
+                    if ui.button(im_str!("Compute route"), [120.0, 25.0]) {
+                        app_state.compute_route();
+                    }
+                    if let Some(route) = &app_state.route {
+                        ui.text(im_str!(
+                            "{:.1}nm, arrival {} after {:.1}h",
+                            route.distance(),
+                            UtcDateTime::from_unix(route.arrival()).to_iso8601(),
+                            (route.arrival() - route.departure()) / 3600.0
+                        ));
+                        for point in &route.points[1..] {
+                            let time = UtcDateTime::from_unix(point.time);
+                            ui.text(im_str!(
+                                "{:02} {:02}:{:02} {} {:.1}kn",
+                                time.day,
+                                time.hour,
+                                time.minute,
+                                app_state.format_bearing(point.heading.unwrap_or(0.0)),
+                                point.speed
+                            ));
+                        }
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("MOB"));
+            window
+                .position([960.0, 1100.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 90.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || match app_state.mob {
+                    Some(mob) => {
+                        ui.text(im_str!(
+                            "{}",
+                            mob.summary(
+                                &app_state.own_ship,
+                                app_state.display_variation(),
+                                Instant::now()
+                            )
+                        ));
+                        if ui.button(im_str!("Recovered (Shift+F9)"), [160.0, 25.0]) {
+                            app_state.recover_mob();
+                        }
+                    }
+                    None => {
+                        if ui.button(im_str!("Man overboard (F9)"), [160.0, 25.0]) {
+                            app_state.man_overboard();
+                        }
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Waypoint"));
+            window
+                .position([520.0, 880.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 130.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    match app_state.waypoint {
+                        Some(waypoint) => {
+                            ui.text(im_str!("Waypoint {:.5} {:.5}", waypoint.lat, waypoint.lon));
+                            if let Some(position) = app_state.own_ship.position {
+                                ui.text(im_str!(
+                                    "{:.2}nm at {}",
+                                    position.distance_to(&waypoint),
+                                    app_state.format_bearing(position.bearing_to(&waypoint))
+                                ));
+                            }
+                        }
+                        None => ui.text(im_str!("No active waypoint")),
+                    }
+
+                    if ui.button(im_str!("Pick on chart"), [120.0, 25.0]) {
+                        app_state.ui.pick_waypoint = true;
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Route destination"), [120.0, 25.0]) {
+                        app_state.waypoint = app_state.route_destination;
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Clear"), [120.0, 25.0]) {
+                        app_state.waypoint = None;
+                    }
+                    if app_state.ui.pick_waypoint {
+                        ui.text(im_str!("Click on the chart to set the waypoint."));
+                    }
+
+                    if let Some(wind) = app_state.wind_smoother.average() {
+                        ui.text(im_str!(
+                            "Averaged TWD {} TWS {:.1}kn",
+                            app_state.format_bearing(wind.angle),
+                            wind.speed
+                        ));
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Performance"));
+            window
+                .position([520.0, 540.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 160.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    let polar = match &app_state.polar {
+                        Some(polar) => polar,
+                        None => {
+                            ui.text(im_str!("No polar loaded."));
+                            return;
+                        }
+                    };
+                    let wind = match app_state.own_ship.true_wind_angle() {
+                        Some(wind) => wind,
+                        None => {
+                            ui.text(im_str!("No true wind."));
+                            return;
+                        }
+                    };
+
+                    ui.text(im_str!("TWA {:.0}° TWS {:.1}kn", wind.angle, wind.speed));
+                    let (upwind_angle, upwind_speed) = polar.best_upwind(wind.speed);
+                    let (downwind_angle, downwind_speed) = polar.best_downwind(wind.speed);
+                    ui.text(im_str!(
+                        "Best VMG upwind {:.0}° at {:.1}kn, downwind {:.0}° at {:.1}kn",
+                        upwind_angle,
+                        upwind_speed,
+                        downwind_angle,
+                        downwind_speed
+                    ));
+
+                    if let Some(speed) = app_state.own_ship.stw_or_sog() {
+                        let performance = polar.performance(&wind, speed);
+                        ui.text(im_str!(
+                            "Polar {:.1}kn, sailing at {:.0}%",
+                            performance.polar_speed,
+                            performance.percentage
+                        ));
+                        ui.text(im_str!(
+                            "Target {:.1}kn at {:.0}°",
+                            performance.target_speed,
+                            performance.target_angle
+                        ));
+                    }
+                });
+
+            let window = imgui::Window::new(im_str!("Track"));
+            window
+                .position([520.0, 350.0], imgui::Condition::FirstUseEver)
+                .size([400.0, 180.0], imgui::Condition::FirstUseEver)
+                .build(&ui, || {
+                    if let Some(replay) = &mut app_state.replay {
+                        if let Some((start, end)) = replay.track().time_range() {
+                            ui.text(im_str!(
+                                "Replaying {}",
+                                UtcDateTime::from_unix(replay.time()).to_iso8601()
+                            ));
+                            // Slide relative to the start so the f32 keeps a precision of seconds.
+                            let mut offset = (replay.time() - start) as f32;
+                            if imgui::Slider::new(im_str!("Time [s]"))
+                                .range(0.0..=(end - start) as f32)
+                                .build(&ui, &mut offset)
+                            {
+                                replay.set_time(start + offset as f64);
+                            }
+                        }
+                        let label = if replay.playing {
+                            im_str!("Pause")
+                        } else {
+                            im_str!("Play")
+                        };
+                        if ui.button(label, [100.0, 25.0]) {
+                            replay.playing = !replay.playing;
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(im_str!("Stop"), [100.0, 25.0]) {
+                            app_state.stop_replay();
+                        }
+                    } else {
+                        ui.text(im_str!(
+                            "{} points in {}",
+                            app_state.track_recorder.track().len(),
+                            app_state.track_recorder.log_path().display()
+                        ));
+                        if ui.button(im_str!("Export GPX"), [100.0, 25.0]) {
+                            app_state.track_recorder.export_gpx();
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(im_str!("Export CSV"), [100.0, 25.0]) {
+                            app_state.track_recorder.export_csv();
+                        }
+
+                        let mut value = ImString::with_capacity(200);
+                        value.push_str(&app_state.ui.replay_file);
+                        imgui::InputText::new(&ui, im_str!("Track log"), &mut value).build();
+                        app_state.ui.replay_file = value.to_string();
+                        if ui.button(im_str!("Replay"), [100.0, 25.0]) {
+                            let path = app_state.ui.replay_file.clone();
+                            app_state.start_replay(&path);
+                        }
+                    }
+                });
+
+            if !app_state.alarms.is_empty() {
+                let window = imgui::Window::new(im_str!("Alarms"));
+                window
+                    .position([960.0, 380.0], imgui::Condition::FirstUseEver)
+                    .size([400.0, 150.0], imgui::Condition::FirstUseEver)
+                    .build(&ui, || {
+                        for alarm in app_state.alarms.iter() {
+                            let color = if alarm.acknowledged {
+                                [1.0, 1.0, 1.0, 1.0]
+                            } else {
+                                [1.0, 0.0, 0.0, 1.0]
+                            };
+                            ui.text_colored(
+                                color,
+                                im_str!("{} ({}s)", alarm.message, alarm.since.elapsed().as_secs()),
+                            );
+                        }
+                        if ui.button(im_str!("Acknowledge"), [0.0, 0.0]) {
+                            app_state.alarms.acknowledge_all();
+                        }
+                    });
+            }
+
             ruda.pop(&ui);
             // ui.show_demo_window(&mut false);
         }
@@ -250,28 +742,23 @@ impl HUD {
 }
 
 fn add_color_picker(ui: &Ui, rule: &mut Rule, attribute: &str) {
+    if show_interpolation(ui, rule, attribute) {
+        return;
+    }
     let default_color = CSSValue::Color(Color::TRANSPARENT);
     let color = if let Some(color) = rule.kvs.get(attribute) {
         color
     } else {
         &default_color
     };
-    let color = match color {
-        CSSValue::String(string) => match &string[..] {
-            "red" => Color::RED,
-            "green" => Color::GREEN,
-            "blue" => Color::BLUE,
-            "black" => Color::BLACK,
-            "white" => Color::WHITE,
-            _ => Color::TRANSPARENT,
-        },
-        CSSValue::Color(color) => color.clone(),
-        _ => Color::TRANSPARENT, // This should never happen, but transparent should be a decent fallback
-    };
+    // This should never fail, but transparent should be a decent fallback.
+    let color = color.to_color().unwrap_or(Color::TRANSPARENT);
     let mut color = [color.r, color.g, color.b, color.a];
     let label = im_str!("{}", attribute);
     let cp = ColorEdit::new(&label, EditableColor::Float4(&mut color));
-    cp.build(&ui);
+    if !cp.build(&ui) {
+        return;
+    }
 
     rule.kvs.insert(
         attribute.to_string(),
@@ -285,6 +772,9 @@ fn add_color_picker(ui: &Ui, rule: &mut Rule, attribute: &str) {
 }
 
 fn add_slider_float(ui: &Ui, rule: &mut Rule, attribute: &str) {
+    if show_interpolation(ui, rule, attribute) {
+        return;
+    }
     let default_number = CSSValue::Number(Number::Px(0.0));
     let value = if let Some(value) = rule.kvs.get(attribute) {
         value
@@ -300,14 +790,42 @@ fn add_slider_float(ui: &Ui, rule: &mut Rule, attribute: &str) {
     };
 
     let label = im_str!("{}", attribute);
-    imgui::Slider::new(&label)
+    if !imgui::Slider::new(&label)
         .range(0.0..=10.0)
-        .build(&ui, &mut value);
+        .build(&ui, &mut value)
+    {
+        return;
+    }
 
     rule.kvs
         .insert(attribute.to_string(), CSSValue::Number(Number::Px(value)));
 }
 
+/// Shows interpolated values and variables as text since the editors only handle plain values.
+///
+/// Returns whether the value is such a value.
+fn show_interpolation(ui: &Ui, rule: &Rule, attribute: &str) -> bool {
+    match rule.kvs.get(attribute) {
+        Some(CSSValue::Interpolate(interpolation)) => {
+            ui.text(im_str!(
+                "{}: interpolated over zoom {:?}",
+                attribute,
+                interpolation
+                    .stops
+                    .iter()
+                    .map(|(zoom, _)| *zoom)
+                    .collect::<Vec<_>>()
+            ));
+            true
+        }
+        Some(value @ CSSValue::Var(..)) => {
+            ui.text(im_str!("{}: {}", attribute, value));
+            true
+        }
+        _ => false,
+    }
+}
+
 fn add_display_none(ui: &Ui, rule: &mut Rule) {
     let attribute = "display";
     let mut value = if let Some(value) = rule.kvs.get(attribute) {
@@ -320,7 +838,9 @@ fn add_display_none(ui: &Ui, rule: &mut Rule) {
     };
 
     let label = im_str!("{}", attribute);
-    ui.checkbox(&label, &mut value);
+    if !ui.checkbox(&label, &mut value) {
+        return;
+    }
 
     if !value {
         rule.kvs
@@ -330,6 +850,31 @@ fn add_display_none(ui: &Ui, rule: &mut Rule) {
     }
 }
 
+/// Colors the HUD to match the theme of the chart and dims it like the chart by `brightness`.
+fn set_hud_colors(style: &mut Style, hud_colors: HudColors, brightness: f32) {
+    match hud_colors {
+        HudColors::Light => {
+            style.use_light_colors();
+        }
+        HudColors::Dark => {
+            style.use_dark_colors();
+        }
+        HudColors::Red => {
+            // Keep the contrast of the dark style but map every color to red of the same brightness.
+            style.use_dark_colors();
+            for color in style.colors.iter_mut() {
+                let luminance = 0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2];
+                *color = [luminance.min(1.0), 0.0, 0.0, color[3]];
+            }
+        }
+    }
+    for color in style.colors.iter_mut() {
+        for channel in &mut color[..3] {
+            *channel *= brightness;
+        }
+    }
+}
+
 fn add_header_separator(ui: &Ui, title: impl Into<ImString>) -> bool {
     CollapsingHeader::new(&title.into())
         .default_open(true)
@@ -1,22 +1,31 @@
 use crossbeam_channel::{unbounded, TryRecvError};
 use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use wgpu::{
-    util::DeviceExt, BindGroup, BindGroupLayout, Device, RenderPipeline, Sampler, ShaderModule,
-    Texture,
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, Sampler,
+    ShaderModule, Texture,
 };
 
 use crate::drawing::helpers::{load_glsl, ShaderStage};
 
 use crate::config::CONFIG;
-use osm::as_byte_slice;
+use osm::{as_byte_slice, GribFile, GribParameter, Screen};
+
+/// The width and height of the texture the GRIB field is resampled to.
+const TEXTURE_SIZE: u32 = 64 * 8;
 
 pub struct Temperature {
     pipeline: RenderPipeline,
     bind_group_layout: BindGroupLayout,
-    _bind_group: BindGroup,
+    bind_group: BindGroup,
     rx: crossbeam_channel::Receiver<std::result::Result<notify::event::Event, notify::Error>>,
     _watcher: RecommendedWatcher,
     texture: Texture,
+    /// The visible part of the Web Mercator world as `[left, top, right, bottom]` in global space.
+    viewport_buffer: Buffer,
+    /// Whether a GRIB field was loaded into the texture.
+    has_field: bool,
+    /// The forecast time the texture was last loaded for.
+    hours: Option<f64>,
 }
 
 impl Temperature {
@@ -84,6 +93,15 @@ impl Temperature {
                     ty: wgpu::BindingType::Sampler { comparison: false },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::UniformBuffer {
+                        dynamic: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -96,7 +114,8 @@ impl Temperature {
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: None,
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            // The world wraps around along the longitude.
+            address_mode_u: wgpu::AddressMode::Repeat,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
@@ -108,8 +127,8 @@ impl Temperature {
             anisotropy_clamp: None,
         });
 
-        let width = 64 * 8;
-        let height = 64 * 8;
+        let width = TEXTURE_SIZE;
+        let height = TEXTURE_SIZE;
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: None,
@@ -128,18 +147,33 @@ impl Temperature {
                 | wgpu::TextureUsage::COPY_DST,
         });
 
-        let bind_group = Self::create_bind_group(&device, &bind_group_layout, &texture, &sampler);
+        let viewport_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: as_byte_slice(&[0.0f32, 0.0, 1.0, 1.0]),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let bind_group = Self::create_bind_group(
+            &device,
+            &bind_group_layout,
+            &texture,
+            &sampler,
+            &viewport_buffer,
+        );
 
         let init_command_buf = init_encoder.finish();
         queue.submit(vec![init_command_buf]);
 
         Self {
             bind_group_layout,
-            _bind_group: bind_group,
+            bind_group,
             _watcher: watcher,
             rx,
             pipeline,
             texture,
+            viewport_buffer,
+            has_field: false,
+            hours: None,
         }
     }
 
@@ -205,6 +239,7 @@ impl Temperature {
         bind_group_layout: &BindGroupLayout,
         texture: &Texture,
         sampler: &Sampler,
+        viewport_buffer: &Buffer,
     ) -> BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
@@ -220,29 +255,42 @@ impl Temperature {
                     binding: 1,
                     resource: wgpu::BindingResource::Sampler(sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer(viewport_buffer.slice(..)),
+                },
             ],
         })
     }
 
-    pub fn generate_texture(
+    /// Loads the field of the weather forecast at `hours` into the texture if another time was selected.
+    ///
+    /// The wind and the isobars are drawn from the same file and time.
+    pub fn update_field(
         &mut self,
-        device: &mut wgpu::Device,
-        queue: &mut wgpu::Queue,
-        width: u32,
-        height: u32,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        weather: &GribFile,
+        hours: Option<f64>,
     ) {
-        // Generate data.
-        let mut data = Vec::with_capacity((width * height) as usize);
-        for y in 0..width {
-            for x in 0..height {
-                data.push(f32::sin(x as f32 * 0.5) * f32::cos(y as f32 * 0.5));
-            }
+        if hours == self.hours {
+            return;
+        }
+        self.hours = hours;
+        let field = hours.and_then(|hours| Self::load_field(weather, hours));
+        self.has_field = field.is_some();
+        if let Some(data) = field {
+            self.generate_texture(device, queue, &data);
         }
+    }
+
+    fn generate_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[f32]) {
+        let (width, height) = (TEXTURE_SIZE, TEXTURE_SIZE);
 
         // Place in wgpu buffer
         let buffer = &device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            contents: as_byte_slice(data.as_slice()),
+            contents: as_byte_slice(data),
             usage: wgpu::BufferUsage::COPY_SRC,
         });
 
@@ -275,6 +323,48 @@ impl Temperature {
         queue.submit(vec![encoder.finish()]);
     }
 
+    /// Loads the configured GRIB field resampled to the texture and normalized to [-1, 1].
+    ///
+    /// Texels without data are set to -1.
+    fn load_field(file: &GribFile, hours: f64) -> Option<Vec<f32>> {
+        let config = &CONFIG.renderer.temperature;
+        let parameter = match GribParameter::from_name(&config.parameter) {
+            Some(parameter) => parameter,
+            None => {
+                log::warn!("The GRIB parameter {} is not supported.", config.parameter);
+                return None;
+            }
+        };
+        let field = match file.find(parameter, hours) {
+            Some(field) => field,
+            None => {
+                log::warn!("The GRIB file contains no {} field.", parameter.name());
+                return None;
+            }
+        };
+        log::info!(
+            "Displaying {} at +{}h.",
+            parameter.name(),
+            field.forecast_hours
+        );
+
+        let (min, max) = field.range().unwrap_or((0.0, 1.0));
+        let span = (max - min).max(f32::EPSILON);
+        Some(
+            field
+                .resample_to_mercator(TEXTURE_SIZE, TEXTURE_SIZE)
+                .into_iter()
+                .map(|v| {
+                    if v.is_nan() {
+                        -1.0
+                    } else {
+                        (v - min) / span * 2.0 - 1.0
+                    }
+                })
+                .collect(),
+        )
+    }
+
     /// Loads a shader module from a GLSL vertex and fragment shader each.
     fn load_shader(
         device: &Device,
@@ -336,25 +426,44 @@ impl Temperature {
         }
     }
 
-    pub fn _paint(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+    /// Draws the weather layer on top of the chart.
+    ///
+    /// Nothing is drawn if no GRIB field is loaded.
+    pub fn paint(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        attachment: &wgpu::TextureView,
+        resolve_target: Option<&wgpu::TextureView>,
+        screen: &Screen,
+        zoom: f32,
+    ) {
+        if !self.has_field {
+            return;
+        }
+
+        let top_left = screen.screen_to_global(zoom, (0.0, 0.0));
+        let bottom_right =
+            screen.screen_to_global(zoom, (screen.width as f32, screen.height as f32));
+        queue.write_buffer(
+            &self.viewport_buffer,
+            0,
+            as_byte_slice(&[top_left.x, top_left.y, bottom_right.x, bottom_right.y]),
+        );
+
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: view,
-                resolve_target: None,
+                attachment,
+                resolve_target,
                 ops: wgpu::Operations::<wgpu::Color> {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
+                    load: wgpu::LoadOp::Load,
                     store: true,
                 },
             }],
             depth_stencil_attachment: None,
         });
         rpass.set_pipeline(&self.pipeline);
-        rpass.set_bind_group(0, &self._bind_group, &[]);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
         rpass.draw(0..6, 0..1);
     }
 }
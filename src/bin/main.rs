@@ -9,13 +9,33 @@ use osm::*;
 use winit::{
     dpi::PhysicalPosition,
     event::{
-        ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
-        WindowEvent,
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
     },
     event_loop::ControlFlow,
 };
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("check-style") {
+        match args.get(2) {
+            Some(path) => std::process::exit(check_style(path)),
+            None => {
+                eprintln!("Usage: sailor check-style <stylesheet>");
+                std::process::exit(2);
+            }
+        }
+    }
+    if args.get(1).map(String::as_str) == Some("import-style") {
+        match args.get(2) {
+            Some(path) => std::process::exit(import_style(path)),
+            None => {
+                eprintln!("Usage: sailor import-style <style.json> > <stylesheet>");
+                std::process::exit(2);
+            }
+        }
+    }
+
     log::set_max_level(CONFIG.general.log_level.to_level_filter());
     pretty_env_logger::init();
 
@@ -33,13 +53,15 @@ fn main() {
         .expect("No monitors found")
         .scale_factor();
 
-    let mut app_state =
-        app_state::AppState::new("config/style.css", zurich, width, height, z, hdpi_factor);
+    let mut app_state = app_state::AppState::new(zurich, width, height, z, hdpi_factor);
 
     let mut painter = drawing::Painter::init(&event_loop, width, height, &app_state);
     let mut hud = drawing::ui::HUD::new(&painter.window, &mut painter.device, &mut painter.queue);
 
     let mut mouse_down = false;
+    let mut modifiers = ModifiersState::empty();
+    // Keys which are held down, so the auto repeat of the OS doesn't trigger them again.
+    let mut pressed_keys = std::collections::HashSet::new();
     let mut last_pos = winit::dpi::LogicalPosition::new(0.0, 0.0);
 
     event_loop.run(move |event, _, control_flow| {
@@ -63,10 +85,30 @@ fn main() {
                     input:
                         KeyboardInput {
                             virtual_keycode: Some(keycode),
+                            state,
                             ..
                         },
                     ..
                 } => {
+                    let first_press = match state {
+                        ElementState::Pressed => pressed_keys.insert(keycode),
+                        ElementState::Released => {
+                            pressed_keys.remove(&keycode);
+                            false
+                        }
+                    };
+                    // MOB has to work no matter where the keyboard focus is.
+                    if keycode == VirtualKeyCode::F9 && first_press {
+                        if modifiers.shift() {
+                            app_state.recover_mob();
+                        } else {
+                            app_state.man_overboard();
+                        }
+                    }
+                    // Same for the theme, nobody wants to hunt for a menu while the white chart blinds them.
+                    if keycode == VirtualKeyCode::F8 && first_press {
+                        app_state.next_theme();
+                    }
                     if route_keyboard {
                         match keycode {
                             VirtualKeyCode::Escape => {
@@ -77,6 +119,7 @@ fn main() {
                         }
                     }
                 }
+                WindowEvent::ModifiersChanged(state) => modifiers = state,
                 WindowEvent::CloseRequested => {
                     *control_flow = ControlFlow::Exit;
                 }
@@ -89,7 +132,22 @@ fn main() {
                                 }
                                 ElementState::Released => {
                                     mouse_down = false;
-                                    app_state.update_selected_hover_objects();
+                                    if app_state.ui.pick_anchor {
+                                        app_state
+                                            .drop_anchor_at((last_pos.x as f32, last_pos.y as f32));
+                                    } else if app_state.ui.pick_waypoint {
+                                        app_state
+                                            .pick_waypoint((last_pos.x as f32, last_pos.y as f32));
+                                    } else if app_state.ui.pick_route_start
+                                        || app_state.ui.pick_route_destination
+                                    {
+                                        app_state.pick_route_point((
+                                            last_pos.x as f32,
+                                            last_pos.y as f32,
+                                        ));
+                                    } else {
+                                        app_state.update_selected_hover_objects();
+                                    }
                                 }
                             }
                         }
@@ -134,16 +192,17 @@ fn main() {
                             app_state.screen.center -= delta;
                         }
 
-                        app_state.update_hovered_objects((
-                            logical_position.x as f32,
-                            logical_position.y as f32,
-                        ))
+                        let point = (logical_position.x as f32, logical_position.y as f32);
+                        app_state.update_cursor(point);
+                        app_state.update_hovered_objects(point)
                     }
                 }
                 _ => (),
             },
             Event::MainEventsCleared => {
                 painter.update_shader();
+                app_state.update_navigation();
+                app_state.update_weather();
                 app_state.load_tiles();
                 painter.paint(&mut hud, &mut app_state);
 
@@ -160,3 +219,44 @@ fn main() {
         }
     });
 }
+
+/// Prints all diagnostics of a stylesheet and the files it imports like a compiler does.
+///
+/// Returns the exit code which is `1` if there was anything to complain about.
+fn check_style(path: &str) -> i32 {
+    let loaded = load_styles(path);
+    for (file, diagnostic) in &loaded.diagnostics {
+        println!("{}:{}", file.display(), diagnostic);
+    }
+    if loaded.diagnostics.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+/// Prints the rules converted from a Mapbox GL style and lists everything that could not be converted.
+///
+/// Returns the exit code which is `2` if the style could not be read.
+fn import_style(path: &str) -> i32 {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return 2;
+        }
+    };
+    match import_mapbox_style(&json) {
+        Some(import) => {
+            print!("{}", serialize_styles(&import.rules));
+            for unsupported in &import.unsupported {
+                eprintln!("{}: unsupported {}", path, unsupported);
+            }
+            0
+        }
+        None => {
+            eprintln!("{}: not a Mapbox GL style with layers", path);
+            2
+        }
+    }
+}
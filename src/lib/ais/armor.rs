@@ -0,0 +1,81 @@
+/// The bits of a de-armored AIS payload.
+///
+/// AIS payloads are transmitted as printable ASCII where every character carries six bits.
+#[derive(Debug, Clone, Default)]
+pub struct AisPayload {
+    bits: Vec<bool>,
+}
+
+impl AisPayload {
+    /// Creates an empty payload.
+    pub fn new() -> Self {
+        Self { bits: vec![] }
+    }
+
+    /// Appends an armored payload fragment.
+    ///
+    /// `fill_bits` is the number of padding bits at the end of the fragment that have to be dropped.
+    /// Returns `None` if the fragment contains characters outside the armoring alphabet.
+    pub fn push_armored(&mut self, armored: &str, fill_bits: usize) -> Option<()> {
+        for c in armored.bytes() {
+            let value = dearmor(c)?;
+            for i in (0..6).rev() {
+                self.bits.push((value >> i) & 1 == 1);
+            }
+        }
+        let len = self.bits.len().saturating_sub(fill_bits.min(5));
+        self.bits.truncate(len);
+        Some(())
+    }
+
+    /// Returns the number of bits in the payload.
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Reads `len` bits starting at bit `start` as an unsigned integer.
+    ///
+    /// Bits past the end of the payload are read as zero, as some transmitters truncate messages.
+    pub fn unsigned(&self, start: usize, len: usize) -> u32 {
+        (start..start + len).fold(0, |acc, i| {
+            (acc << 1) | self.bits.get(i).copied().unwrap_or(false) as u32
+        })
+    }
+
+    /// Reads `len` bits starting at bit `start` as a two's complement integer.
+    pub fn signed(&self, start: usize, len: usize) -> i32 {
+        let value = self.unsigned(start, len);
+        let shift = 32 - len;
+        ((value << shift) as i32) >> shift
+    }
+
+    /// Reads the bit at `start` as a boolean flag.
+    pub fn flag(&self, start: usize) -> bool {
+        self.unsigned(start, 1) == 1
+    }
+
+    /// Reads `len` bits starting at bit `start` as six bit ASCII text.
+    ///
+    /// Trailing `@` padding and spaces are stripped.
+    pub fn text(&self, start: usize, len: usize) -> String {
+        let mut text = String::with_capacity(len / 6);
+        for i in 0..len / 6 {
+            let value = self.unsigned(start + i * 6, 6) as u8;
+            text.push(if value < 32 { value + 64 } else { value } as char);
+        }
+        text.trim_end_matches(&['@', ' '][..]).to_string()
+    }
+}
+
+/// Converts a single armored character into its six bit value.
+fn dearmor(c: u8) -> Option<u8> {
+    match c {
+        48..=87 => Some(c - 48),
+        96..=119 => Some(c - 56),
+        _ => None,
+    }
+}
@@ -0,0 +1,162 @@
+use super::*;
+use std::collections::HashMap;
+
+/// A message that was received and decoded by the `AisDecoder`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AisReport {
+    /// `true` if the message was sent by our own transponder (`!AIVDO`).
+    pub own_vessel: bool,
+    pub message: AisMessage,
+}
+
+/// A partially received multi-fragment message.
+struct PendingMessage {
+    fragment_count: usize,
+    next_fragment: usize,
+    payload: AisPayload,
+}
+
+/// Decodes `!AIVDM`/`!AIVDO` sentences into AIS messages.
+///
+/// Messages that span multiple sentences are reassembled before decoding.
+pub struct AisDecoder {
+    pending: HashMap<(String, String), PendingMessage>,
+}
+
+impl Default for AisDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AisDecoder {
+    /// Creates a new decoder without any pending fragments.
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Feeds a single sentence to the decoder.
+    ///
+    /// Returns the decoded report once a message is complete.
+    /// Returns `None` if the sentence is not an AIS sentence, the message is still incomplete,
+    /// the fragments arrived out of order or the message type is not supported.
+    pub fn decode(&mut self, sentence: &NmeaSentence) -> Option<AisReport> {
+        let own_vessel = match &sentence.kind[..] {
+            "VDM" => false,
+            "VDO" => true,
+            _ => return None,
+        };
+
+        let fragment_count: usize = sentence.number(0)?;
+        let fragment_number: usize = sentence.number(1)?;
+        let sequence_id = sentence.field(2).unwrap_or_default().to_string();
+        let channel = sentence.field(3).unwrap_or_default().to_string();
+        let armored = sentence.field(4)?;
+        let fill_bits: usize = sentence.number(5).unwrap_or(0);
+
+        if fragment_count <= 1 {
+            let mut payload = AisPayload::new();
+            payload.push_armored(armored, fill_bits)?;
+            return Self::finish(own_vessel, &payload);
+        }
+
+        let key = (sequence_id, channel);
+        if fragment_number == 1 {
+            if self.pending.contains_key(&key) {
+                log::debug!("Dropping incomplete AIS message {:?}.", key);
+            }
+            self.pending.insert(
+                key.clone(),
+                PendingMessage {
+                    fragment_count,
+                    next_fragment: 1,
+                    payload: AisPayload::new(),
+                },
+            );
+        }
+
+        let pending = self.pending.get_mut(&key)?;
+        if pending.next_fragment != fragment_number || pending.fragment_count != fragment_count {
+            log::debug!(
+                "Received AIS fragment {} of {} out of order. Dropping message {:?}.",
+                fragment_number,
+                fragment_count,
+                key
+            );
+            self.pending.remove(&key);
+            return None;
+        }
+
+        // Fill bits are only allowed in the last fragment.
+        let fill_bits = if fragment_number == fragment_count {
+            fill_bits
+        } else {
+            0
+        };
+        if pending.payload.push_armored(armored, fill_bits).is_none() {
+            self.pending.remove(&key);
+            return None;
+        }
+        pending.next_fragment += 1;
+
+        if fragment_number == fragment_count {
+            let pending = self.pending.remove(&key)?;
+            Self::finish(own_vessel, &pending.payload)
+        } else {
+            None
+        }
+    }
+
+    fn finish(own_vessel: bool, payload: &AisPayload) -> Option<AisReport> {
+        AisMessage::decode(payload).map(|message| AisReport {
+            own_vessel,
+            message,
+        })
+    }
+}
+
+#[test]
+fn decode_recorded_log() {
+    let mut decoder = AisDecoder::new();
+    let reports: Vec<AisReport> = include_str!("../../../data/ais.nmea")
+        .lines()
+        .filter_map(NmeaSentence::parse)
+        .filter_map(|sentence| decoder.decode(&sentence))
+        .collect();
+
+    assert_eq!(reports.len(), 8);
+
+    if let AisMessage::PositionReport { mmsi, position, .. } = &reports[0].message {
+        assert_eq!(*mmsi, 371_798_000);
+        let (lat, lon) = position.lat_lon().unwrap();
+        assert!((lat - 48.381_63).abs() < 1e-5);
+        assert!((lon + 123.395_383).abs() < 1e-5);
+        assert_eq!(position.sog, Some(12.3));
+        assert_eq!(position.cog, Some(224.0));
+        assert_eq!(position.heading, Some(215));
+    } else {
+        panic!("Expected a position report, got {:?}", reports[0]);
+    }
+
+    if let AisMessage::StaticAndVoyage {
+        mmsi,
+        name,
+        callsign,
+        destination,
+        dimensions,
+        ..
+    } = &reports[1].message
+    {
+        assert_eq!(*mmsi, 351_759_000);
+        assert_eq!(name, "EVER DIADEM");
+        assert_eq!(callsign, "3FOF8");
+        assert_eq!(destination, "NEW YORK");
+        assert_eq!(dimensions.length(), 295);
+    } else {
+        panic!("Expected static and voyage data, got {:?}", reports[1]);
+    }
+
+    assert!(reports.iter().any(|r| r.own_vessel));
+}
@@ -0,0 +1,279 @@
+use super::*;
+
+/// The dimensions of a vessel relative to its position reference point in meters.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct AisDimensions {
+    pub to_bow: u16,
+    pub to_stern: u16,
+    pub to_port: u8,
+    pub to_starboard: u8,
+}
+
+impl AisDimensions {
+    fn decode(payload: &AisPayload, start: usize) -> Self {
+        Self {
+            to_bow: payload.unsigned(start, 9) as u16,
+            to_stern: payload.unsigned(start + 9, 9) as u16,
+            to_port: payload.unsigned(start + 18, 6) as u8,
+            to_starboard: payload.unsigned(start + 24, 6) as u8,
+        }
+    }
+
+    /// The overall length of the vessel in meters.
+    pub fn length(&self) -> u16 {
+        self.to_bow + self.to_stern
+    }
+
+    /// The overall beam of the vessel in meters.
+    pub fn beam(&self) -> u8 {
+        self.to_port + self.to_starboard
+    }
+}
+
+/// The kinematic part of a position report, shared by class A, class B and AtoN reports.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct AisPosition {
+    /// Latitude in degrees.
+    pub lat: Option<f64>,
+    /// Longitude in degrees.
+    pub lon: Option<f64>,
+    /// Speed over ground in knots.
+    pub sog: Option<f64>,
+    /// Course over ground in degrees true.
+    pub cog: Option<f64>,
+    /// True heading in degrees.
+    pub heading: Option<u16>,
+    /// Rate of turn in degrees per minute. Positive is turning to starboard.
+    pub rot: Option<f64>,
+}
+
+impl AisPosition {
+    /// Returns the position as `(lat, lon)` if both are available.
+    pub fn lat_lon(&self) -> Option<(f64, f64)> {
+        Some((self.lat?, self.lon?))
+    }
+}
+
+/// Any decoded AIS message that we care about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AisMessage {
+    /// Message types 1, 2 and 3: Class A position report.
+    PositionReport {
+        mmsi: u32,
+        status: u8,
+        position: AisPosition,
+    },
+    /// Message type 5: Class A static and voyage related data.
+    StaticAndVoyage {
+        mmsi: u32,
+        imo: u32,
+        callsign: String,
+        name: String,
+        ship_type: u8,
+        dimensions: AisDimensions,
+        /// Draught in meters.
+        draught: f64,
+        destination: String,
+    },
+    /// Message type 18: Standard class B position report.
+    ClassBPositionReport { mmsi: u32, position: AisPosition },
+    /// Message type 19: Extended class B position report.
+    ExtendedClassBPositionReport {
+        mmsi: u32,
+        position: AisPosition,
+        name: String,
+        ship_type: u8,
+        dimensions: AisDimensions,
+    },
+    /// Message type 21: Aid-to-navigation report.
+    AidToNavigation {
+        mmsi: u32,
+        aid_type: u8,
+        name: String,
+        position: AisPosition,
+        dimensions: AisDimensions,
+        off_position: bool,
+        virtual_aid: bool,
+    },
+    /// Message type 24 part A: Static data report with the vessel name.
+    StaticDataReportA { mmsi: u32, name: String },
+    /// Message type 24 part B: Static data report with the vessel details.
+    StaticDataReportB {
+        mmsi: u32,
+        ship_type: u8,
+        callsign: String,
+        dimensions: AisDimensions,
+    },
+}
+
+impl AisMessage {
+    /// Decodes a fully reassembled payload.
+    ///
+    /// Returns `None` for message types that are not supported.
+    pub fn decode(payload: &AisPayload) -> Option<Self> {
+        let message_type = payload.unsigned(0, 6);
+        let mmsi = payload.unsigned(8, 30);
+        let message = match message_type {
+            1..=3 => AisMessage::PositionReport {
+                mmsi,
+                status: payload.unsigned(38, 4) as u8,
+                position: AisPosition {
+                    rot: decode_rot(payload.signed(42, 8)),
+                    sog: decode_sog(payload.unsigned(50, 10)),
+                    lon: decode_lon(payload.signed(61, 28)),
+                    lat: decode_lat(payload.signed(89, 27)),
+                    cog: decode_cog(payload.unsigned(116, 12)),
+                    heading: decode_heading(payload.unsigned(128, 9)),
+                },
+            },
+            5 => AisMessage::StaticAndVoyage {
+                mmsi,
+                imo: payload.unsigned(40, 30),
+                callsign: payload.text(70, 42),
+                name: payload.text(112, 120),
+                ship_type: payload.unsigned(232, 8) as u8,
+                dimensions: AisDimensions::decode(payload, 240),
+                draught: payload.unsigned(294, 8) as f64 / 10.0,
+                destination: payload.text(302, 120),
+            },
+            18 => AisMessage::ClassBPositionReport {
+                mmsi,
+                position: decode_class_b_position(payload),
+            },
+            19 => AisMessage::ExtendedClassBPositionReport {
+                mmsi,
+                position: decode_class_b_position(payload),
+                name: payload.text(143, 120),
+                ship_type: payload.unsigned(263, 8) as u8,
+                dimensions: AisDimensions::decode(payload, 271),
+            },
+            21 => {
+                let mut name = payload.text(43, 120);
+                // Names longer than 20 characters continue after the fixed part of the message.
+                if payload.len() > 272 {
+                    let extension_len = (payload.len() - 272) / 6 * 6;
+                    name += &payload.text(272, extension_len);
+                }
+                AisMessage::AidToNavigation {
+                    mmsi,
+                    aid_type: payload.unsigned(38, 5) as u8,
+                    name,
+                    position: AisPosition {
+                        lon: decode_lon(payload.signed(164, 28)),
+                        lat: decode_lat(payload.signed(192, 27)),
+                        ..Default::default()
+                    },
+                    dimensions: AisDimensions::decode(payload, 219),
+                    off_position: payload.flag(259),
+                    virtual_aid: payload.flag(269),
+                }
+            }
+            24 => match payload.unsigned(38, 2) {
+                0 => AisMessage::StaticDataReportA {
+                    mmsi,
+                    name: payload.text(40, 120),
+                },
+                1 => AisMessage::StaticDataReportB {
+                    mmsi,
+                    ship_type: payload.unsigned(40, 8) as u8,
+                    callsign: payload.text(90, 42),
+                    dimensions: AisDimensions::decode(payload, 132),
+                },
+                part => {
+                    log::debug!("Unknown AIS message 24 part number {}.", part);
+                    return None;
+                }
+            },
+            message_type => {
+                log::trace!("AIS message type {} is not supported.", message_type);
+                return None;
+            }
+        };
+        Some(message)
+    }
+
+    /// The MMSI of the station which sent the message.
+    pub fn mmsi(&self) -> u32 {
+        match self {
+            AisMessage::PositionReport { mmsi, .. }
+            | AisMessage::StaticAndVoyage { mmsi, .. }
+            | AisMessage::ClassBPositionReport { mmsi, .. }
+            | AisMessage::ExtendedClassBPositionReport { mmsi, .. }
+            | AisMessage::AidToNavigation { mmsi, .. }
+            | AisMessage::StaticDataReportA { mmsi, .. }
+            | AisMessage::StaticDataReportB { mmsi, .. } => *mmsi,
+        }
+    }
+}
+
+fn decode_class_b_position(payload: &AisPayload) -> AisPosition {
+    AisPosition {
+        sog: decode_sog(payload.unsigned(46, 10)),
+        lon: decode_lon(payload.signed(57, 28)),
+        lat: decode_lat(payload.signed(85, 27)),
+        cog: decode_cog(payload.unsigned(112, 12)),
+        heading: decode_heading(payload.unsigned(124, 9)),
+        rot: None,
+    }
+}
+
+/// Longitudes are transmitted in 1/10000 minutes. 181° means not available.
+fn decode_lon(value: i32) -> Option<f64> {
+    let lon = value as f64 / 600_000.0;
+    if lon.abs() <= 180.0 {
+        Some(lon)
+    } else {
+        None
+    }
+}
+
+/// Latitudes are transmitted in 1/10000 minutes. 91° means not available.
+fn decode_lat(value: i32) -> Option<f64> {
+    let lat = value as f64 / 600_000.0;
+    if lat.abs() <= 90.0 {
+        Some(lat)
+    } else {
+        None
+    }
+}
+
+/// Speeds are transmitted in 1/10 knots. 1023 means not available.
+fn decode_sog(value: u32) -> Option<f64> {
+    if value < 1023 {
+        Some(value as f64 / 10.0)
+    } else {
+        None
+    }
+}
+
+/// Courses are transmitted in 1/10 degrees. 3600 means not available.
+fn decode_cog(value: u32) -> Option<f64> {
+    if value < 3600 {
+        Some(value as f64 / 10.0)
+    } else {
+        None
+    }
+}
+
+/// Headings are transmitted in degrees. 511 means not available.
+fn decode_heading(value: u32) -> Option<u16> {
+    if value < 360 {
+        Some(value as u16)
+    } else {
+        None
+    }
+}
+
+/// The rate of turn is transmitted as `4.733 * sqrt(rot)`. -128 means not available.
+fn decode_rot(value: i32) -> Option<f64> {
+    match value {
+        -128 => None,
+        // Turning faster than 5° per 30s without a turn indicator. The exact value is unknown.
+        127 => Some(10.0),
+        -127 => Some(-10.0),
+        value => {
+            let rot = (value as f64 / 4.733).powi(2);
+            Some(rot.copysign(value as f64))
+        }
+    }
+}
@@ -0,0 +1,11 @@
+mod armor;
+mod decoder;
+mod message;
+mod targets;
+
+pub use armor::*;
+pub use decoder::*;
+pub use message::*;
+pub use targets::*;
+
+use crate::*;
@@ -0,0 +1,208 @@
+use super::*;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// The kind of station an `AisTarget` represents.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AisTargetClass {
+    ClassA,
+    ClassB,
+    AidToNavigation,
+    /// Only static data has been received so far.
+    Unknown,
+}
+
+/// Everything we know about a single AIS station.
+#[derive(Debug, Clone)]
+pub struct AisTarget {
+    pub mmsi: u32,
+    pub class: AisTargetClass,
+    pub position: AisPosition,
+    pub name: Option<String>,
+    pub callsign: Option<String>,
+    pub ship_type: Option<u8>,
+    pub dimensions: Option<AisDimensions>,
+    pub destination: Option<String>,
+    /// When the last message of any type was received from this station.
+    pub last_seen: Instant,
+    /// When the last position report was received from this station.
+    pub last_position: Option<Instant>,
+}
+
+impl AisTarget {
+    fn new(mmsi: u32, now: Instant) -> Self {
+        Self {
+            mmsi,
+            class: AisTargetClass::Unknown,
+            position: Default::default(),
+            name: None,
+            callsign: None,
+            ship_type: None,
+            dimensions: None,
+            destination: None,
+            last_seen: now,
+            last_position: None,
+        }
+    }
+
+    /// Returns the name of the target if known and its MMSI otherwise.
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| self.mmsi.to_string())
+    }
+
+    fn update_position(&mut self, class: AisTargetClass, position: AisPosition, now: Instant) {
+        self.class = class;
+        self.position = position;
+        self.last_position = Some(now);
+    }
+}
+
+/// All AIS targets currently in range, keyed by MMSI.
+///
+/// Targets which have not been heard of for longer than `max_age` are dropped.
+pub struct AisTargetTable {
+    targets: BTreeMap<u32, AisTarget>,
+    max_age: Duration,
+}
+
+impl AisTargetTable {
+    /// Creates a new empty target table.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            targets: BTreeMap::new(),
+            max_age,
+        }
+    }
+
+    /// Merges a decoded message into the target it belongs to.
+    ///
+    /// Reports from our own transponder are ignored.
+    pub fn update(&mut self, report: &AisReport, now: Instant) {
+        if report.own_vessel {
+            return;
+        }
+
+        let mmsi = report.message.mmsi();
+        let target = self
+            .targets
+            .entry(mmsi)
+            .or_insert_with(|| AisTarget::new(mmsi, now));
+        target.last_seen = now;
+
+        match &report.message {
+            AisMessage::PositionReport { position, .. } => {
+                target.update_position(AisTargetClass::ClassA, *position, now);
+            }
+            AisMessage::StaticAndVoyage {
+                callsign,
+                name,
+                ship_type,
+                dimensions,
+                destination,
+                ..
+            } => {
+                target.name = Some(name.clone());
+                target.callsign = Some(callsign.clone());
+                target.ship_type = Some(*ship_type);
+                target.dimensions = Some(*dimensions);
+                target.destination = Some(destination.clone());
+            }
+            AisMessage::ClassBPositionReport { position, .. } => {
+                target.update_position(AisTargetClass::ClassB, *position, now);
+            }
+            AisMessage::ExtendedClassBPositionReport {
+                position,
+                name,
+                ship_type,
+                dimensions,
+                ..
+            } => {
+                target.update_position(AisTargetClass::ClassB, *position, now);
+                target.name = Some(name.clone());
+                target.ship_type = Some(*ship_type);
+                target.dimensions = Some(*dimensions);
+            }
+            AisMessage::AidToNavigation {
+                name,
+                position,
+                dimensions,
+                ..
+            } => {
+                target.update_position(AisTargetClass::AidToNavigation, *position, now);
+                target.name = Some(name.clone());
+                target.dimensions = Some(*dimensions);
+            }
+            AisMessage::StaticDataReportA { name, .. } => {
+                target.name = Some(name.clone());
+            }
+            AisMessage::StaticDataReportB {
+                ship_type,
+                callsign,
+                dimensions,
+                ..
+            } => {
+                target.ship_type = Some(*ship_type);
+                target.callsign = Some(callsign.clone());
+                target.dimensions = Some(*dimensions);
+            }
+        }
+    }
+
+    /// Removes all targets which have not been heard of for longer than the configured max age.
+    ///
+    /// Returns the number of removed targets.
+    pub fn remove_stale(&mut self, now: Instant) -> usize {
+        let max_age = self.max_age;
+        let len = self.targets.len();
+        self.targets
+            .retain(|_, target| now.saturating_duration_since(target.last_seen) <= max_age);
+        len - self.targets.len()
+    }
+
+    pub fn get(&self, mmsi: u32) -> Option<&AisTarget> {
+        self.targets.get(&mmsi)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AisTarget> {
+        self.targets.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+#[test]
+fn track_and_age_out_targets() {
+    let mut decoder = AisDecoder::new();
+    let mut table = AisTargetTable::new(Duration::from_secs(360));
+    let start = Instant::now();
+
+    for sentence in include_str!("../../../data/ais.nmea")
+        .lines()
+        .filter_map(NmeaSentence::parse)
+    {
+        if let Some(report) = decoder.decode(&sentence) {
+            table.update(&report, start);
+        }
+    }
+
+    let class_b = table
+        .iter()
+        .find(|t| t.class == AisTargetClass::ClassB)
+        .expect("The log contains class B targets.");
+    assert_eq!(class_b.display_name(), "SEA BREEZE");
+    assert_eq!(class_b.callsign.as_deref(), Some("HB1234"));
+
+    let len = table.len();
+    assert_eq!(table.remove_stale(start + Duration::from_secs(60)), 0);
+    assert_eq!(table.remove_stale(start + Duration::from_secs(361)), len);
+    assert!(table.is_empty());
+}
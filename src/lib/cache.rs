@@ -106,6 +106,15 @@ impl TileCache {
         }
     }
 
+    /// Drops all cached tiles, so they are loaded again when they are requested next.
+    ///
+    /// Tiles which are being loaded right now are dropped as well once they are done, since they might be out of date.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        // The detached loaders still send their id, but `finalize_loaded_tiles` does not know it anymore.
+        self.loaders.clear();
+    }
+
     /// Get a `Tile` from the `TileCache`.
     ///
     /// Returns `None` if the tile is not in the cache.
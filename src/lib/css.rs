@@ -1,49 +1,625 @@
 use crossbeam_channel::{unbounded, TryRecvError};
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while, take_while_m_n},
-    character::complete::multispace0,
+    bytes::complete::{tag, take_until, take_while, take_while1, take_while_m_n},
+    character::complete::{multispace1, satisfy},
     character::{complete::char, is_alphanumeric},
-    combinator::map_res,
+    combinator::{map, map_res, not, opt, recognize},
     error::FromExternalError,
-    error::{convert_error, ParseError, VerboseError},
-    multi::many0,
+    error::{ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    multi::{fold_many0, many0, separated_list1},
     number::complete::float,
-    sequence::{delimited, preceded, separated_pair, tuple},
-    AsChar, Err, IResult, InputTakeAtPosition,
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    Err, IResult, Offset,
 };
 use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::{collections::BTreeMap, num::ParseIntError};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    num::ParseIntError,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 /// Tries to parse an entire stylesheet.
+///
+/// All diagnostics are logged and `None` is returned if there is any error among them.
 pub fn try_parse_styles(style: &str) -> Option<Vec<Rule>> {
-    match rules::<VerboseError<&str>>(style) {
-        Ok((_, s)) => Some(s),
-        Err(Err::Error(e)) | Err(Err::Failure(e)) => {
-            log::info!("Failed to load stylesheet.");
-            log::info!("Trace: {}", convert_error(style, e));
-            None
+    let (rules, diagnostics) = parse_styles(style);
+    for diagnostic in &diagnostics {
+        log_diagnostic("", diagnostic);
+    }
+
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        log::info!("Failed to load stylesheet.");
+        None
+    } else {
+        Some(rules)
+    }
+}
+
+/// Parses a single value as it would appear in a declaration, e.g. `#0eb5df` or `interpolate(zoom, 8: 1px, 14: 4px)`.
+pub fn parse_value(value: &str) -> Option<CSSValue> {
+    match css_value::<VerboseError<&str>>(value) {
+        Ok((remaining, value)) if remaining.trim().is_empty() => Some(value),
+        _ => None,
+    }
+}
+
+/// Parses an entire stylesheet and collects all the problems found in it.
+///
+/// A syntax error skips the broken rule up to the next `}` so the rules after it are still checked.
+/// Imports can only be resolved relative to a file, use `load_styles` for stylesheets that have any.
+pub fn parse_styles(style: &str) -> (Vec<Rule>, Vec<Diagnostic>) {
+    let mut stylesheet = parse_stylesheet(style);
+    for (offset, import) in &stylesheet.imports {
+        stylesheet.diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            style,
+            *offset,
+            format!(
+                "'{}' is not imported as the stylesheet is not a file",
+                import
+            ),
+        ));
+    }
+    (stylesheet.rules, stylesheet.diagnostics)
+}
+
+/// The statements of a single stylesheet.
+struct Stylesheet {
+    /// The files to import with the byte offset of their `@import`.
+    imports: Vec<(usize, String)>,
+    rules: Vec<Rule>,
+    /// The byte ranges of the rules in the stylesheet.
+    spans: Vec<Range<usize>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Parses the imports and rules of a single stylesheet without resolving the imports.
+fn parse_stylesheet(style: &str) -> Stylesheet {
+    let mut stylesheet = Stylesheet {
+        imports: vec![],
+        rules: vec![],
+        spans: vec![],
+        diagnostics: vec![],
+    };
+    let mut input = style;
+    loop {
+        if let Ok((remaining, _)) = blank::<VerboseError<&str>>(input) {
+            input = remaining;
+        }
+        if input.is_empty() {
+            break;
+        }
+
+        let start = style.len() - input.len();
+        let result = if input.starts_with("@import") {
+            import::<VerboseError<&str>>(input).map(|(remaining, path)| {
+                if stylesheet.rules.is_empty() {
+                    stylesheet.imports.push((start, path.to_string()));
+                } else {
+                    stylesheet.diagnostics.push(Diagnostic::new(
+                        Severity::Warning,
+                        style,
+                        start,
+                        format!(
+                            "'{}' is ignored as @import has to come before all rules",
+                            path
+                        ),
+                    ));
+                }
+                remaining
+            })
+        } else {
+            rule::<VerboseError<&str>>(input).map(|(remaining, (rule, names))| {
+                check_rule(style, start, &rule, &names, &mut stylesheet.diagnostics);
+                stylesheet.rules.push(rule);
+                stylesheet.spans.push(start..style.len() - remaining.len());
+                remaining
+            })
+        };
+
+        match result {
+            Ok(remaining) => input = remaining,
+            Err(Err::Error(e)) | Err(Err::Failure(e)) => {
+                let offset = e
+                    .errors
+                    .iter()
+                    .map(|(remaining, _)| style.len() - remaining.len())
+                    .max()
+                    .unwrap_or(start);
+                let kind = e
+                    .errors
+                    .iter()
+                    .find(|(remaining, _)| style.len() - remaining.len() == offset)
+                    .map(|(_, kind)| kind);
+                stylesheet.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    style,
+                    offset,
+                    syntax_error(&style[offset..], kind),
+                ));
+                let end = if input.starts_with("@import") {
+                    ';'
+                } else {
+                    '}'
+                };
+                match style[offset..].find(end) {
+                    Some(end) => input = &style[offset + end + 1..],
+                    None => break,
+                }
+            }
+            Err(Err::Incomplete(_)) => {
+                stylesheet.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    style,
+                    style.len(),
+                    "unexpected end of the stylesheet",
+                ));
+                break;
+            }
+        }
+    }
+    stylesheet
+}
+
+/// A single file of a stylesheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleFile {
+    pub path: PathBuf,
+    /// The imports as they are written in the file.
+    pub imports: Vec<String>,
+    /// The file as it was last read from or written to disk.
+    pub contents: String,
+    /// The rules of the file serialized when it was last read or written, to tell whether they were edited since.
+    saved_rules: String,
+    /// The byte range of every rule in `contents` and the rule serialized, to tell which rules were edited.
+    rule_sources: Vec<(Range<usize>, String)>,
+}
+
+/// A stylesheet loaded from a file including all the files it imports.
+#[derive(Debug, Default)]
+pub struct LoadedStyles {
+    /// The rules of all files, the ones of an imported file in place of its `@import`.
+    pub rules: Vec<Rule>,
+    /// All files that were read, the stylesheet itself first.
+    pub files: Vec<StyleFile>,
+    /// The problems found in each of the files.
+    pub diagnostics: Vec<(PathBuf, Diagnostic)>,
+}
+
+impl LoadedStyles {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|(_, diagnostic)| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// Loads a stylesheet and everything it imports.
+///
+/// Imports are resolved relative to the importing file. Files that can not be read and circular imports are errors.
+pub fn load_styles(path: impl AsRef<Path>) -> LoadedStyles {
+    let path = path.as_ref();
+    let mut loaded = LoadedStyles::default();
+    match std::fs::read_to_string(path) {
+        Ok(contents) => import_styles(path, contents, &mut loaded, &mut vec![]),
+        Err(err) => loaded.diagnostics.push((
+            path.to_path_buf(),
+            Diagnostic {
+                severity: Severity::Error,
+                line: 1,
+                column: 1,
+                message: format!("unable to read the stylesheet: {}", err),
+            },
+        )),
+    }
+    loaded
+}
+
+/// Adds a file and its imports to `loaded`.
+///
+/// `stack` holds the canonical paths of the files currently being imported to detect cycles.
+fn import_styles(
+    path: &Path,
+    contents: String,
+    loaded: &mut LoadedStyles,
+    stack: &mut Vec<PathBuf>,
+) {
+    stack.push(std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    let file = loaded.files.len();
+    let stylesheet = parse_stylesheet(&contents);
+    loaded.files.push(StyleFile {
+        path: path.to_path_buf(),
+        imports: stylesheet
+            .imports
+            .iter()
+            .map(|(_, import)| import.clone())
+            .collect(),
+        contents: String::new(),
+        saved_rules: String::new(),
+        rule_sources: vec![],
+    });
+    loaded.diagnostics.extend(
+        stylesheet
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| (path.to_path_buf(), diagnostic)),
+    );
+
+    for (offset, import) in &stylesheet.imports {
+        let import_path = path.parent().unwrap_or_else(|| Path::new("")).join(import);
+        let canonical = std::fs::canonicalize(&import_path).unwrap_or_else(|_| import_path.clone());
+        let problem = if stack.contains(&canonical) {
+            format!("'{}' imports itself", import)
+        } else {
+            match std::fs::read_to_string(&import_path) {
+                Ok(imported) => {
+                    import_styles(&import_path, imported, loaded, stack);
+                    continue;
+                }
+                Err(err) => format!("unable to import '{}': {}", import, err),
+            }
+        };
+        loaded.diagnostics.push((
+            path.to_path_buf(),
+            Diagnostic::new(Severity::Error, &contents, *offset, problem),
+        ));
+    }
+
+    loaded.files[file].rule_sources = stylesheet
+        .spans
+        .into_iter()
+        .zip(&stylesheet.rules)
+        .map(|(span, rule)| (span, rule.to_string()))
+        .collect();
+    loaded.rules.extend(
+        stylesheet
+            .rules
+            .into_iter()
+            .map(|rule| Rule { file, ..rule }),
+    );
+    loaded.files[file].contents = contents;
+    loaded.files[file].saved_rules =
+        serialize_styles(loaded.rules.iter().filter(|rule| rule.file == file));
+    stack.pop();
+}
+
+/// The contents of a file and the byte range and serialization of each of its rules.
+type FileContents = (String, Vec<(Range<usize>, String)>);
+
+/// Replaces the edited rules in the contents of `file` and adds the new ones at the end.
+///
+/// Returns `None` if the file had more rules than `rules`.
+fn splice_rules(file: &StyleFile, rules: &[&Rule]) -> Option<FileContents> {
+    if rules.len() < file.rule_sources.len() {
+        return None;
+    }
+    let mut contents = String::new();
+    let mut rule_sources = vec![];
+    let mut end = 0;
+    for ((span, source), rule) in file.rule_sources.iter().zip(rules) {
+        contents += &file.contents[end..span.start];
+        let serialized = rule.to_string();
+        let start = contents.len();
+        if serialized == *source {
+            contents += &file.contents[span.clone()];
+        } else {
+            contents += &serialized;
+        }
+        rule_sources.push((start..contents.len(), serialized));
+        end = span.end;
+    }
+    contents += &file.contents[end..];
+    for rule in &rules[file.rule_sources.len()..] {
+        contents.truncate(contents.trim_end().len());
+        if !contents.is_empty() {
+            contents += "\n\n";
+        }
+        let serialized = rule.to_string();
+        let start = contents.len();
+        contents += &serialized;
+        contents += "\n";
+        rule_sources.push((start..start + serialized.len(), serialized));
+    }
+    Some((contents, rule_sources))
+}
+
+/// Writes the imports and rules of `file` anew.
+fn rewrite_rules(file: &StyleFile, rules: &[&Rule]) -> FileContents {
+    let mut contents = file
+        .imports
+        .iter()
+        .map(|import| format!("@import \"{}\";\n", import))
+        .collect::<String>();
+    let mut rule_sources = vec![];
+    for rule in rules {
+        if !contents.is_empty() {
+            contents += "\n";
+        }
+        let serialized = rule.to_string();
+        let start = contents.len();
+        contents += &serialized;
+        contents += "\n";
+        rule_sources.push((start..start + serialized.len(), serialized));
+    }
+    (contents, rule_sources)
+}
+
+/// Logs a diagnostic of the stylesheet at `path`.
+fn log_diagnostic(path: &str, diagnostic: &Diagnostic) {
+    let separator = if path.is_empty() { "" } else { ":" };
+    match diagnostic.severity {
+        Severity::Error => log::error!("{}{}{}", path, separator, diagnostic),
+        Severity::Warning => log::warn!("{}{}{}", path, separator, diagnostic),
+    }
+}
+
+/// Writes rules back into a stylesheet that parses to the same rules again.
+///
+/// Comments and the formatting of the original file are not preserved and properties are sorted by name.
+pub fn serialize_styles<'a>(rules: impl IntoIterator<Item = &'a Rule>) -> String {
+    rules
+        .into_iter()
+        .map(|rule| format!("{}\n", rule))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// How bad a problem in a stylesheet is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    /// The stylesheet is not loaded at all.
+    Error,
+    /// The stylesheet is loaded but likely does not do what was intended.
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem in a stylesheet.
+/// E.g. `3:5: warning: unknown property 'backgroud-color', did you mean 'background-color'?`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The line of the problem starting at 1.
+    pub line: usize,
+    /// The column of the problem in characters starting at 1.
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic at the byte `offset` into `style`.
+    fn new(severity: Severity, style: &str, offset: usize, message: impl Into<String>) -> Self {
+        let before = &style[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            severity,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
         }
-        Err(Err::Incomplete(_)) => {
-            log::info!("Unexpected EOF loading the stylesheet.");
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )
+    }
+}
+
+/// Describes what went wrong where the parser gave up.
+fn syntax_error(remaining: &str, kind: Option<&VerboseErrorKind>) -> String {
+    if remaining.starts_with("/*") {
+        return "unterminated comment".into();
+    }
+    // The body of a rule stops at the first declaration it can not parse, so explain that instead of the `}`.
+    if let Ok((_, name)) = terminated(css_name::<VerboseError<&str>>, char(':'))(remaining) {
+        if !name.is_empty() {
+            return format!("invalid value for '{}'", name);
+        }
+    }
+
+    let found = match remaining.chars().next() {
+        Some(c) => format!("'{}'", c),
+        None => "the end of the stylesheet".into(),
+    };
+    match kind {
+        Some(VerboseErrorKind::Char(c)) => format!("expected '{}' but found {}", c, found),
+        Some(VerboseErrorKind::Context(context)) => {
+            format!("expected {} but found {}", context, found)
+        }
+        _ => format!("unexpected {}", found),
+    }
+}
+
+/// The kinds of values a property accepts.
+#[derive(Debug, Copy, Clone)]
+enum ValueKind {
+    Color,
+    /// A length in `px`.
+    Px,
+    /// A length in `px` or `w`.
+    Length,
+    Unitless,
+    Keyword(&'static [&'static str]),
+}
+
+impl ValueKind {
+    /// Checks a value and returns what is wrong with it.
+    fn check(&self, value: &CSSValue) -> Option<String> {
+        let accepted = match (self, value) {
+            // Variables are only known after the cascade.
+            (_, CSSValue::Var(..)) => true,
+            (_, CSSValue::Interpolate(interpolation)) => {
+                return interpolation
+                    .stops
+                    .iter()
+                    .find_map(|(_, value)| self.check(value));
+            }
+            (ValueKind::Color, CSSValue::Color(_)) => true,
+            (ValueKind::Color, CSSValue::String(name)) => {
+                if Color::from_name(name).is_some() {
+                    true
+                } else {
+                    return Some(format!("unknown color '{}'", name));
+                }
+            }
+            (ValueKind::Px, CSSValue::Number(Number::Px(_))) => true,
+            (ValueKind::Length, CSSValue::Number(Number::Px(_)))
+            | (ValueKind::Length, CSSValue::Number(Number::World(_))) => true,
+            (ValueKind::Unitless, CSSValue::Number(Number::Unitless(_))) => true,
+            (ValueKind::Keyword(keywords), CSSValue::String(keyword)) => {
+                keywords.contains(&keyword.as_str())
+            }
+            _ => false,
+        };
+        if accepted {
             None
+        } else {
+            Some(format!(
+                "expected {} but found {}",
+                self,
+                describe_value(value)
+            ))
         }
     }
 }
 
+impl std::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueKind::Color => write!(f, "a color"),
+            ValueKind::Px => write!(f, "a length in px"),
+            ValueKind::Length => write!(f, "a length in px or w"),
+            ValueKind::Unitless => write!(f, "a number without unit"),
+            ValueKind::Keyword(keywords) => write!(f, "one of '{}'", keywords.join("', '")),
+        }
+    }
+}
+
+/// Describes a value for a diagnostic.
+fn describe_value(value: &CSSValue) -> String {
+    match value {
+        CSSValue::String(value) => format!("'{}'", value),
+        CSSValue::Color(_) => "a color".into(),
+        CSSValue::Number(Number::Px(_)) => "a length in px".into(),
+        CSSValue::Number(Number::World(_)) => "a length in w".into(),
+        CSSValue::Number(Number::Unitless(_)) => "a number without unit".into(),
+        CSSValue::Interpolate(_) => "an interpolation".into(),
+        CSSValue::Var(name, _) => format!("var({})", name),
+    }
+}
+
+/// The properties the renderer understands and the values they accept.
+const PROPERTIES: [(&str, ValueKind); 6] = [
+    ("background-color", ValueKind::Color),
+    ("border-color", ValueKind::Color),
+    ("border-width", ValueKind::Px),
+    ("display", ValueKind::Keyword(&["none", "block"])),
+    ("line-width", ValueKind::Length),
+    ("z-index", ValueKind::Unitless),
+];
+
+/// Checks a parsed rule for unknown properties, values of the wrong type and a selector that never matches.
+///
+/// The rule starts at the byte `start` into `style`, `names` are the property names of its declarations within `style`.
+fn check_rule(
+    style: &str,
+    start: usize,
+    rule: &Rule,
+    names: &BTreeMap<String, &str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if let Some(reason) = rule.selector.contradiction() {
+        diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            style,
+            start,
+            format!("the selector '{}' never matches, {}", rule.selector, reason),
+        ));
+    }
+
+    for (property, value) in &rule.kvs {
+        // Custom properties hold any value for the variables referencing them.
+        if property.starts_with("--") {
+            continue;
+        }
+        let offset = names.get(property).map_or(start, |name| style.offset(name));
+        let message = match PROPERTIES.iter().find(|(name, _)| name == property) {
+            Some((_, kind)) => match kind.check(value) {
+                Some(problem) => format!("invalid value for '{}', {}", property, problem),
+                None => continue,
+            },
+            None => match PROPERTIES
+                .iter()
+                .map(|(name, _)| (edit_distance(name, property), name))
+                .min()
+            {
+                Some((distance, name)) if distance <= 2 => {
+                    format!("unknown property '{}', did you mean '{}'?", property, name)
+                }
+                _ => format!("unknown property '{}'", property),
+            },
+        };
+        diagnostics.push(Diagnostic::new(Severity::Warning, style, offset, message));
+    }
+}
+
+/// The number of single character insertions, deletions and substitutions to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 pub struct RulesCache {
     pub rules: Vec<Rule>,
+    /// The stylesheet and all the files it imports.
+    pub files: Vec<StyleFile>,
     rx: crossbeam_channel::Receiver<std::result::Result<notify::event::Event, notify::Error>>,
-    _watcher: RecommendedWatcher,
+    watcher: RecommendedWatcher,
 }
 
 impl RulesCache {
     /// Tries to create a new CSS rule cache from a given CSS file path.
+    ///
+    /// The file and every file it imports are watched for changes.
     pub fn try_load_from_file(filename: impl Into<String>) -> Option<Self> {
         let filename = filename.into();
 
-        let contents = std::fs::read_to_string(std::path::Path::new(&filename))
-            .expect("Something went wrong reading the file");
+        let loaded = load_styles(&filename);
+        for (path, diagnostic) in &loaded.diagnostics {
+            log_diagnostic(&path.to_string_lossy(), diagnostic);
+        }
+        if loaded.has_errors() {
+            log::info!("Failed to load stylesheet.");
+            return None;
+        }
 
         let (tx, rx) = unbounded();
         let mut watcher: RecommendedWatcher =
@@ -56,24 +632,43 @@ impl RulesCache {
                 }
             };
 
-        match watcher.watch(&filename, RecursiveMode::Recursive) {
-            Ok(_) => {}
-            Err(err) => {
-                log::info!("Failed to start watching {}:", filename);
-                log::info!("{}", err);
-                return None;
-            }
-        };
-
-        let rules = try_parse_styles(&contents)?;
+        for file in &loaded.files {
+            match watcher.watch(&file.path, RecursiveMode::NonRecursive) {
+                Ok(_) => {}
+                Err(err) => {
+                    log::info!("Failed to start watching {:?}:", file.path);
+                    log::info!("{}", err);
+                    return None;
+                }
+            };
+        }
 
         Some(Self {
-            rules,
+            rules: loaded.rules,
+            files: loaded.files,
             rx,
-            _watcher: watcher,
+            watcher,
         })
     }
 
+    /// Returns the distinct selectors of all rules with a filter expression in the order they appear.
+    ///
+    /// Tiles evaluate the filters of the selectors that may match a feature with its tags, see `Selector::satisfied`.
+    pub fn filters(&self) -> Vec<Selector> {
+        let mut filters: Vec<Selector> = vec![];
+        for selector in self
+            .rules
+            .iter()
+            .map(|rule| &rule.selector)
+            .filter(|selector| selector.filter.is_some())
+        {
+            if !filters.contains(selector) {
+                filters.push(selector.clone());
+            }
+        }
+        filters
+    }
+
     /// Returns all Rules that match a given selector.
     ///
     /// E.g. `layer` does not match the `layer[zoom=5]` rule selector.
@@ -85,6 +680,56 @@ impl RulesCache {
             .collect()
     }
 
+    /// Computes the style of a selector with the CSS cascade.
+    ///
+    /// For every property the declaration of the matching rule with the highest specificity wins,
+    /// declarations marked `!important` win over all others and the later rule breaks ties.
+    pub fn compute_style(&self, selector: &Selector) -> ComputedStyle {
+        let mut values: BTreeMap<String, ComputedValue> = BTreeMap::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !selector.matches(&rule.selector) {
+                continue;
+            }
+            let specificity = rule.selector.specificity();
+            for (property, value) in &rule.kvs {
+                let important = rule.important.contains(property);
+                let wins = values.get(property).iter().all(|current| {
+                    (important, specificity)
+                        >= (
+                            current.important,
+                            self.rules[current.rule].selector.specificity(),
+                        )
+                });
+                if wins {
+                    values.insert(
+                        property.clone(),
+                        ComputedValue {
+                            value: value.clone(),
+                            rule: index,
+                            important,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Variables take the value the custom property has for the same selector after the cascade.
+        // A property that references an undeclared variable without a fallback is dropped.
+        let variables: BTreeMap<String, CSSValue> = values
+            .iter()
+            .filter(|(property, _)| property.starts_with("--"))
+            .map(|(property, computed)| (property.clone(), computed.value.clone()))
+            .collect();
+        let values = values
+            .into_iter()
+            .filter_map(|(property, computed)| {
+                let value = computed.value.resolve(&variables)?;
+                Some((property, ComputedValue { value, ..computed }))
+            })
+            .collect();
+        ComputedStyle { values }
+    }
+
     pub fn get_matching_rules_mut(&mut self, selector: &Selector) -> Vec<&mut Rule> {
         self.rules
             .iter_mut()
@@ -100,7 +745,7 @@ impl RulesCache {
         self.rules.iter_mut().find(|rule| selector == rule.selector)
     }
 
-    /// Updates the CSS cache from the watched file if there was any changes.
+    /// Updates the CSS cache from the watched files if there was any changes.
     ///
     /// Returns whether a successful update happened.
     /// Returns false if there was no changes or if the update failed.
@@ -108,9 +753,8 @@ impl RulesCache {
         match self.rx.try_recv() {
             Ok(Ok(notify::event::Event {
                 kind: EventKind::Modify(ModifyKind::Data(_)),
-                paths,
                 ..
-            })) => self.try_reload_from_file(&paths[0].as_path()),
+            })) => self.try_reload(false),
             // Everything is alright but file wasn't actually changed.
             Ok(Ok(_)) => false,
             Ok(Err(err)) => {
@@ -132,24 +776,89 @@ impl RulesCache {
         }
     }
 
-    /// Tries reloading the cached styles from a file.
+    /// Writes the rules including all edits made in memory back to the files they came from.
     ///
-    /// Returns `true` if it succeeded.
-    /// Returns `false` in any error case.
-    fn try_reload_from_file(&mut self, filename: &std::path::Path) -> bool {
-        match std::fs::read_to_string(filename) {
-            Ok(contents) => {
-                self.rules = match try_parse_styles(&contents) {
-                    Some(rules) => rules,
-                    None => return false,
+    /// Only the edited rules are written anew, the rest of the files keeps its comments and formatting.
+    /// Comments within an edited rule are lost.
+    /// The watcher reports the writes like any other change, but `update` ignores them as the contents are the same.
+    pub fn save(&mut self) -> bool {
+        let mut saved = true;
+        for (index, file) in self.files.iter_mut().enumerate() {
+            let rules: Vec<&Rule> = self
+                .rules
+                .iter()
+                .filter(|rule| rule.file == index)
+                .collect();
+            let serialized = serialize_styles(rules.iter().copied());
+            if serialized == file.saved_rules {
+                continue;
+            }
+            let (contents, rule_sources) = match splice_rules(file, &rules) {
+                Some(spliced) => spliced,
+                None => {
+                    log::warn!(
+                        "Rules were removed from {:?}, writing it anew without its comments.",
+                        file.path
+                    );
+                    rewrite_rules(file, &rules)
                 }
+            };
+            if let Err(err) = std::fs::write(&file.path, &contents) {
+                log::warn!("Failed to save the stylesheet to {:?}:", file.path);
+                log::warn!("{}", err);
+                saved = false;
+                continue;
             }
-            Err(err) => {
-                log::info!("Failed to read file at {:?}:", filename);
-                log::info!("{}", err);
-                return false;
+            file.contents = contents;
+            file.saved_rules = serialized;
+            file.rule_sources = rule_sources;
+        }
+        saved
+    }
+
+    /// Whether any rule was edited in memory since the files were last read or saved.
+    pub fn has_unsaved_edits(&self) -> bool {
+        self.files.iter().enumerate().any(|(index, file)| {
+            serialize_styles(self.rules.iter().filter(|rule| rule.file == index))
+                != file.saved_rules
+        })
+    }
+
+    /// Discards all edits made in memory by loading the rules from the files again.
+    pub fn revert(&mut self) -> bool {
+        self.try_reload(true)
+    }
+
+    /// Tries reloading the cached styles from the files.
+    ///
+    /// Unless `force` is set nothing is reloaded if none of the files changed since they were last read or saved,
+    /// so the edits made in memory are kept if a file was only touched or we just saved it ourselves.
+    ///
+    /// Returns `true` if it succeeded.
+    /// Returns `false` in any error case or if nothing was reloaded.
+    fn try_reload(&mut self, force: bool) -> bool {
+        let loaded = load_styles(&self.files[0].path);
+        if !force && loaded.files == self.files {
+            return false;
+        }
+        for (path, diagnostic) in &loaded.diagnostics {
+            log_diagnostic(&path.to_string_lossy(), diagnostic);
+        }
+        if loaded.has_errors() {
+            return false;
+        }
+
+        // An import might have been added.
+        for file in &loaded.files {
+            if self.files.iter().all(|watched| watched.path != file.path) {
+                if let Err(err) = self.watcher.watch(&file.path, RecursiveMode::NonRecursive) {
+                    log::info!("Failed to start watching {:?}:", file.path);
+                    log::info!("{}", err);
+                }
             }
         }
+        self.rules = loaded.rules;
+        self.files = loaded.files;
         true
     }
 }
@@ -161,10 +870,52 @@ pub struct Rule {
     pub selector: Selector,
     /// The key/value pairs the rule holds.
     pub kvs: BTreeMap<String, CSSValue>,
+    /// The properties declared with `!important`.
+    pub important: BTreeSet<String>,
+    /// The index of the file in `RulesCache::files` the rule was read from.
+    pub file: usize,
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {{", self.selector)?;
+        for (property, value) in &self.kvs {
+            let important = if self.important.contains(property) {
+                " !important"
+            } else {
+                ""
+            };
+            writeln!(f, "    {}: {}{};", property, value, important)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// The value of a property after the cascade.
+#[derive(Debug, Clone)]
+pub struct ComputedValue {
+    pub value: CSSValue,
+    /// The index of the rule in `RulesCache::rules` the value came from.
+    pub rule: usize,
+    /// Whether the value was declared with `!important`.
+    pub important: bool,
+}
+
+/// The properties of a selector after the cascade.
+#[derive(Debug, Clone, Default)]
+pub struct ComputedStyle {
+    pub values: BTreeMap<String, ComputedValue>,
+}
+
+impl ComputedStyle {
+    /// Returns the value of a property.
+    pub fn get(&self, property: &str) -> Option<&CSSValue> {
+        self.values.get(property).map(|computed| &computed.value)
+    }
 }
 
 /// A single CSS selector.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Selector {
     /// The type a selector matches.
     /// E.g. `"layer"`.
@@ -178,39 +929,221 @@ pub struct Selector {
     /// The name a selector matches.
     /// E.g. `"water"`.
     pub any: BTreeMap<String, String>,
+    /// The attribute tests other than equality a selector matches.
+    /// E.g. `[zoom>=10]` or `[bridge]`.
+    pub conditions: Vec<AttributeCondition>,
+    /// Whether the selector is `:root`, which matches everything and is where variables are declared.
+    pub root: bool,
+    /// The filter expression of `:where(...)` the tags of a feature have to satisfy.
+    /// E.g. `class in (motorway, trunk) and brunnel != tunnel`.
+    pub filter: Option<Filter>,
+    /// The filters of the stylesheet the tags of a classified feature satisfy.
+    ///
+    /// Only these end up in the selector of a feature instead of the tags, so the tags don't multiply the features.
+    pub satisfied: Vec<Filter>,
 }
 
-impl Default for Selector {
-    fn default() -> Self {
-        Self {
-            typ: None,
-            id: None,
-            classes: vec![],
-            any: BTreeMap::new(),
+/// How an attribute is tested by a selector.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AttributeOperator {
+    /// `[key]`, the attribute is present with any value.
+    Exists,
+    /// `[key!=value]`, also true if the attribute is missing.
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl std::fmt::Display for AttributeOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self {
+            AttributeOperator::Exists => "",
+            AttributeOperator::NotEqual => "!=",
+            AttributeOperator::Less => "<",
+            AttributeOperator::LessOrEqual => "<=",
+            AttributeOperator::Greater => ">",
+            AttributeOperator::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+/// A single attribute test of a selector.
+/// E.g. `[zoom<13]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AttributeCondition {
+    pub key: String,
+    pub operator: AttributeOperator,
+    /// The value to compare with, empty for `AttributeOperator::Exists`.
+    pub value: String,
+}
+
+impl AttributeCondition {
+    /// Checks the value of the attribute, `None` if it is missing.
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        let ordering = match (self.operator, value) {
+            (AttributeOperator::Exists, value) => return value.is_some(),
+            (AttributeOperator::NotEqual, None) => return true,
+            (_, None) => return false,
+            (_, Some(value)) => compare_values(value, &self.value),
+        };
+        match self.operator {
+            AttributeOperator::NotEqual => ordering != Some(Ordering::Equal),
+            AttributeOperator::Less => ordering == Some(Ordering::Less),
+            AttributeOperator::LessOrEqual => {
+                ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal)
+            }
+            AttributeOperator::Greater => ordering == Some(Ordering::Greater),
+            AttributeOperator::GreaterOrEqual => {
+                ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal)
+            }
+            AttributeOperator::Exists => unreachable!(),
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.operator == AttributeOperator::Exists {
+            write!(f, "[{}]", self.key)
+        } else {
+            write!(
+                f,
+                "[{}{}{}]",
+                self.key,
+                self.operator,
+                quote_value(&self.value)
+            )
+        }
+    }
+}
+
+/// Compares two attribute values, numerically if both are numbers.
+///
+/// Strings are only ever equal or unordered, so `<` and `>` never match them.
+fn compare_values(a: &str, b: &str) -> Option<Ordering> {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b),
+        _ if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+/// Quotes an attribute value if it can't be written bare.
+fn quote_value(value: &str) -> String {
+    if value.chars().all(is_attribute_char) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+/// A filter expression of a `:where(...)` selector, which can test all tags of a feature.
+/// E.g. `class in (motorway, trunk) and not (has bridge or layer > 0)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// `key = value`.
+    Equal(String, String),
+    /// `has key`, `key != value`, `key < value` and the other comparisons.
+    Condition(AttributeCondition),
+    /// `key in (value, value)`.
+    In(String, Vec<String>),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+impl Filter {
+    /// Evaluates the filter with the tags of a feature.
+    ///
+    /// Values are compared numerically if both are numbers like in attribute tests.
+    pub fn matches(&self, tags: &HashMap<String, String>) -> bool {
+        let equals = |key: &String, value: &String| {
+            tags.get(key).and_then(|tag| compare_values(tag, value)) == Some(Ordering::Equal)
+        };
+        match self {
+            Filter::Equal(key, value) => equals(key, value),
+            Filter::Condition(condition) => {
+                condition.matches(tags.get(&condition.key).map(String::as_str))
+            }
+            Filter::In(key, values) => values.iter().any(|value| equals(key, value)),
+            Filter::Not(filter) => !filter.matches(tags),
+            Filter::And(a, b) => a.matches(tags) && b.matches(tags),
+            Filter::Or(a, b) => a.matches(tags) || b.matches(tags),
+        }
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Writes a nested filter in parentheses if it binds weaker than `and`.
+        let nested = |filter: &Filter| match filter {
+            Filter::Or(..) => format!("({})", filter),
+            filter => filter.to_string(),
+        };
+        match self {
+            Filter::Equal(key, value) => write!(f, "{} = {}", key, quote_value(value)),
+            Filter::Condition(condition) if condition.operator == AttributeOperator::Exists => {
+                write!(f, "has {}", condition.key)
+            }
+            Filter::Condition(condition) => write!(
+                f,
+                "{} {} {}",
+                condition.key,
+                condition.operator,
+                quote_value(&condition.value)
+            ),
+            Filter::In(key, values) => {
+                let values = values
+                    .iter()
+                    .map(|value| quote_value(value))
+                    .collect::<Vec<_>>();
+                write!(f, "{} in ({})", key, values.join(", "))
+            }
+            Filter::Not(filter) => match **filter {
+                Filter::And(..) | Filter::Or(..) => write!(f, "not ({})", filter),
+                _ => write!(f, "not {}", filter),
+            },
+            Filter::And(a, b) => write!(f, "{} and {}", nested(a), nested(b)),
+            Filter::Or(a, b) => write!(f, "{} or {}", a, b),
         }
     }
 }
 
 impl std::fmt::Display for Selector {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut selector = self.typ.clone().unwrap_or_default();
+        let mut selector = if self.root {
+            ":root".to_string()
+        } else {
+            String::new()
+        };
+        selector += self.typ.as_deref().unwrap_or_default();
 
         if let Some(id) = self.id.as_ref() {
-            selector += id
+            selector += "#";
+            selector += id;
         }
 
         for class in &self.classes {
             selector += ".";
-            selector += &class;
+            selector += class;
         }
         for (k, v) in &self.any {
             selector += "[";
-            selector += &k;
+            selector += k;
             selector += "=";
-            selector += &v;
+            selector += &quote_value(v);
             selector += "]";
         }
-        write!(f, "({})", selector)
+        for condition in &self.conditions {
+            selector += &condition.to_string();
+        }
+        if let Some(filter) = &self.filter {
+            selector += &format!(":where({})", filter);
+        }
+        write!(f, "{}", selector)
     }
 }
 
@@ -222,6 +1155,10 @@ impl Selector {
             id: None,
             classes: vec![],
             any: BTreeMap::new(),
+            conditions: vec![],
+            root: false,
+            filter: None,
+            satisfied: vec![],
         }
     }
 
@@ -238,7 +1175,7 @@ impl Selector {
     }
 
     /// Makes the selector require the class `class`.
-    pub fn _with_class(mut self, class: impl Into<String>) -> Self {
+    pub fn with_class(mut self, class: impl Into<String>) -> Self {
         self.classes.push(class.into());
         self
     }
@@ -249,8 +1186,126 @@ impl Selector {
         self
     }
 
+    /// Makes the selector require the attribute test `key` `operator` `value`.
+    pub fn with_condition(
+        mut self,
+        key: impl Into<String>,
+        operator: AttributeOperator,
+        value: impl Into<String>,
+    ) -> Self {
+        self.conditions.push(AttributeCondition {
+            key: key.into(),
+            operator,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Makes the selector require the filter expression of `:where(...)` in addition to the one it has.
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = match self.filter.take() {
+            Some(existing) if existing != filter => {
+                Some(Filter::And(Box::new(existing), Box::new(filter)))
+            }
+            _ => Some(filter),
+        };
+        self
+    }
+
+    /// Returns how specific the selector is as the number of ids, attribute tests, classes and types.
+    ///
+    /// Comparing the tuples orders the selectors like the CSS cascade does.
+    /// Like in CSS `:where(...)` adds nothing, so a rule with a filter only wins over an equally specific one by order.
+    pub fn specificity(&self) -> (usize, usize, usize, usize) {
+        (
+            self.id.iter().count(),
+            self.any.len() + self.conditions.len(),
+            self.classes.len() + self.root as usize,
+            self.typ.iter().count(),
+        )
+    }
+
+    /// Returns why the attribute tests of the selector exclude each other, `None` if some feature may match it.
+    /// E.g. `[zoom>14][zoom<10]` or `[zoom=5][zoom>=10]`.
+    pub fn contradiction(&self) -> Option<String> {
+        for condition in &self.conditions {
+            if let Some(value) = self.any.get(&condition.key) {
+                if !condition.matches(Some(value)) {
+                    return Some(format!(
+                        "[{}={}] contradicts {}",
+                        condition.key,
+                        quote_value(value),
+                        condition
+                    ));
+                }
+            }
+        }
+
+        // The tightest lower and upper bound of every attribute compared with numbers.
+        let mut lower: BTreeMap<&str, (f64, &AttributeCondition)> = BTreeMap::new();
+        let mut upper: BTreeMap<&str, (f64, &AttributeCondition)> = BTreeMap::new();
+        for condition in &self.conditions {
+            let value = match condition.value.parse::<f64>() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let (bounds, tighter) = match condition.operator {
+                AttributeOperator::Greater | AttributeOperator::GreaterOrEqual => {
+                    (&mut lower, Ordering::Greater)
+                }
+                AttributeOperator::Less | AttributeOperator::LessOrEqual => {
+                    (&mut upper, Ordering::Less)
+                }
+                _ => continue,
+            };
+            let exclusive = |condition: &AttributeCondition| {
+                condition.operator == AttributeOperator::Greater
+                    || condition.operator == AttributeOperator::Less
+            };
+            let replace = match bounds.get(condition.key.as_str()) {
+                Some((bound, current)) => match value.partial_cmp(bound) {
+                    Some(Ordering::Equal) => exclusive(condition) && !exclusive(current),
+                    ordering => ordering == Some(tighter),
+                },
+                None => true,
+            };
+            if replace {
+                bounds.insert(&condition.key, (value, condition));
+            }
+        }
+
+        for (key, (low, low_condition)) in &lower {
+            if let Some((high, high_condition)) = upper.get(key) {
+                let empty = match low.partial_cmp(high) {
+                    Some(Ordering::Equal) => {
+                        low_condition.operator == AttributeOperator::Greater
+                            || high_condition.operator == AttributeOperator::Less
+                    }
+                    ordering => ordering == Some(Ordering::Greater),
+                };
+                if empty {
+                    return Some(format!("{} contradicts {}", low_condition, high_condition));
+                }
+            }
+        }
+        None
+    }
+
+    /// Checks if this selector matches another one at some zoom level, leaving the filter of the other one aside.
+    ///
+    /// Used to evaluate only the filters of the rules which may apply to a feature.
+    pub fn may_match(&self, other: &Selector) -> bool {
+        let mut other = other.clone();
+        other.filter = None;
+        other.any.remove("zoom");
+        other.conditions.retain(|condition| condition.key != "zoom");
+        self.matches(&other)
+    }
+
     /// Checks if a subset of criteria of this selector matches all the criteria of another.
     ///
+    /// Attribute values are compared numerically if both are numbers, so `[zoom=10]` matches `10.0`.
+    ///
     /// Use example: layer.selector.matches(&landmark_selector)`.
     pub fn matches(&self, other: &Selector) -> bool {
         if let Some(t1) = &other.typ {
@@ -275,7 +1330,7 @@ impl Selector {
 
         for (k, v) in &other.any {
             if let Some(value) = self.any.get(k) {
-                if value != v {
+                if compare_values(value, v) != Some(Ordering::Equal) {
                     return false;
                 }
             } else {
@@ -283,12 +1338,24 @@ impl Selector {
             }
         }
 
+        for condition in &other.conditions {
+            if !condition.matches(self.any.get(&condition.key).map(String::as_str)) {
+                return false;
+            }
+        }
+
         for c in &other.classes {
             if !self.classes.contains(c) {
                 return false;
             }
         }
 
+        if let Some(filter) = &other.filter {
+            if !self.satisfied.contains(filter) {
+                return false;
+            }
+        }
+
         true
     }
 }
@@ -324,41 +1391,74 @@ enum SelectorPart {
     Class(String),
     Id(String),
     Any(String, String),
+    Condition(AttributeCondition),
 }
 
-/// Parses an entire set of rules.
-fn rules<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Vec<Rule>, E>
+/// Munch all whitespace and comments before and after `f`.
+fn whitespace<'a, O, E, F>(f: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
 where
-    E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+    F: Fn(&'a str) -> IResult<&'a str, O, E>,
+    E: ParseError<&'a str>,
 {
-    many0(rule)(input)
+    delimited(blank, f, blank)
 }
 
-/// Munch all whitespace before and after `f`.
-fn whitespace<I, O, E, F>(f: F) -> impl FnMut(I) -> IResult<I, O, E>
-where
-    I: Clone + PartialEq + InputTakeAtPosition,
-    <I as InputTakeAtPosition>::Item: AsChar + Clone,
-    F: Fn(I) -> IResult<I, O, E>,
-    E: ParseError<I>,
-{
-    delimited(multispace0, f, multispace0)
+/// Skips any whitespace and comments.
+fn blank<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, (), E> {
+    map(many0(alt((map(multispace1, |_| ()), comment))), |_| ())(input)
+}
+
+/// Parses a comment.
+/// E.g. `/* water areas */`.
+fn comment<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, (), E> {
+    map(tuple((tag("/*"), take_until("*/"), tag("*/"))), |_| ())(input)
+}
+
+/// Parses an import of another stylesheet and returns its path.
+/// E.g. `@import "base.css";` or `@import url(base.css);`.
+fn import<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    delimited(tag("@import"), whitespace(import_path), char(';'))(input)
+}
+
+/// Parses the path of an import.
+/// E.g. `"base.css"` or `url(base.css)`.
+fn import_path<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    alt((
+        delimited(char('"'), take_while(|c| c != '"'), char('"')),
+        delimited(char('\''), take_while(|c| c != '\''), char('\'')),
+        delimited(
+            tag("url("),
+            take_while1(|c: char| c != ')' && !c.is_whitespace()),
+            char(')'),
+        ),
+    ))(input)
 }
 
 /// Parse a single rule.
 /// E.g. `layer[name=water]{ background-color: #FF0000; }`.
-fn rule<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Rule, E>
+fn rule<'a, E>(input: &'a str) -> IResult<&'a str, (Rule, BTreeMap<String, &'a str>), E>
 where
     E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
-    let (remaining, (selector, _, kvs, _)) = tuple((
+    let (remaining, (selector, _, (kvs, important, names), _)) = tuple((
         whitespace(selector),
         whitespace(char('{')),
         body,
-        whitespace(char('}')),
+        preceded(blank, char('}')),
     ))(input)?;
 
-    Ok((remaining, Rule { selector, kvs }))
+    Ok((
+        remaining,
+        (
+            Rule {
+                selector,
+                kvs,
+                important,
+                file: 0,
+            },
+            names,
+        ),
+    ))
 }
 
 /// Parse a single selector.
@@ -366,8 +1466,11 @@ where
 fn selector<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Selector, E> {
     let mut selector: Selector = Default::default();
 
+    let (remaining, root) = opt(tag(":root"))(input)?;
+    selector.root = root.is_some();
+
     // Try parsing the type (Html tag) of a selector.
-    let (remaining, typ) = take_while(|c| is_alphanumeric(c as u8))(input)?;
+    let (remaining, typ) = take_while(|c| is_alphanumeric(c as u8))(remaining)?;
 
     // The type is optional. So if no type was found, set the type to `None`.
     selector.typ = if !typ.is_empty() {
@@ -386,9 +1489,13 @@ fn selector<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Sele
             SelectorPart::Any(k, v) => {
                 selector.any.insert(k, v);
             }
+            SelectorPart::Condition(condition) => selector.conditions.push(condition),
         }
     }
 
+    let (remaining, filter) = opt(where_filter)(remaining)?;
+    selector.filter = filter;
+
     Ok((remaining, selector))
 }
 
@@ -406,43 +1513,214 @@ fn id<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, SelectorPa
         .map(|(r, v)| (r, SelectorPart::Id(v.into())))
 }
 
-/// Parse any CSS selector k/v pair.
-/// E.g. `[name=water]`
+/// `true` if `c` may be part of an unquoted attribute name or value.
+fn is_attribute_char(c: char) -> bool {
+    is_alphanumeric(c as u8) || c == '_' || c == '-' || c == '.' || c == ':'
+}
+
+/// Parse any CSS selector attribute test.
+/// E.g. `[name=water]`, `[zoom>=10]`, `[name:en="Lake Zurich"]` or `[bridge]`.
 fn any<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, SelectorPart, E> {
     let (remaining, _) = char('[')(input)?;
-    let (remaining, name) = take_while(|c| is_alphanumeric(c as u8))(remaining)?;
-    let (remaining, _) = char('=')(remaining)?;
-    let (remaining, value) = take_while(|c| is_alphanumeric(c as u8))(remaining)?;
-    let (remaining, _) = char(']')(remaining)?;
-    Ok((remaining, SelectorPart::Any(name.into(), value.into())))
+    let (remaining, name) = take_while1(is_attribute_char)(remaining)?;
+    let (remaining, test) = alt((
+        map(char(']'), |_| None),
+        map(
+            tuple((attribute_operator, attribute_value, char(']'))),
+            |(operator, value, _)| Some((operator, value)),
+        ),
+    ))(remaining)?;
+
+    let part = match test {
+        None => SelectorPart::Condition(AttributeCondition {
+            key: name.into(),
+            operator: AttributeOperator::Exists,
+            value: String::new(),
+        }),
+        Some((None, value)) => SelectorPart::Any(name.into(), value.into()),
+        Some((Some(operator), value)) => SelectorPart::Condition(AttributeCondition {
+            key: name.into(),
+            operator,
+            value: value.into(),
+        }),
+    };
+    Ok((remaining, part))
+}
+
+/// Parse the comparison of an attribute test, `None` for equality.
+fn attribute_operator<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, Option<AttributeOperator>, E> {
+    alt((
+        map(tag(">="), |_| Some(AttributeOperator::GreaterOrEqual)),
+        map(tag("<="), |_| Some(AttributeOperator::LessOrEqual)),
+        map(tag("!="), |_| Some(AttributeOperator::NotEqual)),
+        map(tag("="), |_| None),
+        map(tag(">"), |_| Some(AttributeOperator::Greater)),
+        map(tag("<"), |_| Some(AttributeOperator::Less)),
+    ))(input)
+}
+
+/// Parse an attribute value which is either bare or in quotes.
+/// E.g. `10` or `"Lake Zurich"`.
+fn attribute_value<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    alt((
+        delimited(char('"'), take_while(|c| c != '"'), char('"')),
+        delimited(char('\''), take_while(|c| c != '\''), char('\'')),
+        take_while(is_attribute_char),
+    ))(input)
 }
 
+/// Parses the filter expression of a selector.
+/// E.g. `:where(class in (motorway, trunk) and brunnel != tunnel)`.
+fn where_filter<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Filter, E> {
+    delimited(tag(":where("), filter, char(')'))(input)
+}
+
+/// Parses a filter expression in which `not` binds stronger than `and` and that stronger than `or`.
+fn filter<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Filter, E> {
+    let (remaining, first) = filter_and(input)?;
+    fold_many0(preceded(keyword("or"), filter_and), first, |a, b| {
+        Filter::Or(Box::new(a), Box::new(b))
+    })(remaining)
+}
+
+/// Parses filters joined by `and`.
+fn filter_and<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Filter, E> {
+    let (remaining, first) = filter_not(input)?;
+    fold_many0(preceded(keyword("and"), filter_not), first, |a, b| {
+        Filter::And(Box::new(a), Box::new(b))
+    })(remaining)
+}
+
+/// Parses a filter which may be negated by `not`.
+fn filter_not<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Filter, E> {
+    alt((
+        map(preceded(keyword("not"), filter_not), |filter| {
+            Filter::Not(Box::new(filter))
+        }),
+        delimited(whitespace(char('(')), filter, whitespace(char(')'))),
+        map(preceded(keyword("has"), filter_key), |key| {
+            Filter::Condition(AttributeCondition {
+                key: key.into(),
+                operator: AttributeOperator::Exists,
+                value: String::new(),
+            })
+        }),
+        filter_test,
+    ))(input)
+}
+
+/// Parses a test of a single tag.
+/// E.g. `layer >= 1`, `brunnel != tunnel` or `class not in (path, track)`.
+fn filter_test<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Filter, E> {
+    let (remaining, key) = filter_key(input)?;
+    alt((
+        map(preceded(keyword("in"), filter_values), move |values| {
+            Filter::In(key.into(), values)
+        }),
+        map(
+            preceded(tuple((keyword("not"), keyword("in"))), filter_values),
+            move |values| Filter::Not(Box::new(Filter::In(key.into(), values))),
+        ),
+        map(
+            tuple((whitespace(attribute_operator), filter_value)),
+            move |(operator, value)| match operator {
+                None => Filter::Equal(key.into(), value.into()),
+                Some(operator) => Filter::Condition(AttributeCondition {
+                    key: key.into(),
+                    operator,
+                    value: value.into(),
+                }),
+            },
+        ),
+    ))(remaining)
+}
+
+/// Parses the name of a tag in a filter.
+fn filter_key<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    whitespace(take_while1(is_attribute_char))(input)
+}
+
+/// Parses a value in a filter, which unlike in an attribute test can't be empty unless quoted.
+fn filter_value<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    delimited(
+        blank,
+        alt((
+            delimited(char('"'), take_while(|c| c != '"'), char('"')),
+            delimited(char('\''), take_while(|c| c != '\''), char('\'')),
+            take_while1(is_attribute_char),
+        )),
+        blank,
+    )(input)
+}
+
+/// Parses the list of values of `in`.
+/// E.g. `(motorway, trunk)`.
+fn filter_values<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Vec<String>, E> {
+    delimited(
+        whitespace(char('(')),
+        separated_list1(whitespace(char(',')), map(filter_value, String::from)),
+        whitespace(char(')')),
+    )(input)
+}
+
+/// Parses a keyword of the filter expressions which can not be the start of a tag name.
+fn keyword<'a, E: ParseError<&'a str>>(
+    word: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str, E> {
+    delimited(
+        blank,
+        terminated(tag(word), not(satisfy(is_attribute_char))),
+        blank,
+    )
+}
+
+/// The declarations of a rule body, the names of the ones marked `!important`
+/// and the property name of the last declaration of every property as it appears in the input.
+type Declarations<'a> = (
+    BTreeMap<String, CSSValue>,
+    BTreeSet<String>,
+    BTreeMap<String, &'a str>,
+);
+
 /// Parses the body of a CSS rule.
 /// E.g. `{}`.
-fn body<'a, E: ParseError<&'a str>>(
-    input: &'a str,
-) -> IResult<&'a str, std::collections::BTreeMap<String, CSSValue>, E>
+/// Returns the declarations, the names of the ones marked `!important` and where their names are.
+fn body<'a, E>(input: &'a str) -> IResult<&'a str, Declarations<'a>, E>
 where
     E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
-    let mut hm = std::collections::BTreeMap::new();
+    let mut hm = BTreeMap::new();
+    let mut important = BTreeSet::new();
+    let mut names = BTreeMap::new();
     many0(kv)(input).map(|v| {
-        v.1.into_iter().for_each(|v| {
-            hm.insert(v.0.into(), v.1);
+        v.1.into_iter().for_each(|((k, v), is_important)| {
+            // A later declaration of the same property replaces the earlier one including its priority.
+            if is_important {
+                important.insert(k.to_string());
+            } else {
+                important.remove(k);
+            }
+            hm.insert(k.into(), v);
+            names.insert(k.into(), k);
         });
-        (v.0, hm)
+        (v.0, (hm, important, names))
     })
 }
 
-/// Parses a single CSS k/v pair.
-/// E.g. `background-color: #FF0000;`.
-fn kv<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, (&'a str, CSSValue), E>
+/// Parses a single CSS k/v pair and whether it is marked `!important`.
+/// E.g. `background-color: #FF0000 !important;`.
+fn kv<'a, E>(input: &'a str) -> IResult<&'a str, ((&'a str, CSSValue), bool), E>
 where
     E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
-    let (remaining, (kv, _)) =
-        tuple((separated_pair(css_name, char(':'), css_value), char(';')))(input)?;
-    Ok((remaining, kv))
+    let (remaining, (kv, important, _)) = tuple((
+        separated_pair(css_name, char(':'), css_value),
+        opt(whitespace(tag("!important"))),
+        char(';'),
+    ))(input)?;
+    Ok((remaining, (kv, important.is_some())))
 }
 
 /// Parses a CSS qualified name.
@@ -453,13 +1731,23 @@ fn css_name<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a
 
 /// Parses a single CSS qualified value.
 fn css_value<'a, E>(input: &'a str) -> IResult<&'a str, CSSValue, E>
+where
+    E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+{
+    alt((whitespace(interpolate), stop_value))(input)
+}
+
+/// Parses a single CSS value which is the same at all zoom levels.
+fn stop_value<'a, E>(input: &'a str) -> IResult<&'a str, CSSValue, E>
 where
     E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
     alt((
+        whitespace(variable),
         whitespace(hex_color),
         whitespace(rgba_color),
         whitespace(rgb_color),
+        whitespace(hsl_color),
         whitespace(px_value),
         whitespace(world_value),
         whitespace(unitless_value),
@@ -467,10 +1755,76 @@ where
     ))(input)
 }
 
+/// Parses a reference to a variable with an optional fallback.
+/// E.g. `var(--water)` or `var(--water, blue)`.
+fn variable<'a, E>(input: &'a str) -> IResult<&'a str, CSSValue, E>
+where
+    E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+{
+    let (input, (_, name, fallback, _)) = tuple((
+        tag("var("),
+        whitespace(variable_name),
+        opt(preceded(char(','), stop_value)),
+        char(')'),
+    ))(input)?;
+    Ok((
+        input,
+        CSSValue::Var(name.to_string(), fallback.map(Box::new)),
+    ))
+}
+
+/// Parses the name of a variable including the leading `--`.
+/// E.g. `--water`.
+fn variable_name<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(preceded(
+        tag("--"),
+        take_while1(|c| is_alphanumeric(c as u8) || c == '-' || c == '_'),
+    ))(input)
+}
+
+/// Parses the type of an interpolation into the base of the exponential curve.
+/// E.g. `linear` or `exponential(1.5)`.
+fn interpolation_base<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, f32, E> {
+    alt((
+        map(tag("linear"), |_| 1.0),
+        delimited(tag("exponential("), whitespace(float), char(')')),
+    ))(input)
+}
+
+/// Parses a value interpolated between zoom levels.
+/// E.g. `interpolate(zoom, 10: 1px, 14: 4px)` or `interpolate(exponential(1.5), zoom, 10: 1px, 14: 4px)`.
+fn interpolate<'a, E>(input: &'a str) -> IResult<&'a str, CSSValue, E>
+where
+    E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+{
+    let (input, _) = tag("interpolate(")(input)?;
+    let (input, base) = opt(terminated(whitespace(interpolation_base), char(',')))(input)?;
+    let (input, _) = whitespace(tag("zoom"))(input)?;
+    let (input, _) = char(',')(input)?;
+    let (input, mut stops) = separated_list1(
+        char(','),
+        map(
+            tuple((whitespace(float), char(':'), stop_value)),
+            |(zoom, _, value)| (zoom, value),
+        ),
+    )(input)?;
+    let (input, _) = char(')')(input)?;
+
+    stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    Ok((
+        input,
+        CSSValue::Interpolate(Interpolation {
+            base: base.unwrap_or(1.0),
+            stops,
+        }),
+    ))
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Number {
     Px(f32),
     Unitless(f32),
+    /// A length which scales with the map, see `world_to_px`.
     World(f32),
 }
 
@@ -482,6 +1836,149 @@ pub enum CSSValue {
     /// Represents a color.
     Color(Color),
     Number(Number),
+    /// A value that changes with the zoom level.
+    Interpolate(Interpolation),
+    /// A reference to a variable and the value to use if it is not declared.
+    /// E.g. `var(--water, blue)`.
+    Var(String, Option<Box<CSSValue>>),
+}
+
+impl CSSValue {
+    /// Returns the color of a color value or a named color.
+    pub fn to_color(&self) -> Option<Color> {
+        match self {
+            CSSValue::Color(color) => Some(color.clone()),
+            CSSValue::String(name) => Color::from_name(name),
+            _ => None,
+        }
+    }
+
+    /// Replaces all references to variables with their values.
+    ///
+    /// Returns `None` if a variable is neither declared nor has a fallback or if variables reference each other in a cycle.
+    pub fn resolve(&self, variables: &BTreeMap<String, CSSValue>) -> Option<CSSValue> {
+        self.resolve_with_depth(variables, 0)
+    }
+
+    fn resolve_with_depth(
+        &self,
+        variables: &BTreeMap<String, CSSValue>,
+        depth: usize,
+    ) -> Option<CSSValue> {
+        // Deeper nesting than any sensible stylesheet has means there is a cycle.
+        if depth > 32 {
+            return None;
+        }
+        match self {
+            CSSValue::Var(name, fallback) => variables
+                .get(name)
+                .and_then(|value| value.resolve_with_depth(variables, depth + 1))
+                .or_else(|| fallback.as_ref()?.resolve_with_depth(variables, depth + 1)),
+            CSSValue::Interpolate(interpolation) => Some(CSSValue::Interpolate(Interpolation {
+                base: interpolation.base,
+                stops: interpolation
+                    .stops
+                    .iter()
+                    .map(|(zoom, value)| {
+                        Some((*zoom, value.resolve_with_depth(variables, depth + 1)?))
+                    })
+                    .collect::<Option<_>>()?,
+            })),
+            value => Some(value.clone()),
+        }
+    }
+
+    /// Returns the value at the given zoom level.
+    ///
+    /// Only interpolated values depend on the zoom, all others are returned as they are.
+    pub fn evaluate(&self, zoom: f32) -> CSSValue {
+        match self {
+            CSSValue::Interpolate(interpolation) => interpolation.evaluate(zoom),
+            value => value.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Px(value) => write!(f, "{}px", value),
+            Number::Unitless(value) => write!(f, "{}", value),
+            Number::World(value) => write!(f, "{}w", value),
+        }
+    }
+}
+
+impl std::fmt::Display for CSSValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CSSValue::String(value) => write!(f, "{}", value),
+            CSSValue::Color(color) => write!(f, "{}", color),
+            CSSValue::Number(number) => write!(f, "{}", number),
+            CSSValue::Var(name, None) => write!(f, "var({})", name),
+            CSSValue::Var(name, Some(fallback)) => write!(f, "var({}, {})", name, fallback),
+            CSSValue::Interpolate(interpolation) => {
+                write!(f, "interpolate(")?;
+                if (interpolation.base - 1.0).abs() > f32::EPSILON {
+                    write!(f, "exponential({}), ", interpolation.base)?;
+                }
+                write!(f, "zoom")?;
+                for (zoom, value) in &interpolation.stops {
+                    write!(f, ", {}: {}", zoom, value)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// A value interpolated between stops at different zoom levels.
+#[derive(Debug, Clone)]
+pub struct Interpolation {
+    /// The base of the exponential curve between two stops, `1.0` interpolates linearly.
+    ///
+    /// Bases above one make the value change faster towards the higher zoom level like the scale of the map does.
+    pub base: f32,
+    /// The zoom levels and the values at them in ascending order.
+    pub stops: Vec<(f32, CSSValue)>,
+}
+
+impl Interpolation {
+    /// Returns the value at the given zoom level.
+    ///
+    /// Outside of the stops the value of the nearest stop is used. Numbers of the same unit and colors are
+    /// interpolated, all other values switch at the stop.
+    pub fn evaluate(&self, zoom: f32) -> CSSValue {
+        let upper = match self.stops.iter().position(|(z, _)| *z > zoom) {
+            Some(0) => return self.stops[0].1.clone(),
+            Some(upper) => upper,
+            None => return self.stops[self.stops.len() - 1].1.clone(),
+        };
+        let (z0, v0) = &self.stops[upper - 1];
+        let (z1, v1) = &self.stops[upper];
+        let t = if (self.base - 1.0).abs() < 1e-6 {
+            (zoom - z0) / (z1 - z0)
+        } else {
+            (self.base.powf(zoom - z0) - 1.0) / (self.base.powf(z1 - z0) - 1.0)
+        };
+
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        match (v0, v1) {
+            (CSSValue::Number(Number::Px(a)), CSSValue::Number(Number::Px(b))) => {
+                CSSValue::Number(Number::Px(lerp(*a, *b)))
+            }
+            (CSSValue::Number(Number::World(a)), CSSValue::Number(Number::World(b))) => {
+                CSSValue::Number(Number::World(lerp(*a, *b)))
+            }
+            (CSSValue::Number(Number::Unitless(a)), CSSValue::Number(Number::Unitless(b))) => {
+                CSSValue::Number(Number::Unitless(lerp(*a, *b)))
+            }
+            (a, b) => match (a.to_color(), b.to_color()) {
+                (Some(a), Some(b)) => CSSValue::Color(a.interpolate(&b, t)),
+                _ => a.clone(),
+            },
+        }
+    }
 }
 
 /// Parses a single CSS qualified string.
@@ -491,7 +1988,7 @@ fn string<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, CSSVal
         is_alphanumeric(c as u8) || c == '-' || c == ' '
     }))(input)?;
 
-    Ok((input, CSSValue::String(value.into())))
+    Ok((input, CSSValue::String(value.trim().into())))
 }
 
 /// Parses a single CSS px value.
@@ -561,6 +2058,270 @@ impl Color {
     };
 }
 
+/// The named colors of CSS as `0xrrggbb` sorted by name.
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+impl Color {
+    /// Looks up one of the 148 named colors of CSS or `transparent`, ignoring the case.
+    pub fn from_name(name: &str) -> Option<Color> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Some(Color::TRANSPARENT);
+        }
+        let index = NAMED_COLORS
+            .binary_search_by(|(n, _)| (*n).cmp(&name[..]))
+            .ok()?;
+        let rgb = NAMED_COLORS[index].1;
+        Some(Color {
+            r: (rgb >> 16 & 0xff) as f32 / 255.0,
+            g: (rgb >> 8 & 0xff) as f32 / 255.0,
+            b: (rgb & 0xff) as f32 / 255.0,
+            a: 1.0,
+        })
+    }
+
+    /// Converts a color from the HSL color space with the hue in degrees and the saturation and lightness in `[0, 1]`.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Color {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let a = saturation * lightness.min(1.0 - lightness);
+        let channel = |n: f32| {
+            let k = (n + hue / 30.0).rem_euclid(12.0);
+            lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+        Color {
+            r: channel(0.0),
+            g: channel(8.0),
+            b: channel(4.0),
+            a: alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Mixes two colors in the Oklab color space, so the steps look even to the eye.
+    ///
+    /// `t` goes from `0.0` for `self` to `1.0` for `other`. The alpha is mixed linearly.
+    pub fn interpolate(&self, other: &Color, t: f32) -> Color {
+        let (l0, a0, b0) = self.to_oklab();
+        let (l1, a1, b1) = other.to_oklab();
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Color::from_oklab(
+            lerp(l0, l1),
+            lerp(a0, a1),
+            lerp(b0, b1),
+            lerp(self.a, other.a),
+        )
+    }
+
+    fn to_oklab(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+        );
+        let l = (0.412_221_47 * r + 0.536_332_54 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        (
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        )
+    }
+
+    fn from_oklab(l: f32, a: f32, b: f32, alpha: f32) -> Color {
+        let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+        Color {
+            r: linear_to_srgb(4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_),
+            g: linear_to_srgb(-1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_),
+            b: linear_to_srgb(-0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_),
+            a: alpha,
+        }
+    }
+}
+
+impl std::fmt::Display for Color {
+    /// Writes `rgb(...)` for opaque colors and `rgba(...)` otherwise, the channels rounded to bytes.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let (r, g, b) = (byte(self.r), byte(self.g), byte(self.b));
+        if self.a >= 1.0 {
+            write!(f, "rgb({}, {}, {})", r, g, b)
+        } else {
+            write!(f, "rgba({}, {}, {}, {})", r, g, b, self.a.max(0.0))
+        }
+    }
+}
+
+/// Converts a sRGB channel to linear light.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a channel in linear light to sRGB.
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 /// Converts a hex string into an `u8`.
 fn from_hex(input: &str) -> Result<u8, std::num::ParseIntError> {
     u8::from_str_radix(input, 16)
@@ -568,42 +2329,70 @@ fn from_hex(input: &str) -> Result<u8, std::num::ParseIntError> {
 
 /// `true` if `c` is a hexadecimal valid digit.
 fn is_hex_digit(c: char) -> bool {
-    c.is_digit(16)
-}
-
-/// Parse an actual hex code.
-fn hex_primary<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, u8, E>
-where
-    E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
-{
-    map_res(take_while_m_n(2, 2, is_hex_digit), from_hex)(input)
+    c.is_ascii_hexdigit()
 }
 
 /// Parse a single hex color code including the `#`.
-fn hex_color<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, CSSValue, E>
+/// E.g. `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+fn hex_color<'a, E>(input: &'a str) -> IResult<&'a str, CSSValue, E>
 where
     E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
     let (input, _) = tag("#")(input)?;
-    let (input, (r, g, b)) = tuple((hex_primary, hex_primary, hex_primary))(input)?;
+    let (remaining, digits) = take_while_m_n(3, 8, is_hex_digit)(input)?;
+    let channels = match digits.len() {
+        // The short forms repeat every digit, so `#f80` is `#ff8800`.
+        3 | 4 => digits
+            .chars()
+            .map(|c| from_hex(&c.to_string().repeat(2)))
+            .collect::<Result<Vec<_>, _>>(),
+        6 | 8 => (0..digits.len())
+            .step_by(2)
+            .map(|i| from_hex(&digits[i..i + 2]))
+            .collect(),
+        _ => return Err(Err::Error(E::from_error_kind(input, ErrorKind::HexDigit))),
+    }
+    .map_err(|err| Err::Error(E::from_external_error(input, ErrorKind::HexDigit, err)))?;
 
     Ok((
-        input,
+        remaining,
         CSSValue::Color(Color {
-            r: r as f32 / 255.0,
-            g: g as f32 / 255.0,
-            b: b as f32 / 255.0,
-            a: 1.0,
+            r: channels[0] as f32 / 255.0,
+            g: channels[1] as f32 / 255.0,
+            b: channels[2] as f32 / 255.0,
+            a: channels.get(3).map_or(1.0, |a| *a as f32 / 255.0),
         }),
     ))
 }
 
+/// Parse a color in the HSL color space with an optional alpha.
+/// E.g. `hsl(200, 80%, 45%)` or `hsla(200deg, 80%, 45%, 0.5)`.
+fn hsl_color<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, CSSValue, E> {
+    let (input, _) = alt((tag("hsla("), tag("hsl(")))(input)?;
+    let (input, (h, _, _, s, _, _, l, _, a, _)) = tuple((
+        whitespace(float),
+        opt(tag("deg")),
+        whitespace(char(',')),
+        float,
+        char('%'),
+        whitespace(char(',')),
+        float,
+        char('%'),
+        opt(preceded(whitespace(char(',')), float)),
+        whitespace(char(')')),
+    ))(input)?;
+    Ok((
+        input,
+        CSSValue::Color(Color::from_hsl(h, s / 100.0, l / 100.0, a.unwrap_or(1.0))),
+    ))
+}
+
 fn u8<'a, E>(input: &'a str) -> IResult<&'a str, u8, E>
 where
     E: ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
     use std::str::FromStr;
-    map_res(take_while(|c: char| c.is_digit(10)), u8::from_str)(input)
+    map_res(take_while(|c: char| c.is_ascii_digit()), u8::from_str)(input)
 }
 
 /// Parse a single hex color code including the `#`.
@@ -634,7 +2423,7 @@ where
 }
 
 /// Parse a single hex color code including the `#`.
-fn rgb_color<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, CSSValue, E>
+fn rgb_color<'a, E>(input: &'a str) -> IResult<&'a str, CSSValue, E>
 where
     E: ParseError<&'a str> + ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
@@ -652,3 +2441,408 @@ where
         }),
     ))
 }
+
+#[test]
+fn attribute_conditions() {
+    let rules = try_parse_styles(
+        r#"
+        layer[name=transportation][zoom>=10][zoom<13] { line-width: 1px; }
+        layer[name:en="Lake Zurich"][bridge] { line-width: 2px; }
+        layer[layer!=-1][zoom<=14.5] { line-width: 3px; }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(rules.len(), 3);
+    assert_eq!(
+        rules[1].selector.to_string(),
+        r#"layer[name:en="Lake Zurich"][bridge]"#
+    );
+
+    let road = |zoom: &str| {
+        Selector::new()
+            .with_type("layer")
+            .with_any("name", "transportation")
+            .with_any("zoom", zoom)
+    };
+    assert!(!road("9").matches(&rules[0].selector));
+    assert!(road("10.0").matches(&rules[0].selector));
+    assert!(road("12").matches(&rules[0].selector));
+    assert!(!road("13").matches(&rules[0].selector));
+
+    let lake = Selector::new()
+        .with_type("layer")
+        .with_any("name:en", "Lake Zurich");
+    assert!(!lake.matches(&rules[1].selector));
+    assert!(lake
+        .clone()
+        .with_any("bridge", "true")
+        .matches(&rules[1].selector));
+
+    // A missing attribute is never equal to a value.
+    assert!(road("14").matches(&rules[2].selector));
+    assert!(!road("14")
+        .with_any("layer", "-1")
+        .matches(&rules[2].selector));
+    assert!(!road("15").matches(&rules[2].selector));
+}
+
+#[test]
+fn zoom_interpolation() {
+    let rules = try_parse_styles(
+        r#"
+        layer {
+            line-width: interpolate(zoom, 10: 1px, 14: 4px);
+            border-width: interpolate(exponential(2), zoom, 14: 4px, 10: 1px);
+            background-color: interpolate(linear, zoom, 0: #ffffff, 10: rgb(0, 0, 0));
+        }
+        "#,
+    )
+    .unwrap();
+    let px = |name: &str, zoom: f32| match rules[0].kvs[name].evaluate(zoom) {
+        CSSValue::Number(Number::Px(px)) => px,
+        value => panic!("{:?}", value),
+    };
+    assert_eq!(px("line-width", 8.0), 1.0);
+    assert!((px("line-width", 12.0) - 2.5).abs() < 1e-6);
+    assert_eq!(px("line-width", 15.0), 4.0);
+    // Half way on an exponential curve with base two covers a fifth of the range.
+    assert!((px("border-width", 12.0) - 1.6).abs() < 1e-6);
+
+    let color = |zoom: f32| match rules[0].kvs["background-color"].evaluate(zoom) {
+        CSSValue::Color(color) => color,
+        value => panic!("{:?}", value),
+    };
+    assert_eq!(color(-1.0), Color::WHITE);
+    assert!((color(0.0).b - 1.0).abs() < 1e-3);
+    // The perceptual middle between white and black is lighter than a linear blend of the light.
+    let gray = color(5.0);
+    assert!((gray.r - 0.389).abs() < 0.01 && (gray.r - gray.b).abs() < 1e-3);
+    assert!((Color::RED.interpolate(&Color::BLUE, 0.0).r - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn cascade_by_specificity() {
+    let path = std::env::temp_dir().join(format!("sailor_cascade_test_{}.css", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+        layer[name=water].lake { background-color: #0000ff; line-width: 2px; }
+        layer[name=water] { background-color: #ff0000; line-width: 1px !important; }
+        layer { display: none; }
+        .lake { display: block; }
+        "#,
+    )
+    .unwrap();
+    let cache = RulesCache::try_load_from_file(path.to_string_lossy()).unwrap();
+
+    let lake = Selector::new()
+        .with_type("layer")
+        .with_any("name", "water")
+        .with_class("lake");
+    let style = cache.compute_style(&lake);
+    // The more specific rule wins although it comes first.
+    assert_eq!(style.values["background-color"].rule, 0);
+    // Unless the other declaration is important.
+    assert_eq!(style.values["line-width"].rule, 1);
+    assert!(style.values["line-width"].important);
+    // A class is more specific than a type.
+    assert!(matches!(style.get("display"), Some(CSSValue::String(value)) if value == "block"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn comments_and_colors() {
+    let rules = try_parse_styles(
+        r#"
+        /* The sea. */
+        layer[name=water] {
+            background-color: #0af; /* short */
+            border-color: #00aaff80;
+            /* line-width: 1px; */
+        }
+        layer { background-color: hsl(120, 100%, 25%); border-color: hsla(240deg, 100%, 50%, 0.5); }
+        ais { background-color: RebeccaPurple; border-color: green; }
+        /* The end. */
+        "#,
+    )
+    .unwrap();
+    assert_eq!(rules.len(), 3);
+    assert!(!rules[0].kvs.contains_key("line-width"));
+
+    let color = |rule: usize, property: &str| rules[rule].kvs[property].to_color().unwrap();
+    let rgba = |r: u8, g: u8, b: u8, a: f32| Color {
+        r: r as f32 / 255.0,
+        g: g as f32 / 255.0,
+        b: b as f32 / 255.0,
+        a,
+    };
+    assert_eq!(color(0, "background-color"), rgba(0, 0xaa, 0xff, 1.0));
+    assert_eq!(color(0, "border-color"), rgba(0, 0xaa, 0xff, 128.0 / 255.0));
+    assert_eq!(
+        color(1, "background-color"),
+        Color {
+            g: 0.5,
+            ..Color::BLACK
+        }
+    );
+    assert_eq!(
+        color(1, "border-color"),
+        Color {
+            a: 0.5,
+            ..Color::BLUE
+        }
+    );
+    assert_eq!(color(2, "background-color"), rgba(0x66, 0x33, 0x99, 1.0));
+    assert_eq!(color(2, "border-color"), rgba(0, 0x80, 0, 1.0));
+
+    assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(Color::from_name("transparent"), Some(Color::TRANSPARENT));
+    assert_eq!(Color::from_name("sea"), None);
+}
+
+#[test]
+fn style_diagnostics() {
+    let (rules, diagnostics) = parse_styles(
+        "layer[name=water] {\n    backgroud-color: red;\n    line-width: blue;\n}\n\
+         layer[zoom>14][zoom<10] { display: hidden; }\n\
+         layer[zoom=5][zoom>=10] { z-index: 2px; }\n\
+         layer[zoom>=10][zoom<=10] { background-color: sea; }\n",
+    );
+    assert_eq!(rules.len(), 4);
+    let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "2:5: warning: unknown property 'backgroud-color', did you mean 'background-color'?",
+            "3:5: warning: invalid value for 'line-width', expected a length in px or w but found 'blue'",
+            "5:1: warning: the selector 'layer[zoom>14][zoom<10]' never matches, [zoom>14] contradicts [zoom<10]",
+            "5:27: warning: invalid value for 'display', expected one of 'none', 'block' but found 'hidden'",
+            "6:1: warning: the selector 'layer[zoom=5][zoom>=10]' never matches, [zoom=5] contradicts [zoom>=10]",
+            "6:27: warning: invalid value for 'z-index', expected a number without unit but found a length in px",
+            "7:29: warning: invalid value for 'background-color', unknown color 'sea'",
+        ]
+    );
+    assert!(try_parse_styles("layer { backgroud-color: red; }").is_some());
+    // The location is that of the declaration, not of the same text in a comment or another property.
+    let (_, diagnostics) = parse_styles("ais { /* width */ border-width: 1px; width: 2px; }");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 38));
+
+    // Syntax errors are located and the parser carries on after the broken rule.
+    let (rules, diagnostics) =
+        parse_styles("layer {\n  line-width: 2px\n}\nais { border-width: 1px; }\n/* never closed");
+    assert_eq!(rules.len(), 1);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 3));
+    assert_eq!(diagnostics[0].message, "invalid value for 'line-width'");
+    assert_eq!(
+        diagnostics[1].to_string(),
+        "5:1: error: unterminated comment"
+    );
+    assert!(try_parse_styles("layer { line-width: 2px }").is_none());
+}
+
+#[test]
+fn serialize_round_trip() {
+    let style = r#"
+        layer[name=water].lake#sea { background-color: rgba(14, 181, 223, 0.952); border-width: 1px; }
+        layer[name="Lake Zurich"][zoom>=13][bridge] { line-width: interpolate(exponential(1.5), zoom, 8: 1px, 14: 4w) !important; }
+        ais { border-color: #0af; display: none; z-index: 3; }
+        "#;
+    let rules = try_parse_styles(style).unwrap();
+    let serialized = serialize_styles(&rules);
+    assert!(serialized.starts_with(
+        "layer#sea.lake[name=water] {\n    background-color: rgba(14, 181, 223, 0.952);\n"
+    ));
+    assert!(serialized.contains(
+        "    line-width: interpolate(exponential(1.5), zoom, 8: 1px, 14: 4w) !important;\n"
+    ));
+
+    let reparsed = try_parse_styles(&serialized).unwrap();
+    assert_eq!(serialize_styles(&reparsed), serialized);
+    for (rule, reparsed) in rules.iter().zip(&reparsed) {
+        assert_eq!(rule.selector, reparsed.selector);
+        assert_eq!(rule.important, reparsed.important);
+        assert_eq!(format!("{:?}", rule.kvs), format!("{:?}", reparsed.kvs));
+    }
+
+    // Saving does not make the watcher reload the file and revert drops the edits.
+    let path =
+        std::env::temp_dir().join(format!("sailor_serialize_test_{}.css", std::process::id()));
+    let style = format!("/* The chart colors. */\n{}", style);
+    std::fs::write(&path, &style).unwrap();
+    let mut cache = RulesCache::try_load_from_file(path.to_string_lossy()).unwrap();
+    assert!(!cache.has_unsaved_edits());
+    cache.rules[2]
+        .kvs
+        .insert("z-index".into(), CSSValue::Number(Number::Unitless(5.0)));
+    assert!(cache.has_unsaved_edits());
+    assert!(cache.save());
+    assert!(!cache.has_unsaved_edits());
+    assert!(!cache.try_reload(false));
+    // Only the edited rule is written anew.
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("z-index: 5;"));
+    assert!(saved.starts_with(&style[..style.find("ais").unwrap()]));
+    assert!(saved.ends_with("}\n        "));
+    cache.add_rule(
+        try_parse_styles("layer { display: none; }")
+            .unwrap()
+            .remove(0),
+    );
+    assert!(cache.save());
+    let appended = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        appended,
+        format!("{}\n\nlayer {{\n    display: none;\n}}\n", saved.trim_end())
+    );
+    assert_eq!(load_styles(&path).rules.len(), 4);
+    cache.rules.pop();
+    cache.rules[2].kvs.clear();
+    assert!(cache.revert());
+    assert_eq!(cache.rules[2].kvs.len(), 3);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn variables_and_imports() {
+    let dir = std::env::temp_dir().join(format!("sailor_import_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("base.css"),
+        "layer[name=water] { background-color: var(--water); line-width: var(--width, 2px); }\n\
+         layer[name=park] { background-color: var(--park); }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("night.css"),
+        "@import \"base.css\";\n:root { --water: #000080; }\nlayer[name=water] { --width: 1px; }\n",
+    )
+    .unwrap();
+
+    let cache = RulesCache::try_load_from_file(dir.join("night.css").to_string_lossy()).unwrap();
+    assert_eq!(cache.files.len(), 2);
+    assert!(cache.files[1].path.ends_with("base.css"));
+    // The imported rules come first.
+    assert_eq!(cache.rules[0].file, 1);
+    assert!(cache.rules[2].selector.root);
+
+    let water = cache.compute_style(&Selector::new().with_type("layer").with_any("name", "water"));
+    assert_eq!(
+        water.get("background-color").unwrap().to_color(),
+        Some(Color::from_name("navy").unwrap())
+    );
+    assert!(matches!(water.get("line-width"), Some(CSSValue::Number(Number::Px(w))) if *w == 1.0));
+    // An undeclared variable without fallback drops the property.
+    let park = cache.compute_style(&Selector::new().with_type("layer").with_any("name", "park"));
+    assert!(park.get("background-color").is_none());
+
+    let cycle = CSSValue::Var("--a".into(), None);
+    let variables = vec![
+        ("--a".to_string(), CSSValue::Var("--b".into(), None)),
+        ("--b".to_string(), cycle.clone()),
+    ];
+    assert!(cycle.resolve(&variables.into_iter().collect()).is_none());
+
+    // Saving writes each rule back to its own file and leaves the files without edits alone.
+    let night = std::fs::read_to_string(dir.join("night.css")).unwrap();
+    let mut cache = cache;
+    cache.rules[0]
+        .kvs
+        .insert("z-index".into(), CSSValue::Number(Number::Unitless(2.0)));
+    assert!(cache.save());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("night.css")).unwrap(),
+        night
+    );
+    assert!(std::fs::read_to_string(dir.join("base.css"))
+        .unwrap()
+        .contains("z-index: 2;"));
+    assert!(!cache.try_reload(false));
+    assert_eq!(load_styles(dir.join("night.css")).rules.len(), 4);
+
+    std::fs::write(dir.join("base.css"), "@import 'night.css';\n").unwrap();
+    let loaded = load_styles(dir.join("night.css"));
+    assert_eq!(loaded.diagnostics.len(), 1);
+    assert!(loaded.diagnostics[0].0.ends_with("base.css"));
+    assert_eq!(
+        loaded.diagnostics[0].1.to_string(),
+        "1:1: error: 'night.css' imports itself"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn where_filters() {
+    let path = std::env::temp_dir().join(format!("sailor_where_test_{}.css", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+        layer[name=transportation] { line-width: 1px; }
+        layer[name=transportation]:where(class in (motorway, trunk) and brunnel != tunnel) { line-width: 4px; }
+        layer[name=transportation]:where(not (has bridge or layer > 0)) { display: none; }
+        layer[name=water]:where( class = lake ) { display: none; }
+        "#,
+    )
+    .unwrap();
+    let cache = RulesCache::try_load_from_file(path.to_string_lossy()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        cache.rules[1].selector.to_string(),
+        "layer[name=transportation]:where(class in (motorway, trunk) and brunnel != tunnel)"
+    );
+    assert_eq!(
+        cache.rules[2].selector.to_string(),
+        "layer[name=transportation]:where(not (has bridge or layer > 0))"
+    );
+    let filters = cache.filters();
+    assert_eq!(filters.len(), 3);
+
+    // The class and the selection tags go into the selector, but the filters still see them.
+    let selection_tags = vec!["brunnel".to_string()];
+    let classify = |tags: &[(&str, &str)]| {
+        let tags = tags
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        let (selector, tags) =
+            crate::classify_tags("transportation", tags, &selection_tags, &filters);
+        assert!(!tags.contains_key("class") && !tags.contains_key("brunnel"));
+        selector
+    };
+    let line_width = |style: &ComputedStyle| match style.get("line-width") {
+        Some(CSSValue::Number(Number::Px(width))) => *width,
+        _ => 0.0,
+    };
+
+    let bridge = cache.compute_style(&classify(&[
+        ("class", "motorway"),
+        ("brunnel", "bridge"),
+        ("layer", "1"),
+    ]));
+    assert_eq!(line_width(&bridge), 4.0);
+    assert!(bridge.get("display").is_none());
+
+    let tunnel = cache.compute_style(&classify(&[
+        ("class", "motorway"),
+        ("brunnel", "tunnel"),
+        ("layer", "-1"),
+    ]));
+    assert_eq!(line_width(&tunnel), 1.0);
+    assert!(tunnel.get("display").is_some());
+
+    // Only the filters of the rules for the layer are evaluated, so the others don't split its features.
+    let tags = vec![("class", "lake"), ("brunnel", "bridge")]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let (lake, _) = crate::classify_tags("water", tags, &selection_tags, &filters);
+    assert_eq!(lake.satisfied.len(), 1);
+    assert_eq!(lake.satisfied[0].to_string(), "class = lake");
+    assert!(cache.compute_style(&lake).get("display").is_some());
+
+    let (_, diagnostics) = parse_styles("layer:where(class in ()) { display: none; }");
+    assert_eq!(diagnostics.len(), 1);
+}
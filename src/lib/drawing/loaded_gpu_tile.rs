@@ -1,4 +1,5 @@
 use crate::*;
+use lyon::tessellation::geometry_builder::VertexBuffers;
 use wgpu::util::DeviceExt;
 use wgpu::*;
 
@@ -9,17 +10,21 @@ pub struct LoadedGPUTile {
 
 impl LoadedGPUTile {
     pub fn load(device: &Device, tile: &Tile) -> Self {
+        Self::from_mesh(device, tile.mesh())
+    }
+
+    pub fn from_mesh(device: &Device, mesh: &VertexBuffers<Vertex, u32>) -> Self {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            // size: tile.mesh().vertices.len() as u64 * 12,
-            contents: as_byte_slice(&tile.mesh().vertices),
+            // size: mesh.vertices.len() as u64 * 12,
+            contents: as_byte_slice(&mesh.vertices),
             usage: wgpu::BufferUsage::VERTEX,
         });
 
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            // size: tile.mesh().indices.len() as u64 * 4,
-            contents: as_byte_slice(&tile.mesh().indices),
+            // size: mesh.indices.len() as u64 * 4,
+            contents: as_byte_slice(&mesh.indices),
             usage: wgpu::BufferUsage::INDEX,
         });
 
@@ -1,11 +1,13 @@
 mod line_tesselator;
 mod loaded_gpu_tile;
 mod mesh;
+mod overlay;
 mod vertex;
 
 pub use line_tesselator::*;
 pub use loaded_gpu_tile::*;
 pub use mesh::*;
+pub use overlay::*;
 pub use vertex::*;
 
 pub fn as_byte_slice<T>(slice: &[T]) -> &[u8] {
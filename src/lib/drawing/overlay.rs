@@ -0,0 +1,433 @@
+use crate::*;
+use lyon::{
+    math::{point, vector, Point},
+    path::Path,
+    tessellation::{geometry_builder::VertexBuffers, FillOptions, FillTessellator},
+};
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+use vector_tile::mod_Tile::GeomType;
+use wgpu::{RenderPass, RenderPipeline};
+use wgpu_glyph::{GlyphBrush, HorizontalAlign, Layout, Section, Text, VerticalAlign};
+
+/// The font size of the banners in pixels.
+const BANNER_SIZE: f32 = 48.0;
+
+/// The range of tile space coordinates a vertex can hold.
+const TILE_SPACE_MIN: f32 = i16::MIN as f32;
+const TILE_SPACE_MAX: f32 = i16::MAX as f32;
+
+/// Geometry which is not part of the vector tiles, like AIS targets or routes.
+///
+/// All geometry is placed in the space of the tile at the current zoom level that contains the screen center.
+/// This way it is drawn with the same pipeline and styled by the same CSS rules as the tiles.
+pub struct Overlay {
+    tile_id: TileId,
+    extent: f32,
+    zoom: f32,
+    tile_size: f32,
+    mesh: VertexBuffers<Vertex, u32>,
+    features: Vec<(u32, Range<u32>)>,
+    labels: Vec<(Point, String)>,
+    banners: Vec<String>,
+    feature_collection: Arc<RwLock<FeatureCollection>>,
+}
+
+impl Overlay {
+    /// Creates a new empty overlay for the current screen.
+    pub fn new(
+        screen: &Screen,
+        zoom: f32,
+        feature_collection: Arc<RwLock<FeatureCollection>>,
+    ) -> Self {
+        let z = zoom.max(0.0) as u32;
+        Self {
+            tile_id: global_to_num_space(&screen.center, z).into(),
+            extent: 4096.0,
+            zoom,
+            tile_size: screen.get_tile_size() as f32,
+            mesh: VertexBuffers::with_capacity(1_000, 1_000),
+            features: vec![],
+            labels: vec![],
+            banners: vec![],
+            feature_collection,
+        }
+    }
+
+    pub fn tile_id(&self) -> TileId {
+        self.tile_id
+    }
+
+    pub fn extent(&self) -> u16 {
+        self.extent as u16
+    }
+
+    pub fn mesh(&self) -> &VertexBuffers<Vertex, u32> {
+        &self.mesh
+    }
+
+    pub fn features(&self) -> &Vec<(u32, Range<u32>)> {
+        &self.features
+    }
+
+    /// Converts a length in screen pixels to a length in global space at the current zoom level.
+    ///
+    /// Used to draw symbols which keep their size on screen while zooming.
+    pub fn pixels_to_global(&self, pixels: f32) -> f32 {
+        pixels / self.tile_size / 2f32.powf(self.zoom)
+    }
+
+    /// Adds a filled polygon given in global space.
+    pub fn add_polygon(&mut self, selector: &Selector, points: &[Point]) {
+        let points = clip_polygon(points.iter().map(|p| self.to_tile_space(p)).collect());
+        if points.len() < 3 {
+            return;
+        }
+
+        let mut path_builder = Path::builder();
+        path_builder.move_to(points[0]);
+        for p in &points[1..] {
+            path_builder.line_to(*p);
+        }
+        path_builder.close();
+        let path = path_builder.build();
+
+        let feature_id = self.ensure_feature(selector);
+        let mut builder =
+            MeshBuilder::new(&mut self.mesh, LayerVertexCtor::new(&self.tile_id, 1.0));
+        builder.set_current_feature_id(feature_id);
+        builder.set_current_extent(self.extent);
+        builder.set_current_vertex_type(VertexType::Polygon);
+        let index_start_before = builder.get_current_index();
+        let _ = FillTessellator::new()
+            .tessellate_path(
+                &path,
+                &FillOptions::tolerance(0.0001).with_normals(true),
+                &mut builder,
+            )
+            .map_err(|_e| {
+                log::error!("Broken overlay path.");
+            });
+        let index_end = builder.get_current_index();
+        self.features
+            .push((feature_id, index_start_before..index_end));
+    }
+
+    /// Adds a line given in global space.
+    pub fn add_line(&mut self, selector: &Selector, points: &[Point]) {
+        self.add_lines(selector, std::iter::once(points));
+    }
+
+    /// Adds several lines given in global space which share a selector.
+    ///
+    /// The lines are tesselated by `paths_to_drawable` just like the line features of the tiles.
+    pub fn add_lines<'a>(
+        &mut self,
+        selector: &Selector,
+        lines: impl IntoIterator<Item = &'a [Point]>,
+    ) {
+        let paths: Vec<Path> = lines
+            .into_iter()
+            .flat_map(|points| self.line_to_paths(points))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        let feature_id = self.ensure_feature(selector);
+        let mut builder =
+            MeshBuilder::new(&mut self.mesh, LayerVertexCtor::new(&self.tile_id, 1.0));
+        builder.set_current_feature_id(feature_id);
+        let index_start_before = builder.get_current_index();
+        paths_to_drawable(
+            &mut builder,
+            GeomType::LINESTRING,
+            &paths,
+            self.extent,
+            self.tile_id.z,
+        );
+        let index_end = builder.get_current_index();
+        self.features
+            .push((feature_id, index_start_before..index_end));
+    }
+
+    /// Adds a text label centered on a position in global space.
+    pub fn add_label(&mut self, position: &Point, text: impl Into<String>) {
+        self.labels.push((*position, text.into()));
+    }
+
+    /// Adds a big line of text at the top of the screen for urgent information.
+    pub fn add_banner(&mut self, text: impl Into<String>) {
+        self.banners.push(text.into());
+    }
+
+    /// Adds a closed circle outline around `center` with a `radius` in global space.
+    pub fn add_circle(&mut self, selector: &Selector, center: &Point, radius: f32) {
+        const SEGMENTS: usize = 64;
+        let points: Vec<Point> = (0..=SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / SEGMENTS as f32 * 2.0 * std::f32::consts::PI;
+                *center + vector(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+        self.add_line(selector, &points);
+    }
+
+    /// Adds a triangle pointing towards `heading` (degrees true) centered on `position`.
+    ///
+    /// `length` and `width` are given in screen pixels so the symbol does not scale with the map.
+    pub fn add_oriented_triangle(
+        &mut self,
+        selector: &Selector,
+        position: &Point,
+        heading: f32,
+        length: f32,
+        width: f32,
+    ) {
+        let (sin, cos) = heading.to_radians().sin_cos();
+        // Global space grows to the south, so north is -y.
+        let forward = vector(sin, -cos);
+        let right = vector(cos, sin);
+        let length = self.pixels_to_global(length);
+        let width = self.pixels_to_global(width);
+        let points = [
+            *position + forward * length * 0.6,
+            *position - forward * length * 0.4 + right * width * 0.5,
+            *position - forward * length * 0.4 - right * width * 0.5,
+        ];
+        self.add_polygon(selector, &points);
+    }
+
+    /// Adds an arrow from `from` pointing towards `heading` (degrees true).
+    ///
+    /// `length` and `head` are given in screen pixels.
+    pub fn add_arrow(
+        &mut self,
+        selector: &Selector,
+        from: &Point,
+        heading: f32,
+        length: f32,
+        head: f32,
+    ) {
+        let (sin, cos) = heading.to_radians().sin_cos();
+        let forward = vector(sin, -cos);
+        let shaft_end = *from + forward * self.pixels_to_global((length - head).max(0.0));
+        self.add_line(selector, &[*from, shaft_end]);
+        let head_center = shaft_end + forward * self.pixels_to_global(head * 0.4);
+        self.add_oriented_triangle(selector, &head_center, heading, head, head * 0.8);
+    }
+
+    fn ensure_feature(&mut self, selector: &Selector) -> u32 {
+        // Scope the lock guard real tight to ensure it's released quickly.
+        let mut feature_collection = self.feature_collection.write().unwrap();
+        feature_collection.ensure_feature(selector)
+    }
+
+    /// Converts a line to paths in tile space.
+    ///
+    /// The line is clipped to the range of the vertices, so it is split where it leaves that range and comes back.
+    fn line_to_paths(&self, points: &[Point]) -> Vec<Path> {
+        let tile_points: Vec<Point> = points.iter().map(|p| self.to_tile_space(p)).collect();
+        let mut lines: Vec<Vec<Point>> = vec![];
+        for segment in tile_points.windows(2) {
+            let (a, b) = match clip_segment(segment[0], segment[1]) {
+                Some(segment) => segment,
+                None => continue,
+            };
+            let continued = matches!(
+                lines.last().and_then(|line| line.last()),
+                Some(last) if (*last - a).length() <= 0.5
+            );
+            if !continued {
+                lines.push(vec![a]);
+            }
+            if let Some(line) = lines.last_mut() {
+                // Drop degenerate segments, the line tesselator can't handle them.
+                if !matches!(line.last(), Some(last) if (*last - b).length() <= 0.5) {
+                    line.push(b);
+                }
+            }
+        }
+
+        lines
+            .into_iter()
+            .filter(|line| line.len() >= 2)
+            .map(|line| {
+                let mut path_builder = Path::builder();
+                path_builder.move_to(line[0]);
+                for p in &line[1..] {
+                    path_builder.line_to(*p);
+                }
+                path_builder.build()
+            })
+            .collect()
+    }
+
+    fn to_tile_space(&self, p: &Point) -> Point {
+        let scale = 2f32.powi(self.tile_id.z as i32);
+        point(
+            (p.x * scale - self.tile_id.x as f32) * self.extent,
+            (p.y * scale - self.tile_id.y as f32) * self.extent,
+        )
+    }
+
+    /// Queues the labels of the overlay for drawing.
+    pub fn queue_text(&self, glyph_brush: &mut GlyphBrush<()>, screen: &Screen, z: f32) {
+        for (position, text) in &self.labels {
+            let (x, y) = screen.global_to_screen_point(z, position);
+            let section = Section::default()
+                .add_text(Text::new(text))
+                .with_screen_position((x, y))
+                .with_layout(
+                    Layout::default()
+                        .h_align(HorizontalAlign::Center)
+                        .v_align(VerticalAlign::Center),
+                );
+
+            glyph_brush.queue(section);
+        }
+
+        for (i, text) in self.banners.iter().enumerate() {
+            let section = Section::default()
+                .add_text(Text::new(text).with_scale(BANNER_SIZE))
+                .with_screen_position((screen.width as f32 / 2.0, BANNER_SIZE * (i as f32 + 0.5)))
+                .with_layout(Layout::default().h_align(HorizontalAlign::Center));
+
+            glyph_brush.queue(section);
+        }
+    }
+
+    pub fn paint<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        blend_pipeline: &'a RenderPipeline,
+        data: &'a LoadedGPUTile,
+        feature_collection: &'a FeatureCollection,
+        tile_id: u32,
+    ) {
+        if self.features.is_empty() {
+            return;
+        }
+
+        render_pass.set_index_buffer(data.index_buffer.slice(..));
+        render_pass.set_vertex_buffer(0, data.vertex_buffer.slice(..));
+
+        let mut features = self.features.clone();
+        features.sort_by(|a, b| {
+            feature_collection
+                .get_zindex(a.0)
+                .partial_cmp(&feature_collection.get_zindex(b.0))
+                .unwrap()
+        });
+
+        render_pass.set_pipeline(blend_pipeline);
+        for (i, (id, range)) in features.iter().enumerate() {
+            if !range.is_empty() && feature_collection.is_visible(*id) {
+                render_pass.set_stencil_reference(i as u32);
+
+                let range_start = (tile_id << 1) | 1;
+                render_pass.draw_indexed(range.clone(), 0, range_start..1 + range_start);
+
+                if feature_collection.has_outline(*id) {
+                    let range_start = tile_id << 1;
+                    render_pass.draw_indexed(range.clone(), 0, range_start..1 + range_start);
+                }
+            }
+        }
+    }
+}
+
+/// Clips the segment from `a` to `b` to the range of the vertices with the Liang–Barsky algorithm.
+///
+/// Returns `None` if no part of the segment is within the range.
+fn clip_segment(a: Point, b: Point) -> Option<(Point, Point)> {
+    let d = b - a;
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for &(p, q) in &[
+        (-d.x, a.x - TILE_SPACE_MIN),
+        (d.x, TILE_SPACE_MAX - a.x),
+        (-d.y, a.y - TILE_SPACE_MIN),
+        (d.y, TILE_SPACE_MAX - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+    Some((a + d * t0, a + d * t1))
+}
+
+/// Clips a polygon to the range of the vertices with the Sutherland–Hodgman algorithm.
+fn clip_polygon(mut points: Vec<Point>) -> Vec<Point> {
+    // Whether the bound is on x or y, the bound and whether it is the upper one.
+    for &(on_x, bound, upper) in &[
+        (true, TILE_SPACE_MIN, false),
+        (true, TILE_SPACE_MAX, true),
+        (false, TILE_SPACE_MIN, false),
+        (false, TILE_SPACE_MAX, true),
+    ] {
+        let coordinate = |p: &Point| if on_x { p.x } else { p.y };
+        let inside = |p: &Point| (coordinate(p) <= bound) == upper || coordinate(p) == bound;
+        let crossing = |a: Point, b: Point| {
+            a.lerp(
+                b,
+                (bound - coordinate(&a)) / (coordinate(&b) - coordinate(&a)),
+            )
+        };
+        let input = std::mem::take(&mut points);
+        for (i, b) in input.iter().enumerate() {
+            let a = input[(i + input.len() - 1) % input.len()];
+            if inside(b) {
+                if !inside(&a) {
+                    points.push(crossing(a, *b));
+                }
+                points.push(*b);
+            } else if inside(&a) {
+                points.push(crossing(a, *b));
+            }
+        }
+    }
+    points
+}
+
+#[test]
+fn clip_to_tile_space() {
+    let far = TILE_SPACE_MAX * 4.0;
+    // A segment from the center of the tile far to the bottom right keeps its direction.
+    let (a, b) = clip_segment(point(0.0, 0.0), point(far, far / 2.0)).unwrap();
+    assert_eq!(a, point(0.0, 0.0));
+    assert_eq!(b, point(TILE_SPACE_MAX, TILE_SPACE_MAX / 2.0));
+    // A segment passing by the range entirely is dropped.
+    assert!(clip_segment(point(far, 0.0), point(far, far)).is_none());
+    assert!(clip_segment(point(-far, far), point(far, far * 2.0)).is_none());
+    // A segment crossing the whole range keeps the part within.
+    let (a, b) = clip_segment(point(-far, 0.0), point(far, 0.0)).unwrap();
+    assert_eq!(
+        (a, b),
+        (point(TILE_SPACE_MIN, 0.0), point(TILE_SPACE_MAX, 0.0))
+    );
+
+    let square = clip_polygon(vec![
+        point(0.0, 0.0),
+        point(far, 0.0),
+        point(far, far),
+        point(0.0, far),
+    ]);
+    assert_eq!(
+        square,
+        vec![
+            point(0.0, TILE_SPACE_MAX),
+            point(0.0, 0.0),
+            point(TILE_SPACE_MAX, 0.0),
+            point(TILE_SPACE_MAX, TILE_SPACE_MAX),
+        ]
+    );
+}
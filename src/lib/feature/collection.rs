@@ -4,6 +4,8 @@ use crate::*;
 pub struct FeatureCollection {
     features: Vec<Feature>,
     n_features_max: u32,
+    /// The selectors of the rules with a filter expression which tiles evaluate for their features.
+    filters: Vec<Selector>,
 }
 
 impl FeatureCollection {
@@ -11,6 +13,7 @@ impl FeatureCollection {
         Self {
             features: vec![],
             n_features_max,
+            filters: vec![],
         }
     }
 
@@ -41,6 +44,24 @@ impl FeatureCollection {
         }
     }
 
+    pub fn filters(&self) -> &[Selector] {
+        &self.filters
+    }
+
+    /// Takes over the selectors with filter expressions of the stylesheet and returns `true` if they changed.
+    ///
+    /// All features are dropped on a change, as they were classified with the old filters.
+    /// So the tiles loaded before have to be loaded again, otherwise their feature ids are stale.
+    pub fn set_filters(&mut self, filters: Vec<Selector>) -> bool {
+        if self.filters == filters {
+            false
+        } else {
+            self.filters = filters;
+            self.features.clear();
+            true
+        }
+    }
+
     pub fn is_visible(&self, feature_id: u32) -> bool {
         let feature = &self.features[feature_id as usize];
         let bga = feature.style.background_color.a;
@@ -76,3 +97,17 @@ impl FeatureCollection {
         self.features.iter().map(|f| f.style).collect()
     }
 }
+
+#[test]
+fn changed_filters_drop_features() {
+    let mut collection = FeatureCollection::new(10);
+    let selector = Selector::new().with_type("layer");
+    assert_eq!(collection.ensure_feature(&selector), 0);
+    assert!(!collection.set_filters(vec![]));
+    assert_eq!(collection.get_features().len(), 1);
+
+    let filtered = selector.with_filter(Filter::Equal("class".into(), "lake".into()));
+    assert!(collection.set_filters(vec![filtered.clone()]));
+    assert!(collection.get_features().is_empty());
+    assert!(!collection.set_filters(vec![filtered]));
+}
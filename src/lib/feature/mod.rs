@@ -4,6 +4,16 @@ pub use collection::*;
 
 use crate::*;
 
+/// The zoom level at which a length in world units (`w`) is as long as in pixels.
+///
+/// This is the most detailed zoom level of the vector tiles, world lengths halve with every zoom level below it.
+const WORLD_SCALE_ZOOM: f32 = 14.0;
+
+/// Converts a length in world units to pixels at the given zoom level.
+pub fn world_to_px(world: f32, zoom: f32) -> f32 {
+    world * 2f32.powf(zoom - WORLD_SCALE_ZOOM)
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct DrawableColor {
     pub r: f32,
@@ -28,7 +38,8 @@ pub struct FeatureStyle {
     pub background_color: DrawableColor,
     pub border_color: DrawableColor,
     pub border_width: f32,
-    pub line_width: u32,
+    /// The line width in pixels, fractional so interpolated widths change smoothly with the zoom.
+    pub line_width: f32,
     pub display: bool,
     pub z_index: f32,
 }
@@ -52,81 +63,47 @@ impl Feature {
         }
     }
 
+    /// Loads the style of the feature at the given zoom level.
+    ///
+    /// Rules are selected by the integer zoom level and combined with the cascade of `RulesCache::compute_style`,
+    /// while interpolated values are evaluated at the exact zoom.
     pub fn load_style(&mut self, zoom: f32, css_cache: &mut RulesCache) {
-        let rules = css_cache.get_matching_rules(
+        let style = css_cache.compute_style(
             &self
                 .selector
                 .clone()
                 .with_any("zoom".to_string(), (zoom.floor() as u32).to_string()),
         );
 
-        let background_color = rules
-            .iter()
-            .filter_map(|r| r.kvs.get("background-color"))
-            .last();
-
-        if let Some(color) = background_color {
-            match color {
-                CSSValue::Color(bg) => {
-                    self.style.background_color = bg.clone().into();
-                }
-                CSSValue::String(string) => {
-                    match &string[..] {
-                        "red" => self.style.background_color = Color::RED.into(),
-                        "green" => self.style.background_color = Color::GREEN.into(),
-                        "blue" => self.style.background_color = Color::BLUE.into(),
-                        "black" => self.style.background_color = Color::BLACK.into(),
-                        "white" => self.style.background_color = Color::WHITE.into(),
-                        // Other CSS colors to come later.
-                        color => log::info!("The color '{}' is currently not supported.", color),
-                    }
-                }
-                value => {
-                    log::info!(
-                        "The value '{:?}' is currently not supported for 'background-color'.",
-                        value
-                    );
-                }
+        let background_color = style
+            .get("background-color")
+            .map(|value| value.evaluate(zoom));
+
+        if let Some(color) = &background_color {
+            match color.to_color() {
+                Some(color) => self.style.background_color = color.into(),
+                None => log::info!(
+                    "The value '{:?}' is currently not supported for 'background-color'.",
+                    color
+                ),
             }
         }
 
-        let border_color = rules
-            .iter()
-            .filter_map(|r| r.kvs.get("border-color"))
-            .last();
-
-        if let Some(color) = border_color {
-            match color {
-                CSSValue::Color(bg) => {
-                    self.style.border_color = bg.clone().into();
-                    self.style.border_color; // TODO this is probably no intended to be this way
-                }
-                CSSValue::String(string) => {
-                    match &string[..] {
-                        "red" => self.style.border_color = Color::RED.into(),
-                        "blue" => self.style.border_color = Color::GREEN.into(),
-                        "green" => self.style.border_color = Color::BLUE.into(),
-                        "black" => self.style.background_color = Color::BLACK.into(),
-                        "white" => self.style.background_color = Color::WHITE.into(),
-                        // Other CSS colors to come later.
-                        color => log::info!("The color '{}' is currently not supported.", color),
-                    }
-                }
-                value => {
-                    log::info!(
-                        "The value '{:?}' is currently not supported for 'border-color'.",
-                        value
-                    );
-                }
+        let border_color = style.get("border-color").map(|value| value.evaluate(zoom));
+
+        if let Some(color) = &border_color {
+            match color.to_color() {
+                Some(color) => self.style.border_color = color.into(),
+                None => log::info!(
+                    "The value '{:?}' is currently not supported for 'border-color'.",
+                    color
+                ),
             }
         }
 
-        let border_width = rules
-            .iter()
-            .filter_map(|r| r.kvs.get("border-width"))
-            .last();
+        let border_width = style.get("border-width").map(|value| value.evaluate(zoom));
 
-        if let Some(border_width) = border_width {
+        if let Some(border_width) = &border_width {
             match border_width {
                 CSSValue::Number(number) => match number {
                     Number::Px(px) => self.style.border_width = *px,
@@ -142,9 +119,9 @@ impl Feature {
             }
         }
 
-        let display = rules.iter().filter_map(|r| r.kvs.get("display")).last();
+        let display = style.get("display").map(|value| value.evaluate(zoom));
 
-        if let Some(display) = display {
+        if let Some(display) = &display {
             match display {
                 CSSValue::String(value) => match &value[..] {
                     "none" => self.style.display = false,
@@ -159,13 +136,14 @@ impl Feature {
             self.style.display = true;
         }
 
-        let line_width = rules.iter().filter_map(|r| r.kvs.get("line-width")).last();
+        let line_width = style.get("line-width").map(|value| value.evaluate(zoom));
 
-        if let Some(line_width) = line_width {
+        if let Some(line_width) = &line_width {
             match line_width {
                 CSSValue::Number(number) => match number {
-                    Number::Px(px) => self.style.line_width = (*px as u32) << 1 | 0b01,
-                    Number::World(world) => self.style.line_width = ((*world as u32) << 1) | 0b00,
+                    Number::Px(px) => self.style.line_width = *px,
+                    // The shader sizes all lines in pixels, so world widths are converted at the current zoom.
+                    Number::World(world) => self.style.line_width = world_to_px(*world, zoom),
                     value => log::info!(
                         "The value '{:?}' is currently not supported for 'line-width'.",
                         value
@@ -177,12 +155,12 @@ impl Feature {
                 ),
             }
         } else {
-            self.style.line_width = 0;
+            self.style.line_width = 0.0;
         }
 
-        let z_index = rules.iter().filter_map(|r| r.kvs.get("z-index")).last();
+        let z_index = style.get("z-index").map(|value| value.evaluate(zoom));
 
-        if let Some(z_index) = z_index {
+        if let Some(z_index) = &z_index {
             match z_index {
                 CSSValue::Number(number) => match number {
                     Number::Unitless(unitless) => self.style.z_index = *unitless,
@@ -201,3 +179,25 @@ impl Feature {
         }
     }
 }
+
+#[test]
+fn world_line_width() {
+    let path = std::env::temp_dir().join(format!("sailor_world_test_{}.css", std::process::id()));
+    std::fs::write(
+        &path,
+        "layer { line-width: 2px; }\nlayer[zoom>=12] { line-width: 4w; }\n",
+    )
+    .unwrap();
+    let mut css_cache = RulesCache::try_load_from_file(path.to_string_lossy()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut feature = Feature::new(Selector::new().with_type("layer"), 0);
+    let mut line_width = |zoom: f32| {
+        feature.load_style(zoom, &mut css_cache);
+        feature.style.line_width
+    };
+    assert_eq!(line_width(11.0), 2.0);
+    assert_eq!(line_width(12.0), 1.0);
+    assert_eq!(line_width(14.0), 4.0);
+    assert!((line_width(13.5) - 2.0f32.powf(1.5)).abs() < 1e-5);
+}
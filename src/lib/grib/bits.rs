@@ -0,0 +1,61 @@
+/// Reads big endian integers of arbitrary bit width from a byte slice.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Reads an unsigned integer with `bits` bits.
+    ///
+    /// Returns `None` if the data ends before.
+    pub fn read(&mut self, bits: usize) -> Option<u64> {
+        if bits == 0 {
+            return Some(0);
+        }
+        if bits > 64 || self.position + bits > self.data.len() * 8 {
+            return None;
+        }
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    /// Reads a sign and magnitude integer with `bits` bits like GRIB stores signed values.
+    pub fn read_signed(&mut self, bits: usize) -> Option<i64> {
+        if bits == 0 {
+            return Some(0);
+        }
+        let value = self.read(bits)?;
+        let sign = 1 << (bits - 1);
+        if value & sign != 0 {
+            Some(-((value & !sign) as i64))
+        } else {
+            Some(value as i64)
+        }
+    }
+
+    /// Skips to the start of the next byte.
+    pub fn align(&mut self) {
+        self.position = (self.position + 7) & !7;
+    }
+}
+
+/// Reads a big endian unsigned integer of `bytes` bytes at `offset`.
+pub fn read_unsigned(data: &[u8], offset: usize, bytes: usize) -> Option<u64> {
+    let slice = data.get(offset..offset + bytes)?;
+    Some(slice.iter().fold(0u64, |value, b| (value << 8) | *b as u64))
+}
+
+/// Reads a big endian sign and magnitude integer of `bytes` bytes at `offset`.
+pub fn read_signed(data: &[u8], offset: usize, bytes: usize) -> Option<i64> {
+    BitReader::new(data.get(offset..offset + bytes)?).read_signed(bytes * 8)
+}
@@ -0,0 +1,432 @@
+use super::*;
+use lyon::math::Point;
+
+/// The meteorological parameters we know how to display.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GribParameter {
+    /// Temperature in K.
+    Temperature,
+    /// U component of the wind in m/s.
+    WindU,
+    /// V component of the wind in m/s.
+    WindV,
+    /// Pressure reduced to mean sea level in Pa.
+    Pressure,
+    /// Total cloud cover in percent.
+    CloudCover,
+    /// Any other parameter given as discipline, category and number.
+    Other(u8, u8, u8),
+}
+
+impl GribParameter {
+    fn from_codes(discipline: u8, category: u8, number: u8) -> Self {
+        match (discipline, category, number) {
+            (0, 0, 0) => GribParameter::Temperature,
+            (0, 2, 2) => GribParameter::WindU,
+            (0, 2, 3) => GribParameter::WindV,
+            (0, 3, 1) => GribParameter::Pressure,
+            (0, 6, 1) => GribParameter::CloudCover,
+            (d, c, n) => GribParameter::Other(d, c, n),
+        }
+    }
+
+    /// Looks up a parameter by its abbreviation, e.g. `TMP`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match &name.to_ascii_uppercase()[..] {
+            "TMP" => Some(GribParameter::Temperature),
+            "UGRD" => Some(GribParameter::WindU),
+            "VGRD" => Some(GribParameter::WindV),
+            "PRMSL" => Some(GribParameter::Pressure),
+            "TCDC" => Some(GribParameter::CloudCover),
+            _ => None,
+        }
+    }
+
+    /// The usual abbreviation of the parameter.
+    pub fn name(&self) -> String {
+        match self {
+            GribParameter::Temperature => "TMP".to_string(),
+            GribParameter::WindU => "UGRD".to_string(),
+            GribParameter::WindV => "VGRD".to_string(),
+            GribParameter::Pressure => "PRMSL".to_string(),
+            GribParameter::CloudCover => "TCDC".to_string(),
+            GribParameter::Other(d, c, n) => format!("{}.{}.{}", d, c, n),
+        }
+    }
+}
+
+/// A single decoded field of a GRIB2 file.
+#[derive(Debug, Clone)]
+pub struct GribField {
+    pub parameter: GribParameter,
+    /// The reference time of the forecast in seconds since the unix epoch.
+    pub reference_time: f64,
+    /// The forecast time in hours after the reference time.
+    pub forecast_hours: f64,
+    pub grid: LatLonGrid,
+    /// The values in the scanning order of the grid. Missing values are NaN.
+    pub values: Vec<f32>,
+}
+
+impl GribField {
+    /// Interpolates the field at a position.
+    pub fn value_at(&self, lat: f64, lon: f64) -> f32 {
+        self.grid.interpolate(&self.values, lat, lon)
+    }
+
+    /// Returns the smallest and largest value of the field ignoring missing values.
+    pub fn range(&self) -> Option<(f32, f32)> {
+        self.values
+            .iter()
+            .filter(|v| !v.is_nan())
+            .fold(None, |range, v| match range {
+                None => Some((*v, *v)),
+                Some((min, max)) => Some((min.min(*v), max.max(*v))),
+            })
+    }
+
+    /// Traces the lines of equal value at every multiple of `interval`.
+    ///
+    /// The points are in global space, closed lines repeat their first point and lines crossing the antimeridian are split.
+    pub fn contours(&self, interval: f32, smoothing: usize) -> Vec<Contour> {
+        // Contour a row major copy which repeats the first column on global grids to close the lines.
+        let grid = &self.grid;
+        let width = if grid.is_global() {
+            grid.ni + 1
+        } else {
+            grid.ni
+        };
+        let mut values = Vec::with_capacity(width * grid.nj);
+        for j in 0..grid.nj {
+            for i in 0..width {
+                values.push(grid.value(&self.values, i % grid.ni, j));
+            }
+        }
+
+        let mut contours = vec![];
+        for contour in contour_lines(&values, width, grid.nj, interval, smoothing) {
+            let mut points: Vec<Point> = contour
+                .points
+                .iter()
+                .map(|p| {
+                    // Web Mercator ends at about 85 degrees, keep the lines near the poles on the map.
+                    let position = grid.position_at(p.x as f64, p.y as f64);
+                    GeoPoint::new(position.lat.clamp(-85.0, 85.0), position.lon).to_global_space()
+                })
+                .collect();
+            if contour.closed {
+                points.push(points[0]);
+            }
+
+            let mut start = 0;
+            for i in 1..=points.len() {
+                if i == points.len() || (points[i].x - points[i - 1].x).abs() > 0.5 {
+                    contours.push(Contour {
+                        level: contour.level,
+                        points: points[start..i].to_vec(),
+                        closed: contour.closed && start == 0 && i == points.len(),
+                    });
+                    start = i;
+                }
+            }
+        }
+        contours
+    }
+
+    /// Resamples the field onto a `width` x `height` texture covering the whole Web Mercator world.
+    ///
+    /// The values are row major starting at the north west corner. Texels outside of the grid are NaN.
+    pub fn resample_to_mercator(&self, width: u32, height: u32) -> Vec<f32> {
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let global = lyon::math::point(
+                    (x as f32 + 0.5) / width as f32,
+                    (y as f32 + 0.5) / height as f32,
+                );
+                let position = GeoPoint::from_global_space(&global);
+                data.push(self.value_at(position.lat, position.lon));
+            }
+        }
+        data
+    }
+}
+
+/// All the fields of a GRIB2 file.
+#[derive(Debug, Clone, Default)]
+pub struct GribFile {
+    fields: Vec<GribField>,
+}
+
+impl GribFile {
+    /// Loads and decodes a GRIB2 file.
+    ///
+    /// Messages which use unsupported templates are skipped.
+    pub fn load(path: &str) -> Option<Self> {
+        match std::fs::read(path) {
+            Ok(data) => Some(Self::parse(&data)),
+            Err(err) => {
+                log::error!("Unable to read the GRIB file {}:", path);
+                log::error!("{}", err);
+                None
+            }
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Self {
+        let mut fields = vec![];
+        let mut offset = 0;
+        while let Some(start) = find_message(data, offset) {
+            if data.get(start + 7) != Some(&2) {
+                log::warn!("Skipping a GRIB message which is not GRIB edition 2.");
+                offset = start + 4;
+                continue;
+            }
+            // A message has at least the indicator section and the end section.
+            let length = match read_unsigned(data, start + 8, 8) {
+                Some(length) if length >= 20 && length <= (data.len() - start) as u64 => {
+                    length as usize
+                }
+                Some(length) if length >= 20 => {
+                    log::warn!("The GRIB file ends in the middle of a message.");
+                    break;
+                }
+                _ => {
+                    log::warn!("Skipping a GRIB message with an invalid length.");
+                    offset = start + 4;
+                    continue;
+                }
+            };
+            fields.extend(parse_message(&data[start..start + length]));
+            offset = start + length;
+        }
+        Self { fields }
+    }
+
+    pub fn fields(&self) -> &[GribField] {
+        &self.fields
+    }
+
+    /// Returns the field of a parameter with the forecast time closest to `forecast_hours`.
+    pub fn find(&self, parameter: GribParameter, forecast_hours: f64) -> Option<&GribField> {
+        self.fields
+            .iter()
+            .filter(|f| f.parameter == parameter)
+            .min_by(|a, b| {
+                (a.forecast_hours - forecast_hours)
+                    .abs()
+                    .partial_cmp(&(b.forecast_hours - forecast_hours).abs())
+                    .unwrap()
+            })
+    }
+
+    /// Returns all the distinct forecast times of the file in hours.
+    pub fn forecast_hours(&self) -> Vec<f64> {
+        let mut hours: Vec<f64> = self.fields.iter().map(|f| f.forecast_hours).collect();
+        hours.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        hours.dedup();
+        hours
+    }
+}
+
+fn find_message(data: &[u8], offset: usize) -> Option<usize> {
+    data.get(offset..)?
+        .windows(4)
+        .position(|w| w == b"GRIB")
+        .map(|p| p + offset)
+}
+
+/// Decodes all the fields of a single GRIB2 message.
+///
+/// A message can repeat the sections 3 to 7 (or 4 to 7) to pack several fields.
+fn parse_message(message: &[u8]) -> Vec<GribField> {
+    let discipline = match message.get(6) {
+        Some(discipline) => *discipline,
+        None => return vec![],
+    };
+    let mut fields = vec![];
+    let mut reference_time = 0.0;
+    let mut grid = None;
+    let mut product = None;
+    let mut representation = None;
+    let mut bitmap: Option<Vec<bool>> = None;
+
+    let mut offset = 16;
+    while offset + 4 <= message.len() {
+        if &message[offset..offset + 4] == b"7777" {
+            break;
+        }
+        let length = match read_unsigned(message, offset, 4) {
+            Some(length) if length >= 5 => length as usize,
+            _ => break,
+        };
+        let section = match message.get(offset..offset + length) {
+            Some(section) => section,
+            None => {
+                log::warn!("A GRIB section exceeds its message.");
+                break;
+            }
+        };
+
+        match section[4] {
+            1 => reference_time = parse_reference_time(section).unwrap_or(0.0),
+            3 => grid = LatLonGrid::parse(section),
+            4 => product = parse_product(section),
+            5 => representation = DataRepresentation::parse(section),
+            6 => match section.get(5) {
+                Some(0) => {
+                    let mut reader = BitReader::new(section.get(6..).unwrap_or_default());
+                    let len = grid.as_ref().map_or(0, |g| g.len());
+                    bitmap = (0..len).map(|_| reader.read(1).map(|b| b == 1)).collect();
+                }
+                // The bitmap of the previous field is reused.
+                Some(254) => {}
+                _ => bitmap = None,
+            },
+            7 => {
+                if let (
+                    Some(grid),
+                    Some((category, number, forecast_hours)),
+                    Some(representation),
+                ) = (&grid, product, &representation)
+                {
+                    if representation.count > grid.len() {
+                        log::warn!("A GRIB data section has more values than its grid.");
+                        offset += length;
+                        continue;
+                    }
+                    match representation.unpack(&section[5..]) {
+                        Some(values) => fields.push(GribField {
+                            parameter: GribParameter::from_codes(discipline, category, number),
+                            reference_time,
+                            forecast_hours,
+                            grid: grid.clone(),
+                            values: apply_bitmap(values, bitmap.as_deref(), grid.len()),
+                        }),
+                        None => log::warn!("Unable to unpack a GRIB data section."),
+                    }
+                }
+            }
+            _ => {}
+        }
+        offset += length;
+    }
+    fields
+}
+
+/// Parses the reference time of the identification section.
+fn parse_reference_time(section: &[u8]) -> Option<f64> {
+    Some(
+        UtcDateTime {
+            year: read_unsigned(section, 12, 2)? as i64,
+            month: *section.get(14)? as u32,
+            day: *section.get(15)? as u32,
+            hour: *section.get(16)? as u32,
+            minute: *section.get(17)? as u32,
+            second: *section.get(18)? as f64,
+        }
+        .to_unix(),
+    )
+}
+
+/// Parses the parameter category, number and forecast time in hours of the product definition section.
+fn parse_product(section: &[u8]) -> Option<(u8, u8, f64)> {
+    let template = read_unsigned(section, 7, 2)?;
+    // All of these templates start with the fields of template 4.0.
+    if ![0, 1, 2, 8, 11, 12].contains(&template) {
+        log::warn!(
+            "The GRIB product definition template 4.{} is not supported.",
+            template
+        );
+        return None;
+    }
+
+    let unit_hours = match *section.get(17)? {
+        0 => 1.0 / 60.0,
+        1 => 1.0,
+        2 => 24.0,
+        10 => 3.0,
+        11 => 6.0,
+        12 => 12.0,
+        13 => 1.0 / 3600.0,
+        unit => {
+            log::warn!("The GRIB time unit {} is not supported.", unit);
+            return None;
+        }
+    };
+    Some((
+        *section.get(9)?,
+        *section.get(10)?,
+        read_signed(section, 18, 4)? as f64 * unit_hours,
+    ))
+}
+
+/// Spreads the packed values over the grid points which are present according to the bitmap.
+fn apply_bitmap(values: Vec<f32>, bitmap: Option<&[bool]>, len: usize) -> Vec<f32> {
+    match bitmap {
+        None => values,
+        Some(bitmap) => {
+            let mut values = values.into_iter();
+            bitmap
+                .iter()
+                .take(len)
+                .map(|present| {
+                    if *present {
+                        values.next().unwrap_or(f32::NAN)
+                    } else {
+                        f32::NAN
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+#[test]
+fn decode_simple_and_complex_packing() {
+    let file = GribFile::parse(include_bytes!("../../../data/sample.grib2"));
+    assert_eq!(file.fields().len(), 2);
+    assert_eq!(file.forecast_hours(), vec![0.0, 6.0]);
+
+    // Simple packing on a global grid which wraps around the antimeridian.
+    let temperature = file.find(GribParameter::Temperature, 0.0).unwrap();
+    let expected =
+        |lat: f64, lon: f64| 273.15 + 30.0 * lat.to_radians().cos() + 2.0 * lon.to_radians().sin();
+    assert!((temperature.value_at(50.0, 20.0) as f64 - expected(50.0, 20.0)).abs() < 0.1);
+    assert!((temperature.value_at(0.0, -10.0) as f64 - expected(0.0, 350.0)).abs() < 0.1);
+
+    // Complex packing with second order spatial differencing and a bitmap masking the poles.
+    let wind = file.find(GribParameter::WindU, 5.0).unwrap();
+    assert_eq!(wind.forecast_hours, 6.0);
+    let expected =
+        |lat: f64, lon: f64| 10.0 * (2.0 * lat).to_radians().sin() + lon.to_radians().cos();
+    assert!((wind.value_at(30.0, 40.0) as f64 - expected(30.0, 40.0)).abs() < 0.01);
+    assert!((wind.value_at(-80.0, 180.0) as f64 - expected(-80.0, 180.0)).abs() < 0.01);
+    assert!(wind.value_at(90.0, 0.0).is_nan());
+}
+
+#[test]
+fn malformed_messages_are_skipped() {
+    let sample = include_bytes!("../../../data/sample.grib2");
+
+    // Truncated files must not panic.
+    for len in 0..sample.len() {
+        GribFile::parse(&sample[..len]);
+    }
+
+    // Neither must corrupt lengths, section numbers and packing parameters.
+    for offset in 8..sample.len() {
+        for byte in &[0x00, 0x01, 0x7F, 0xFF] {
+            let mut data = sample.to_vec();
+            data[offset] = *byte;
+            GribFile::parse(&data);
+        }
+    }
+
+    // A message length of zero is skipped instead of looping forever.
+    let mut data = sample.to_vec();
+    for byte in &mut data[8..16] {
+        *byte = 0;
+    }
+    assert!(GribFile::parse(&data).fields().len() < 2);
+}
@@ -0,0 +1,182 @@
+use super::*;
+
+/// A regular latitude/longitude grid (grid definition template 3.0).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatLonGrid {
+    /// The number of points along a parallel.
+    pub ni: usize,
+    /// The number of points along a meridian.
+    pub nj: usize,
+    /// The latitude of the first grid point in degrees.
+    pub la1: f64,
+    /// The longitude of the first grid point in degrees.
+    pub lo1: f64,
+    /// The latitude of the last grid point in degrees.
+    pub la2: f64,
+    /// The longitude of the last grid point in degrees.
+    pub lo2: f64,
+    /// The increment along a parallel in degrees.
+    pub di: f64,
+    /// The increment along a meridian in degrees.
+    pub dj: f64,
+    /// The scanning mode flags.
+    pub scanning_mode: u8,
+}
+
+impl LatLonGrid {
+    /// Parses the grid definition section.
+    pub(crate) fn parse(section: &[u8]) -> Option<Self> {
+        let template = read_unsigned(section, 12, 2)?;
+        if template != 0 {
+            log::warn!(
+                "The GRIB grid definition template 3.{} is not supported.",
+                template
+            );
+            return None;
+        }
+
+        // Angles are in micro degrees unless a basic angle and subdivisions are given.
+        let basic_angle = read_unsigned(section, 38, 4)?;
+        let subdivisions = read_unsigned(section, 42, 4)?;
+        let unit = if basic_angle == 0 || subdivisions == 0xFFFF_FFFF {
+            1e-6
+        } else {
+            basic_angle as f64 / subdivisions as f64
+        };
+        let angle = |offset| read_signed(section, offset, 4).map(|v| v as f64 * unit);
+
+        let ni = read_unsigned(section, 30, 4)? as usize;
+        let nj = read_unsigned(section, 34, 4)? as usize;
+        if ni == 0 || nj == 0 || ni.checked_mul(nj).is_none() {
+            log::warn!("The GRIB grid of {}x{} points is invalid.", ni, nj);
+            return None;
+        }
+        let la1 = angle(46)?;
+        let lo1 = angle(50)?;
+        let la2 = angle(55)?;
+        let lo2 = angle(59)?;
+        let scanning_mode = *section.get(71)?;
+
+        // Some producers don't give the increments, so derive them from the corners.
+        let di = match read_unsigned(section, 63, 4)? {
+            0xFFFF_FFFF => normalize_degrees(lo2 - lo1) / (ni.max(2) - 1) as f64,
+            di => di as f64 * unit,
+        };
+        let dj = match read_unsigned(section, 67, 4)? {
+            0xFFFF_FFFF => (la2 - la1).abs() / (nj.max(2) - 1) as f64,
+            dj => dj as f64 * unit,
+        };
+
+        Some(Self {
+            ni,
+            nj,
+            la1,
+            lo1,
+            la2,
+            lo2,
+            di,
+            dj,
+            scanning_mode,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ni * self.nj
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the grid wraps around the whole earth along the parallels.
+    pub fn is_global(&self) -> bool {
+        (self.ni as f64 * self.di - 360.0).abs() < self.di / 2.0
+    }
+
+    /// Returns the index into the values of the grid point `(i, j)`.
+    fn index(&self, i: usize, j: usize) -> usize {
+        if self.scanning_mode & 0x20 != 0 {
+            // Adjacent points are consecutive along a meridian.
+            i * self.nj + j
+        } else {
+            j * self.ni + i
+        }
+    }
+
+    /// Returns the position of the grid point `(i, j)`.
+    pub fn position(&self, i: usize, j: usize) -> GeoPoint {
+        self.position_at(i as f64, j as f64)
+    }
+
+    /// Returns the position of fractional grid coordinates.
+    pub fn position_at(&self, i: f64, j: f64) -> GeoPoint {
+        let lon = if self.scanning_mode & 0x80 != 0 {
+            self.lo1 - i * self.di
+        } else {
+            self.lo1 + i * self.di
+        };
+        let lat = if self.scanning_mode & 0x40 != 0 {
+            self.la1 + j * self.dj
+        } else {
+            self.la1 - j * self.dj
+        };
+        GeoPoint::new(lat, normalize_longitude(lon))
+    }
+
+    /// Returns the value of the grid point `(i, j)` or NaN if it is missing.
+    pub fn value(&self, values: &[f32], i: usize, j: usize) -> f32 {
+        values.get(self.index(i, j)).copied().unwrap_or(f32::NAN)
+    }
+
+    /// Returns the fractional grid coordinates of a position.
+    fn grid_coordinates(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let i = if self.scanning_mode & 0x80 != 0 {
+            normalize_degrees(self.lo1 - lon) / self.di
+        } else {
+            normalize_degrees(lon - self.lo1) / self.di
+        };
+        let j = if self.scanning_mode & 0x40 != 0 {
+            (lat - self.la1) / self.dj
+        } else {
+            (self.la1 - lat) / self.dj
+        };
+        (i, j)
+    }
+
+    /// Bilinearly interpolates `values` defined on this grid at a position.
+    ///
+    /// Returns NaN outside of the grid or if any of the surrounding values is missing.
+    pub fn interpolate(&self, values: &[f32], lat: f64, lon: f64) -> f32 {
+        let (i, j) = self.grid_coordinates(lat, lon);
+        if j < 0.0 || j > (self.nj - 1) as f64 {
+            return f32::NAN;
+        }
+
+        let i0 = i.floor() as usize;
+        let j0 = j.floor() as usize;
+        let (i1, j1) = if self.is_global() {
+            ((i0 + 1) % self.ni, (j0 + 1).min(self.nj - 1))
+        } else {
+            if i > (self.ni - 1) as f64 {
+                return f32::NAN;
+            }
+            ((i0 + 1).min(self.ni - 1), (j0 + 1).min(self.nj - 1))
+        };
+        let i0 = i0 % self.ni;
+        let ti = (i - i.floor()) as f32;
+        let tj = (j - j.floor()) as f32;
+
+        // Neighbours without weight are skipped so values on a grid line next to missing data survive.
+        let weighted = |i, j, weight: f32| {
+            if weight == 0.0 {
+                0.0
+            } else {
+                self.value(values, i, j) * weight
+            }
+        };
+        weighted(i0, j0, (1.0 - ti) * (1.0 - tj))
+            + weighted(i1, j0, ti * (1.0 - tj))
+            + weighted(i0, j1, (1.0 - ti) * tj)
+            + weighted(i1, j1, ti * tj)
+    }
+}
@@ -0,0 +1,13 @@
+mod bits;
+mod field;
+mod grid;
+mod packing;
+mod wind;
+
+pub use bits::*;
+pub use field::*;
+pub use grid::*;
+pub use packing::*;
+pub use wind::*;
+
+use crate::*;
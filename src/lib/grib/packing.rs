@@ -0,0 +1,223 @@
+use super::*;
+
+/// How the values of a field are packed (data representation section).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRepresentation {
+    /// The number of packed values.
+    pub count: usize,
+    pub template: u16,
+    /// The reference value `R`.
+    pub reference: f32,
+    /// The binary scale factor `E`.
+    pub binary_scale: i32,
+    /// The decimal scale factor `D`.
+    pub decimal_scale: i32,
+    pub bits_per_value: usize,
+    complex: Option<ComplexPacking>,
+}
+
+/// The parameters of complex packing (templates 5.2 and 5.3).
+#[derive(Debug, Clone, PartialEq)]
+struct ComplexPacking {
+    missing_value_management: u8,
+    groups: usize,
+    group_width_reference: u64,
+    group_width_bits: usize,
+    group_length_reference: u64,
+    group_length_increment: u64,
+    last_group_length: u64,
+    group_length_bits: usize,
+    /// The order of spatial differencing and the number of octets of the extra descriptors (template 5.3 only).
+    spatial_differencing: Option<(u8, usize)>,
+}
+
+impl DataRepresentation {
+    /// Parses the data representation section.
+    pub(crate) fn parse(section: &[u8]) -> Option<Self> {
+        let count = read_unsigned(section, 5, 4)? as usize;
+        let template = read_unsigned(section, 9, 2)? as u16;
+        if template != 0 && template != 2 && template != 3 {
+            log::warn!(
+                "The GRIB data representation template 5.{} is not supported.",
+                template
+            );
+            return None;
+        }
+
+        let spatial_differencing = if template == 3 {
+            let order = *section.get(47)?;
+            let octets = *section.get(48)? as usize;
+            if order != 1 && order != 2 || octets == 0 || octets > 8 {
+                log::warn!(
+                    "The GRIB spatial differencing of order {} with {} octets is not supported.",
+                    order,
+                    octets
+                );
+                return None;
+            }
+            Some((order, octets))
+        } else {
+            None
+        };
+
+        let complex = if template == 0 {
+            None
+        } else {
+            Some(ComplexPacking {
+                missing_value_management: *section.get(22)?,
+                groups: read_unsigned(section, 31, 4)? as usize,
+                group_width_reference: read_unsigned(section, 35, 1)?,
+                group_width_bits: read_unsigned(section, 36, 1)? as usize,
+                group_length_reference: read_unsigned(section, 37, 4)?,
+                group_length_increment: read_unsigned(section, 41, 1)?,
+                last_group_length: read_unsigned(section, 42, 4)?,
+                group_length_bits: read_unsigned(section, 46, 1)? as usize,
+                spatial_differencing,
+            })
+        };
+
+        Some(Self {
+            count,
+            template,
+            reference: f32::from_bits(read_unsigned(section, 11, 4)? as u32),
+            binary_scale: read_signed(section, 15, 2)? as i32,
+            decimal_scale: read_signed(section, 17, 2)? as i32,
+            bits_per_value: *section.get(19)? as usize,
+            complex,
+        })
+    }
+
+    /// Unpacks the data section into physical values.
+    ///
+    /// Missing values are returned as NaN.
+    pub fn unpack(&self, data: &[u8]) -> Option<Vec<f32>> {
+        let packed = match &self.complex {
+            None => self.unpack_simple(data)?,
+            Some(complex) => self.unpack_complex(complex, data)?,
+        };
+
+        let binary = 2f64.powi(self.binary_scale);
+        let decimal = 10f64.powi(-self.decimal_scale);
+        let reference = self.reference as f64;
+        Some(
+            packed
+                .into_iter()
+                .map(|value| match value {
+                    Some(value) => ((reference + value as f64 * binary) * decimal) as f32,
+                    None => f32::NAN,
+                })
+                .collect(),
+        )
+    }
+
+    fn unpack_simple(&self, data: &[u8]) -> Option<Vec<Option<i64>>> {
+        let mut reader = BitReader::new(data);
+        (0..self.count)
+            .map(|_| reader.read(self.bits_per_value).map(|v| Some(v as i64)))
+            .collect()
+    }
+
+    fn unpack_complex(&self, complex: &ComplexPacking, data: &[u8]) -> Option<Vec<Option<i64>>> {
+        let mut reader = BitReader::new(data);
+
+        // The first values and the minimum of the spatial differences are stored up front.
+        let mut first_values = vec![];
+        let mut minimum = 0;
+        if let Some((order, octets)) = complex.spatial_differencing {
+            // Unlike the minimum the first values are unsigned.
+            for _ in 0..order {
+                first_values.push(reader.read(octets * 8)? as i64);
+            }
+            minimum = reader.read_signed(octets * 8)?;
+        }
+
+        let references = (0..complex.groups)
+            .map(|_| reader.read(self.bits_per_value))
+            .collect::<Option<Vec<_>>>()?;
+        reader.align();
+        let widths = (0..complex.groups)
+            .map(|_| {
+                let width = reader.read(complex.group_width_bits)?;
+                width
+                    .checked_add(complex.group_width_reference)
+                    .map(|w| w as usize)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        reader.align();
+        let mut lengths = (0..complex.groups)
+            .map(|_| {
+                let length = reader.read(complex.group_length_bits)?;
+                length
+                    .checked_mul(complex.group_length_increment)?
+                    .checked_add(complex.group_length_reference)
+                    .map(|l| l as usize)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if let Some(last) = lengths.last_mut() {
+            *last = complex.last_group_length as usize;
+        }
+        reader.align();
+
+        let mut values = Vec::with_capacity(self.count);
+        let management = complex.missing_value_management;
+        for ((reference, width), length) in references.iter().zip(widths).zip(lengths) {
+            // Don't let a corrupt group length allocate more values than the field has.
+            if length > self.count - values.len() {
+                return None;
+            }
+            for _ in 0..length {
+                let value = if width == 0 {
+                    if is_missing(management, *reference, self.bits_per_value) {
+                        None
+                    } else {
+                        Some(*reference as i64)
+                    }
+                } else {
+                    let packed = reader.read(width)?;
+                    if is_missing(management, packed, width) {
+                        None
+                    } else {
+                        Some(reference.checked_add(packed)? as i64)
+                    }
+                };
+                values.push(value);
+            }
+        }
+
+        if complex.spatial_differencing.is_some() {
+            Self::undo_spatial_differencing(&mut values, &first_values, minimum);
+        }
+
+        Some(values)
+    }
+
+    /// Restores the original values from the first or second order differences of the present values.
+    fn undo_spatial_differencing(values: &mut [Option<i64>], first_values: &[i64], minimum: i64) {
+        let mut previous: Vec<i64> = vec![];
+        for value in values.iter_mut().filter_map(|v| v.as_mut()) {
+            let restored = match (first_values.len(), previous.len()) {
+                (order, n) if n < order => first_values[n],
+                (1, n) => *value + minimum + previous[n - 1],
+                (_, n) => *value + minimum + 2 * previous[n - 1] - previous[n - 2],
+            };
+            *value = restored;
+            previous.push(restored);
+        }
+    }
+}
+
+/// Whether a packed value is one of the missing values of the missing value management.
+///
+/// The primary missing value has all bits set and the secondary one is one below.
+fn is_missing(management: u8, value: u64, bits: usize) -> bool {
+    let all_ones = match bits {
+        0 => 0,
+        1..=63 => (1u64 << bits) - 1,
+        _ => u64::MAX,
+    };
+    match management {
+        1 => value == all_ones,
+        2 => value == all_ones || Some(value) == all_ones.checked_sub(1),
+        _ => false,
+    }
+}
@@ -0,0 +1,163 @@
+use super::*;
+
+const KNOTS_PER_METER_PER_SECOND: f64 = 3600.0 / 1852.0;
+
+/// The wind of one forecast time made up of the U and V component fields.
+pub struct WindField<'a> {
+    u: &'a GribField,
+    v: &'a GribField,
+}
+
+impl<'a> WindField<'a> {
+    /// Pairs the U and V fields of the forecast time closest to `forecast_hours`.
+    pub fn new(file: &'a GribFile, forecast_hours: f64) -> Option<Self> {
+        let u = file.find(GribParameter::WindU, forecast_hours)?;
+        let v = file.find(GribParameter::WindV, u.forecast_hours)?;
+        if u.forecast_hours != v.forecast_hours || u.grid != v.grid {
+            log::warn!(
+                "The wind components at +{}h don't share the same grid.",
+                u.forecast_hours
+            );
+            return None;
+        }
+        Some(Self { u, v })
+    }
+
+    pub fn forecast_hours(&self) -> f64 {
+        self.u.forecast_hours
+    }
+
+    pub fn reference_time(&self) -> f64 {
+        self.u.reference_time
+    }
+
+    pub fn grid(&self) -> &LatLonGrid {
+        &self.u.grid
+    }
+
+    /// Returns the wind at the grid point `(i, j)` or `None` if it is missing.
+    pub fn at_grid_point(&self, i: usize, j: usize) -> Option<Wind> {
+        let grid = self.grid();
+        wind_from_components(
+            grid.value(&self.u.values, i, j),
+            grid.value(&self.v.values, i, j),
+        )
+    }
+
+    /// Returns the interpolated wind at a position.
+    pub fn at(&self, position: &GeoPoint) -> Option<Wind> {
+        let (u, v) = self.components_at(position);
+        wind_from_components(u, v)
+    }
+
+    /// Returns the interpolated U and V components in m/s at a position.
+    fn components_at(&self, position: &GeoPoint) -> (f32, f32) {
+        (
+            self.u.value_at(position.lat, position.lon),
+            self.v.value_at(position.lat, position.lon),
+        )
+    }
+}
+
+impl GribFile {
+    /// Returns the wind at a position and a time in seconds since the unix epoch.
+    ///
+    /// The components are interpolated linearly between the two forecast times around `time`.
+    /// Before the first and after the last forecast time the closest one is used.
+    pub fn wind_at(&self, position: &GeoPoint, time: f64) -> Option<Wind> {
+        let wind_fields = || {
+            self.fields()
+                .iter()
+                .filter(|f| f.parameter == GribParameter::WindU)
+        };
+        // All the forecasts of a file share the reference time of the model run.
+        let reference_time = wind_fields().next()?.reference_time;
+        let mut hours: Vec<f64> = wind_fields().map(|f| f.forecast_hours).collect();
+        hours.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        hours.dedup();
+
+        let elapsed = (time - reference_time) / 3600.0;
+        let after = hours.iter().position(|h| *h >= elapsed);
+        let (before, after) = match after {
+            Some(0) => (hours[0], hours[0]),
+            Some(i) => (hours[i - 1], hours[i]),
+            None => (*hours.last()?, *hours.last()?),
+        };
+
+        let (u0, v0) = WindField::new(self, before)?.components_at(position);
+        if before == after {
+            return wind_from_components(u0, v0);
+        }
+        let (u1, v1) = WindField::new(self, after)?.components_at(position);
+        let t = ((elapsed - before) / (after - before)) as f32;
+        wind_from_components(u0 + (u1 - u0) * t, v0 + (v1 - v0) * t)
+    }
+}
+
+/// Converts the U and V components in m/s to the direction the wind is coming from and the speed in knots.
+fn wind_from_components(u: f32, v: f32) -> Option<Wind> {
+    if u.is_nan() || v.is_nan() {
+        return None;
+    }
+    let (u, v) = (u as f64, v as f64);
+    Some(Wind {
+        angle: normalize_degrees((-u).atan2(-v).to_degrees()),
+        speed: (u * u + v * v).sqrt() * KNOTS_PER_METER_PER_SECOND,
+    })
+}
+
+/// The symbols of a wind barb for a speed rounded to 5 knots.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WindBarb {
+    /// Pennants of 50 knots each.
+    pub pennants: u32,
+    /// Full barbs of 10 knots each.
+    pub barbs: u32,
+    /// Whether there is a half barb for 5 knots.
+    pub half_barb: bool,
+}
+
+impl WindBarb {
+    pub fn from_speed(knots: f64) -> Self {
+        let rounded = (knots / 5.0).round().max(0.0) as u32 * 5;
+        Self {
+            pennants: rounded / 50,
+            barbs: rounded % 50 / 10,
+            half_barb: rounded % 10 == 5,
+        }
+    }
+
+    /// Whether the wind is calm, which is drawn as a circle instead of a barb.
+    pub fn is_calm(&self) -> bool {
+        self.pennants == 0 && self.barbs == 0 && !self.half_barb
+    }
+}
+
+#[test]
+fn wind_components_to_barb() {
+    // A northerly wind blows towards the south.
+    let wind = wind_from_components(0.0, -10.0).unwrap();
+    assert!(wind.angle.abs() < 1e-9);
+    assert!((wind.speed - 19.438).abs() < 0.001);
+    let wind = wind_from_components(5.0, 0.0).unwrap();
+    assert!((wind.angle - 270.0).abs() < 1e-9);
+    assert!(wind_from_components(f32::NAN, 0.0).is_none());
+
+    assert_eq!(
+        WindBarb::from_speed(19.438),
+        WindBarb {
+            pennants: 0,
+            barbs: 2,
+            half_barb: false
+        }
+    );
+    assert_eq!(
+        WindBarb::from_speed(66.0),
+        WindBarb {
+            pennants: 1,
+            barbs: 1,
+            half_barb: true
+        }
+    );
+    assert!(WindBarb::from_speed(2.0).is_calm());
+}
@@ -1,5 +1,6 @@
 pub use crate::*;
 use ncollide2d::{
+    bounding_volume::AABB,
     math::{Isometry, Point, Vector},
     pipeline::object::{CollisionGroups, GeometricQueryType},
     query::Ray,
@@ -77,6 +78,20 @@ impl TileCollider {
 
         object_ids
     }
+
+    /// Returns the ids of all objects whose bounding boxes overlap the rectangle between `min` and `max`.
+    pub fn get_objects_in_area(&self, min: &Point<f32>, max: &Point<f32>) -> Vec<usize> {
+        let mut interferences = vec![];
+        self.world
+            .broad_phase
+            .interferences_with_bounding_volume(&AABB::new(*min, *max), &mut interferences);
+
+        interferences
+            .into_iter()
+            .filter_map(|handle| self.world.collision_object(*handle))
+            .map(|co| *co.data())
+            .collect()
+    }
 }
 
 pub trait TileColliderLoader {
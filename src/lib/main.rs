@@ -1,21 +1,33 @@
 use nalgebra_glm as glm;
 
+mod ais;
 mod cache;
 mod css;
 mod drawing;
 mod feature;
 mod fetch;
+mod grib;
 mod interaction;
+mod mapbox;
 mod math;
+mod navigation;
+mod nmea;
 mod object;
+mod tide;
 mod vector_tile;
 
+pub use ais::*;
 pub use cache::*;
 pub use css::*;
 pub use drawing::*;
 pub use feature::*;
 pub use fetch::*;
+pub use grib::*;
 pub use interaction::*;
+pub use mapbox::*;
 pub use math::*;
+pub use navigation::*;
+pub use nmea::*;
 pub use object::*;
+pub use tide::*;
 pub use vector_tile::*;
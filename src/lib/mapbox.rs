@@ -0,0 +1,818 @@
+use crate::*;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The imported layers are stacked below this z-index, above it are the overlays like the own ship.
+const MAX_Z_INDEX: f32 = 90.0;
+
+/// Filters that expand to more selectors than this are cut off.
+const MAX_ALTERNATIVES: usize = 64;
+
+/// The rules converted from a Mapbox GL style.
+#[derive(Debug)]
+pub struct MapboxImport {
+    pub rules: Vec<Rule>,
+    /// Everything that could not be converted, each prefixed with the id of its layer.
+    pub unsupported: Vec<String>,
+}
+
+/// Converts the layers of a Mapbox or MapLibre GL style JSON into rules.
+///
+/// `fill`, `line`, `symbol` and `background` layers become one rule per alternative of their `filter`.
+/// The selectors match features by their `source-layer` and their `class` tag, all other tags are tested in
+/// `:where(...)` filters, so they don't need to be listed in the `selection_tags` of the renderer. Zoom stops and `interpolate` or `step` expressions over
+/// the zoom become interpolated values and the order of the layers becomes their z-index.
+///
+/// Data driven values are not supported, neither are labels and icons, so `symbol` layers only keep their visibility.
+pub fn import_mapbox_style(json: &str) -> Option<MapboxImport> {
+    let style: Value = match serde_json::from_str(json) {
+        Ok(style) => style,
+        Err(err) => {
+            log::warn!("Unable to parse the Mapbox GL style:");
+            log::warn!("{}", err);
+            return None;
+        }
+    };
+    let layers = match style.get("layers").and_then(Value::as_array) {
+        Some(layers) => layers,
+        None => {
+            log::warn!("The Mapbox GL style has no layers.");
+            return None;
+        }
+    };
+
+    let mut import = MapboxImport {
+        rules: vec![],
+        unsupported: vec![],
+    };
+    for (index, layer) in layers.iter().enumerate() {
+        let mut report = Report {
+            layer: layer.get("id").and_then(Value::as_str).unwrap_or("?"),
+            unsupported: &mut import.unsupported,
+        };
+        let z_index = index as f32 * MAX_Z_INDEX / layers.len() as f32;
+        import
+            .rules
+            .extend(convert_layer(layer, z_index, &mut report));
+    }
+    Some(import)
+}
+
+/// Collects what could not be converted in a layer.
+struct Report<'a> {
+    layer: &'a str,
+    unsupported: &'a mut Vec<String>,
+}
+
+impl Report<'_> {
+    fn unsupported(&mut self, message: impl std::fmt::Display) {
+        self.unsupported
+            .push(format!("layer '{}': {}", self.layer, message));
+    }
+}
+
+/// The kinds of values of the converted paint properties.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Unit {
+    Color,
+    Px,
+    Unitless,
+}
+
+/// Converts a single style layer into rules.
+fn convert_layer(layer: &Value, z_index: f32, report: &mut Report) -> Vec<Rule> {
+    let typ = layer
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let base = match typ {
+        "background" => Selector::new().with_type("background"),
+        "fill" | "line" | "symbol" => match layer.get("source-layer").and_then(Value::as_str) {
+            Some(source_layer) => Selector::new()
+                .with_type("layer")
+                .with_any("name", source_layer),
+            None => {
+                report.unsupported("a layer without a source-layer");
+                return vec![];
+            }
+        },
+        typ => {
+            report.unsupported(format!("the layer type '{}'", typ));
+            return vec![];
+        }
+    };
+
+    let mut base = base;
+    if let Some(zoom) = layer.get("minzoom").and_then(Value::as_f64) {
+        base = base.with_condition("zoom", AttributeOperator::GreaterOrEqual, zoom.to_string());
+    }
+    if let Some(zoom) = layer.get("maxzoom").and_then(Value::as_f64) {
+        base = base.with_condition("zoom", AttributeOperator::Less, zoom.to_string());
+    }
+    let selectors = match layer.get("filter") {
+        Some(filter) => and(vec![base], convert_filter(filter, report), report),
+        None => vec![base],
+    };
+
+    let kvs = convert_properties(typ, layer, report);
+    if kvs.is_empty() {
+        return vec![];
+    }
+    let mut kvs = kvs;
+    if typ != "background" {
+        kvs.insert(
+            "z-index".to_string(),
+            CSSValue::Number(Number::Unitless(z_index)),
+        );
+    }
+
+    selectors
+        .into_iter()
+        .map(|selector| Rule {
+            selector,
+            kvs: kvs.clone(),
+            important: BTreeSet::new(),
+            file: 0,
+        })
+        .collect()
+}
+
+/// Converts the `paint` and `layout` properties of a layer into declarations.
+fn convert_properties(typ: &str, layer: &Value, report: &mut Report) -> BTreeMap<String, CSSValue> {
+    let mut kvs = BTreeMap::new();
+    let mut opacity = None;
+    let no_properties = serde_json::Map::new();
+
+    let paint = layer
+        .get("paint")
+        .and_then(Value::as_object)
+        .unwrap_or(&no_properties);
+    for (name, value) in paint {
+        let (property, unit) = match (typ, name.as_str()) {
+            ("background", "background-color")
+            | ("fill", "fill-color")
+            | ("line", "line-color") => ("background-color", Unit::Color),
+            ("fill", "fill-outline-color") => ("border-color", Unit::Color),
+            ("line", "line-width") => ("line-width", Unit::Px),
+            ("background", "background-opacity")
+            | ("fill", "fill-opacity")
+            | ("line", "line-opacity") => {
+                opacity = convert_value(value, Unit::Unitless, report);
+                continue;
+            }
+            // Sailor draws the edges of areas smoothly anyway.
+            ("fill", "fill-antialias") => continue,
+            (_, name) => {
+                report.unsupported(format!("the paint property '{}'", name));
+                continue;
+            }
+        };
+        if let Some(value) = convert_value(value, unit, report) {
+            kvs.insert(property.to_string(), value);
+        }
+    }
+    if kvs.contains_key("border-color") {
+        kvs.insert(
+            "border-width".to_string(),
+            CSSValue::Number(Number::Px(1.0)),
+        );
+    }
+
+    if let Some(opacity) = opacity {
+        for property in &["background-color", "border-color"] {
+            if let Some(color) = kvs.get_mut(*property) {
+                match with_opacity(color, &opacity) {
+                    Some(value) => *color = value,
+                    None => report.unsupported(format!(
+                        "an opacity interpolated at other zoom levels than the '{}'",
+                        property
+                    )),
+                }
+            }
+        }
+    }
+
+    let layout = layer
+        .get("layout")
+        .and_then(Value::as_object)
+        .unwrap_or(&no_properties);
+    for (name, value) in layout {
+        match (name.as_str(), value.as_str()) {
+            ("visibility", Some("none")) => {
+                kvs.insert("display".to_string(), CSSValue::String("none".to_string()));
+            }
+            ("visibility", _) => {}
+            (name, _) => report.unsupported(format!("the layout property '{}'", name)),
+        }
+    }
+    kvs
+}
+
+/// Converts a constant, zoom stops or a zoom expression into a value.
+fn convert_value(value: &Value, unit: Unit, report: &mut Report) -> Option<CSSValue> {
+    let stops = match value {
+        Value::String(color) if unit == Unit::Color => {
+            return match parse_value(color).and_then(|value| value.to_color()) {
+                Some(color) => Some(CSSValue::Color(color)),
+                None => {
+                    report.unsupported(format!("the color '{}'", color));
+                    None
+                }
+            };
+        }
+        Value::Number(number) if unit != Unit::Color => {
+            let number = number.as_f64()? as f32;
+            return Some(CSSValue::Number(match unit {
+                Unit::Px => Number::Px(number),
+                _ => Number::Unitless(number),
+            }));
+        }
+        Value::Object(function) => {
+            if function.contains_key("property") {
+                report.unsupported("a data driven function");
+                return None;
+            }
+            let base = function.get("base").and_then(Value::as_f64).unwrap_or(1.0);
+            let stops = function
+                .get("stops")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|stop| {
+                    let stop = stop.as_array()?;
+                    Some((stop.first()?.as_f64()?, stop.get(1)?))
+                })
+                .collect::<Option<Vec<_>>>();
+            match stops {
+                Some(stops) if !stops.is_empty() => (base, stops),
+                _ => {
+                    report.unsupported("a function without zoom stops");
+                    return None;
+                }
+            }
+        }
+        Value::Array(expression) => match convert_expression(expression, report)? {
+            Expression::Constant(value) => return convert_value(value, unit, report),
+            Expression::Stops(base, stops) => (base, stops),
+        },
+        value => {
+            report.unsupported(format!("the value {}", value));
+            return None;
+        }
+    };
+
+    let (base, stops) = stops;
+    let stops = stops
+        .into_iter()
+        .map(|(zoom, value)| match convert_value(value, unit, report)? {
+            CSSValue::Interpolate(_) => {
+                report.unsupported("nested zoom stops");
+                None
+            }
+            value => Some((zoom as f32, value)),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(CSSValue::Interpolate(Interpolation {
+        base: base as f32,
+        stops,
+    }))
+}
+
+/// A value expression reduced to what sailor can display.
+enum Expression<'a> {
+    Constant(&'a Value),
+    /// The base of the curve and the values at the zoom levels.
+    Stops(f64, Vec<(f64, &'a Value)>),
+}
+
+/// Converts `interpolate` and `step` expressions over the zoom and `literal` values.
+fn convert_expression<'a>(expression: &'a [Value], report: &mut Report) -> Option<Expression<'a>> {
+    let operator = expression
+        .first()
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let is_zoom = |input: Option<&Value>| {
+        input
+            .and_then(Value::as_array)
+            .and_then(|input| input.first())
+            == Some(&Value::String("zoom".to_string()))
+    };
+    match operator {
+        "literal" => Some(Expression::Constant(expression.get(1)?)),
+        "interpolate" if is_zoom(expression.get(2)) => {
+            let interpolation = expression.get(1).and_then(Value::as_array)?;
+            let base = match interpolation.first().and_then(Value::as_str) {
+                Some("linear") => 1.0,
+                Some("exponential") => interpolation.get(1).and_then(Value::as_f64)?,
+                Some(curve) => {
+                    report.unsupported(format!("the '{}' curve, interpolated linearly", curve));
+                    1.0
+                }
+                None => return None,
+            };
+            let stops = zoom_stops(&expression[3..], report)?;
+            if stops.is_empty() {
+                report.unsupported("an interpolation without stops");
+                return None;
+            }
+            Some(Expression::Stops(base, stops))
+        }
+        "step" if is_zoom(expression.get(1)) => {
+            // Switch the value right at the step by repeating the previous value just before it.
+            let mut value = match expression.get(2) {
+                Some(value) => value,
+                None => {
+                    report.unsupported("a step without a value");
+                    return None;
+                }
+            };
+            let mut stops = vec![];
+            for (zoom, next) in zoom_stops(&expression[3..], report)? {
+                stops.push((zoom - 0.001, value));
+                value = next;
+                stops.push((zoom, value));
+            }
+            if stops.is_empty() {
+                Some(Expression::Constant(value))
+            } else {
+                Some(Expression::Stops(1.0, stops))
+            }
+        }
+        operator => {
+            report.unsupported(format!("the expression '{}'", operator));
+            None
+        }
+    }
+}
+
+/// Pairs up the zoom levels and values of the stops of an expression.
+fn zoom_stops<'a>(stops: &'a [Value], report: &mut Report) -> Option<Vec<(f64, &'a Value)>> {
+    stops
+        .chunks(2)
+        .map(|stop| match (stop[0].as_f64(), stop.get(1)) {
+            (Some(zoom), Some(value)) => Some((zoom, value)),
+            (None, _) => {
+                report.unsupported(format!("the stop at the zoom level {}", stop[0]));
+                None
+            }
+            (_, None) => {
+                report.unsupported(format!(
+                    "the stop at the zoom level {} without a value",
+                    stop[0]
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Multiplies the alpha of a color with an opacity, both may be interpolated at the same zoom levels.
+fn with_opacity(color: &CSSValue, opacity: &CSSValue) -> Option<CSSValue> {
+    match (color, opacity) {
+        (CSSValue::Color(color), CSSValue::Number(Number::Unitless(opacity))) => {
+            Some(CSSValue::Color(Color {
+                a: color.a * opacity,
+                ..color.clone()
+            }))
+        }
+        (CSSValue::Interpolate(interpolation), CSSValue::Number(_)) => {
+            Some(CSSValue::Interpolate(Interpolation {
+                base: interpolation.base,
+                stops: interpolation
+                    .stops
+                    .iter()
+                    .map(|(zoom, color)| Some((*zoom, with_opacity(color, opacity)?)))
+                    .collect::<Option<_>>()?,
+            }))
+        }
+        (CSSValue::Color(_), CSSValue::Interpolate(interpolation)) => {
+            Some(CSSValue::Interpolate(Interpolation {
+                base: interpolation.base,
+                stops: interpolation
+                    .stops
+                    .iter()
+                    .map(|(zoom, opacity)| Some((*zoom, with_opacity(color, opacity)?)))
+                    .collect::<Option<_>>()?,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Converts a filter in the legacy or the expression syntax into the selectors of which any has to match.
+///
+/// Parts that can not be expressed with selectors are reported and left out, so the rules match more features.
+fn convert_filter(filter: &Value, report: &mut Report) -> Vec<Selector> {
+    let filter = match filter {
+        Value::Bool(true) => return vec![Selector::new()],
+        Value::Bool(false) => return vec![],
+        Value::Array(filter) if !filter.is_empty() => filter,
+        filter => {
+            report.unsupported(format!("the filter {}", filter));
+            return vec![Selector::new()];
+        }
+    };
+    let operator = filter[0].as_str().unwrap_or_default();
+    let args = &filter[1..];
+    match operator {
+        "all" => args
+            .iter()
+            .fold(vec![Selector::new()], |selectors, filter| {
+                let alternatives = convert_filter(filter, report);
+                and(selectors, alternatives, report)
+            }),
+        "any" => {
+            let mut selectors = vec![];
+            for filter in args {
+                selectors.extend(convert_filter(filter, report));
+            }
+            limit(selectors, report)
+        }
+        "none" => {
+            let negated = args.iter().map(negate_filter).collect::<Vec<_>>();
+            convert_filter(&all(negated), report)
+        }
+        "!" => match args.first() {
+            Some(filter) => convert_filter(&negate_filter(filter), report),
+            None => vec![Selector::new()],
+        },
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => {
+            match (
+                args.first().and_then(filter_key),
+                args.get(1).and_then(filter_value),
+            ) {
+                (Some(key), Some(value)) => compare(operator, &key, &value, report),
+                _ => {
+                    report.unsupported(format!("the filter {}", Value::Array(filter.clone())));
+                    vec![Selector::new()]
+                }
+            }
+        }
+        "in" | "!in" => {
+            let key = args.first().and_then(filter_key);
+            // The expression syntax has the values in a literal array, the legacy one right after the key.
+            let values = match args.get(1).and_then(Value::as_array) {
+                Some(literal)
+                    if args[0].is_array() && literal.first() == Some(&"literal".into()) =>
+                {
+                    literal.get(1).and_then(Value::as_array).map(Vec::as_slice)
+                }
+                _ => args.get(1..),
+            };
+            let values =
+                values.map(|values| values.iter().filter_map(filter_value).collect::<Vec<_>>());
+            match (key, values) {
+                (Some(key), Some(values)) if operator == "in" => {
+                    let selectors = values
+                        .iter()
+                        .flat_map(|value| compare("==", &key, value, report))
+                        .collect();
+                    limit(selectors, report)
+                }
+                (Some(key), Some(values)) => {
+                    values
+                        .iter()
+                        .fold(vec![Selector::new()], |selectors, value| {
+                            let alternatives = compare("!=", &key, value, report);
+                            and(selectors, alternatives, report)
+                        })
+                }
+                _ => {
+                    report.unsupported(format!("the filter {}", Value::Array(filter.clone())));
+                    vec![Selector::new()]
+                }
+            }
+        }
+        "match" => convert_match(filter, report),
+        "has" | "!has" => match args.first().and_then(filter_key) {
+            Some(key) if key != "$type" => {
+                let has = Filter::Condition(AttributeCondition {
+                    key,
+                    operator: AttributeOperator::Exists,
+                    value: String::new(),
+                });
+                let filter = if operator == "has" {
+                    has
+                } else {
+                    Filter::Not(Box::new(has))
+                };
+                vec![Selector::new().with_filter(filter)]
+            }
+            _ => {
+                report.unsupported(format!("the filter {}", Value::Array(filter.clone())));
+                vec![Selector::new()]
+            }
+        },
+        _ => {
+            report.unsupported(format!("the filter {}", Value::Array(filter.clone())));
+            vec![Selector::new()]
+        }
+    }
+}
+
+/// Converts a `match` expression with boolean outputs like `["match", ["get", "class"], ["lake", "pond"], true, false]`.
+fn convert_match(filter: &[Value], report: &mut Report) -> Vec<Selector> {
+    let key = filter.get(1).and_then(filter_key);
+    let fallback = filter.last().and_then(Value::as_bool);
+    let (key, fallback) = match (key, fallback) {
+        (Some(key), Some(fallback)) if filter.len() % 2 == 1 => (key, fallback),
+        _ => {
+            report.unsupported(format!("the filter {}", Value::Array(filter.to_vec())));
+            return vec![Selector::new()];
+        }
+    };
+
+    // Only the labels with the other output than the fallback matter.
+    let mut labels = vec![];
+    for arm in filter[2..filter.len() - 1].chunks(2) {
+        if arm[1].as_bool() == Some(!fallback) {
+            match &arm[0] {
+                Value::Array(values) => labels.extend(values.iter().cloned()),
+                value => labels.push(value.clone()),
+            }
+        }
+    }
+    let operator = if fallback { "!in" } else { "in" };
+    let mut legacy = vec![Value::from(operator), Value::from(key)];
+    legacy.extend(labels);
+    convert_filter(&Value::Array(legacy), report)
+}
+
+/// Returns the attribute a filter compares, `"$type"` for the geometry type.
+fn filter_key(key: &Value) -> Option<String> {
+    match key {
+        Value::String(key) => Some(key.clone()),
+        Value::Array(expression) => match expression.first()?.as_str()? {
+            "get" => Some(expression.get(1)?.as_str()?.to_string()),
+            "zoom" => Some("zoom".to_string()),
+            "geometry-type" => Some("$type".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns a value a filter compares with as it appears in a selector.
+fn filter_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Converts a single comparison of an attribute.
+///
+/// Only the class and the zoom are part of the selector of every feature, all other tags are tested with `:where(...)`.
+fn compare(operator: &str, key: &str, value: &str, report: &mut Report) -> Vec<Selector> {
+    if key == "$type" {
+        // Sailor styles points, lines and areas of a layer alike.
+        report.unsupported(format!(
+            "the geometry type filter {} {}, ignored",
+            operator, value
+        ));
+        return vec![Selector::new()];
+    }
+    let selector = Selector::new();
+    let operator = match operator {
+        "==" if key == "class" => return vec![selector.with_class(value)],
+        "==" if key == "zoom" => return vec![selector.with_any(key, value)],
+        "==" => {
+            return vec![selector.with_filter(Filter::Equal(key.to_string(), value.to_string()))]
+        }
+        "!=" => AttributeOperator::NotEqual,
+        "<" => AttributeOperator::Less,
+        "<=" => AttributeOperator::LessOrEqual,
+        ">" => AttributeOperator::Greater,
+        ">=" => AttributeOperator::GreaterOrEqual,
+        _ => return vec![selector],
+    };
+    if key == "zoom" {
+        vec![selector.with_condition(key, operator, value)]
+    } else {
+        vec![selector.with_filter(Filter::Condition(AttributeCondition {
+            key: key.to_string(),
+            operator,
+            value: value.to_string(),
+        }))]
+    }
+}
+
+/// Returns the filter matching exactly the features the given filter does not match.
+fn negate_filter(filter: &Value) -> Value {
+    let filter = match filter.as_array() {
+        Some(filter) if !filter.is_empty() => filter,
+        _ => return Value::Bool(filter.as_bool() != Some(true)),
+    };
+    let negated = match filter[0].as_str().unwrap_or_default() {
+        "==" => "!=",
+        "!=" => "==",
+        "<" => ">=",
+        "<=" => ">",
+        ">" => "<=",
+        ">=" => "<",
+        "in" => "!in",
+        "!in" => "in",
+        "has" => "!has",
+        "!has" => "has",
+        "!" => return filter.get(1).cloned().unwrap_or(Value::Bool(true)),
+        "all" => {
+            let mut any = vec![Value::from("any")];
+            any.extend(filter[1..].iter().map(negate_filter));
+            return Value::Array(any);
+        }
+        "any" => return all(filter[1..].iter().map(negate_filter).collect()),
+        "none" => {
+            let mut any = vec![Value::from("any")];
+            any.extend(filter[1..].iter().cloned());
+            return Value::Array(any);
+        }
+        _ => return Value::Array(vec![Value::from("!"), Value::Array(filter.clone())]),
+    };
+    let mut filter = filter.clone();
+    filter[0] = Value::from(negated);
+    Value::Array(filter)
+}
+
+/// Builds an `all` filter.
+fn all(filters: Vec<Value>) -> Value {
+    let mut all = vec![Value::from("all")];
+    all.extend(filters);
+    Value::Array(all)
+}
+
+/// Combines every selector of `a` with every one of `b` and drops the combinations which never match.
+fn and(a: Vec<Selector>, b: Vec<Selector>, report: &mut Report) -> Vec<Selector> {
+    let mut selectors = vec![];
+    for a in &a {
+        for b in &b {
+            if let Some(selector) = merge(a, b) {
+                selectors.push(selector);
+            }
+        }
+    }
+    limit(selectors, report)
+}
+
+/// Cuts off and reports the selectors beyond `MAX_ALTERNATIVES`.
+fn limit(mut selectors: Vec<Selector>, report: &mut Report) -> Vec<Selector> {
+    if selectors.len() > MAX_ALTERNATIVES {
+        report.unsupported(format!(
+            "a filter with {} alternatives, only the first {} are kept",
+            selectors.len(),
+            MAX_ALTERNATIVES
+        ));
+        selectors.truncate(MAX_ALTERNATIVES);
+    }
+    selectors
+}
+
+/// Returns the selector that requires everything `a` and `b` require, `None` if no feature can match it.
+fn merge(a: &Selector, b: &Selector) -> Option<Selector> {
+    let mut selector = a.clone();
+    if selector.typ.is_none() {
+        selector.typ = b.typ.clone();
+    }
+    for class in &b.classes {
+        if !selector.classes.contains(class) {
+            selector.classes.push(class.clone());
+        }
+    }
+    for (key, value) in &b.any {
+        match selector.any.insert(key.clone(), value.clone()) {
+            Some(existing) if &existing != value => return None,
+            _ => {}
+        }
+    }
+    selector.conditions.extend(b.conditions.iter().cloned());
+    if let Some(filter) = &b.filter {
+        selector = selector.with_filter(filter.clone());
+    }
+
+    // A feature has a single class tag.
+    if selector.classes.len() > 1 || selector.contradiction().is_some() {
+        None
+    } else {
+        Some(selector)
+    }
+}
+
+#[test]
+fn mapbox_import() {
+    let import = import_mapbox_style(
+        r##"{
+            "version": 8,
+            "layers": [
+                { "id": "background", "type": "background", "paint": { "background-color": "#f8f4f0" } },
+                {
+                    "id": "water", "type": "fill", "source": "openmaptiles", "source-layer": "water",
+                    "filter": ["all", ["==", "$type", "Polygon"], ["!=", "intermittent", 1]],
+                    "paint": { "fill-color": "hsl(205, 56%, 73%)", "fill-opacity": 0.5 }
+                },
+                {
+                    "id": "waterway", "type": "line", "source-layer": "waterway", "minzoom": 8,
+                    "filter": ["match", ["get", "class"], ["river", "canal"], true, false],
+                    "layout": { "line-cap": "round" },
+                    "paint": {
+                        "line-color": "#a0c8f0",
+                        "line-width": { "base": 1.3, "stops": [[8, 0.5], [20, 6]] }
+                    }
+                },
+                {
+                    "id": "road", "type": "line", "source-layer": "transportation",
+                    "filter": ["all", ["in", "class", "primary", "trunk"], ["!has", "brunnel"]],
+                    "paint": { "line-color": "red", "line-width": ["step", ["zoom"], 1, 12, 3] }
+                },
+                {
+                    "id": "poi", "type": "symbol", "source-layer": "poi",
+                    "layout": { "text-field": "{name}", "visibility": "none" }
+                },
+                { "id": "hillshade", "type": "hillshade", "source": "dem" }
+            ]
+        }"##,
+    )
+    .unwrap();
+
+    let css = serialize_styles(&import.rules);
+    assert!(css.starts_with("background {\n    background-color: rgb(248, 244, 240);\n}\n"));
+    assert!(css.contains(
+        "layer[name=water]:where(intermittent != 1) {\n    background-color: rgba(148, 193, 225, 0.5);\n"
+    ));
+    assert!(css.contains("layer.river[name=waterway][zoom>=8] {\n"));
+    assert!(css.contains("layer.canal[name=waterway][zoom>=8] {\n"));
+    assert!(
+        css.contains("    line-width: interpolate(exponential(1.3), zoom, 8: 0.5px, 20: 6px);\n")
+    );
+    assert!(css.contains("layer.trunk[name=transportation]:where(not has brunnel) {\n"));
+    assert!(css.contains("    line-width: interpolate(zoom, 11.999: 1px, 12: 3px);\n"));
+    assert!(css.contains("layer[name=poi] {\n    display: none;\n    z-index: 60;\n}"));
+    // Everything that was converted parses again.
+    assert_eq!(try_parse_styles(&css).unwrap().len(), import.rules.len());
+
+    assert_eq!(
+        import.unsupported,
+        vec![
+            "layer 'water': the geometry type filter == Polygon, ignored",
+            "layer 'waterway': the layout property 'line-cap'",
+            "layer 'poi': the layout property 'text-field'",
+            "layer 'hillshade': the layer type 'hillshade'",
+        ]
+    );
+}
+
+#[test]
+fn mapbox_import_truncated_filter() {
+    let classes = (0..70)
+        .map(|i| format!("\"c{}\"", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let import = import_mapbox_style(&format!(
+        r##"{{
+            "version": 8,
+            "layers": [{{
+                "id": "many", "type": "line", "source-layer": "transportation",
+                "filter": ["in", "class", {}], "paint": {{ "line-color": "red" }}
+            }}]
+        }}"##,
+        classes
+    ))
+    .unwrap();
+
+    assert_eq!(import.rules.len(), MAX_ALTERNATIVES);
+    assert_eq!(
+        import.unsupported,
+        vec!["layer 'many': a filter with 70 alternatives, only the first 64 are kept"]
+    );
+}
+
+#[test]
+fn mapbox_import_broken_stops() {
+    let import = import_mapbox_style(
+        r##"{
+            "version": 8,
+            "layers": [
+                {
+                    "id": "empty", "type": "line", "source-layer": "transportation",
+                    "paint": { "line-width": ["interpolate", ["linear"], ["zoom"]] }
+                },
+                {
+                    "id": "odd", "type": "line", "source-layer": "waterway",
+                    "paint": { "line-width": ["interpolate", ["linear"], ["zoom"], 8, 1, 14] }
+                }
+            ]
+        }"##,
+    )
+    .unwrap();
+
+    assert_eq!(
+        import.unsupported,
+        vec![
+            "layer 'empty': an interpolation without stops",
+            "layer 'odd': the stop at the zoom level 14 without a value",
+        ]
+    );
+    let css = serialize_styles(&import.rules);
+    assert!(!css.contains("interpolate"));
+    assert_eq!(try_parse_styles(&css).unwrap().len(), import.rules.len());
+}
@@ -0,0 +1,235 @@
+use super::*;
+use std::collections::HashMap;
+
+/// A line of equal value through a scalar grid.
+#[derive(Debug, Clone)]
+pub struct Contour {
+    pub level: f32,
+    /// The points of the line, in grid coordinates when returned by `contour_lines`.
+    pub points: Vec<Point>,
+    /// Whether the last point connects back to the first one.
+    pub closed: bool,
+}
+
+/// Identifies a grid edge by its orientation and its first grid point.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Edge {
+    Horizontal(usize, usize),
+    Vertical(usize, usize),
+}
+
+/// Traces the contour lines of a row major grid with marching squares.
+///
+/// Lines are traced at every multiple of `interval` and smoothed with `smoothing` rounds of Chaikin's corner cutting.
+/// Cells with a missing (NaN) corner are skipped, so lines end at holes in the data.
+pub fn contour_lines(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    interval: f32,
+    smoothing: usize,
+) -> Vec<Contour> {
+    if width < 2 || height < 2 || values.len() < width * height || interval <= 0.0 {
+        return vec![];
+    }
+
+    let (min, max) = values
+        .iter()
+        .filter(|v| !v.is_nan())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+    if min > max {
+        return vec![];
+    }
+
+    let mut contours = vec![];
+    let first = (min / interval).ceil() as i64;
+    let last = (max / interval).floor() as i64;
+    for step in first..=last {
+        let level = step as f32 * interval;
+        for (points, closed) in trace_level(values, width, height, level) {
+            contours.push(Contour {
+                level,
+                points: smooth(points, closed, smoothing),
+                closed,
+            });
+        }
+    }
+    contours
+}
+
+/// Returns the segments of all cells crossing `level` joined into polylines.
+fn trace_level(values: &[f32], width: usize, height: usize, level: f32) -> Vec<(Vec<Point>, bool)> {
+    let value = |x: usize, y: usize| values[y * width + x];
+    let crossing = |edge: Edge| {
+        let (a, b, from, direction) = match edge {
+            Edge::Horizontal(x, y) => (
+                value(x, y),
+                value(x + 1, y),
+                point(x as f32, y as f32),
+                vector(1.0, 0.0),
+            ),
+            Edge::Vertical(x, y) => (
+                value(x, y),
+                value(x, y + 1),
+                point(x as f32, y as f32),
+                vector(0.0, 1.0),
+            ),
+        };
+        from + direction * ((level - a) / (b - a)).clamp(0.0, 1.0)
+    };
+
+    let mut segments: Vec<(Edge, Edge)> = vec![];
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let corners = [
+                value(x, y),
+                value(x + 1, y),
+                value(x + 1, y + 1),
+                value(x, y + 1),
+            ];
+            if corners.iter().any(|v| v.is_nan()) {
+                continue;
+            }
+
+            let case = corners.iter().enumerate().fold(0, |case, (i, v)| {
+                if *v >= level {
+                    case | (1 << i)
+                } else {
+                    case
+                }
+            });
+            let top = Edge::Horizontal(x, y);
+            let right = Edge::Vertical(x + 1, y);
+            let bottom = Edge::Horizontal(x, y + 1);
+            let left = Edge::Vertical(x, y);
+            // Saddles are resolved by the value at the cell center.
+            let center_above = corners.iter().sum::<f32>() / 4.0 >= level;
+            match case {
+                1 | 14 => segments.push((left, top)),
+                2 | 13 => segments.push((top, right)),
+                3 | 12 => segments.push((left, right)),
+                4 | 11 => segments.push((right, bottom)),
+                6 | 9 => segments.push((top, bottom)),
+                7 | 8 => segments.push((left, bottom)),
+                5 | 10 if center_above == (case == 5) => {
+                    segments.push((top, right));
+                    segments.push((bottom, left));
+                }
+                5 | 10 => {
+                    segments.push((left, top));
+                    segments.push((right, bottom));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Every edge is shared by at most two segments, so the lines can be followed through the edges.
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        by_edge.entry(*a).or_default().push(i);
+        by_edge.entry(*b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = vec![];
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let (first, second) = segments[start];
+        let mut edges = vec![first, second];
+
+        // Walk forward from the second edge and then backward from the first one.
+        let mut closed = false;
+        for forward in &[true, false] {
+            loop {
+                let end = if *forward {
+                    *edges.last().unwrap()
+                } else {
+                    edges[0]
+                };
+                let next = by_edge[&end].iter().copied().find(|s| !used[*s]);
+                let next = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                used[next] = true;
+                let (a, b) = segments[next];
+                let other = if a == end { b } else { a };
+                if (*forward && other == edges[0]) || (!*forward && other == *edges.last().unwrap())
+                {
+                    closed = true;
+                    break;
+                }
+                if *forward {
+                    edges.push(other);
+                } else {
+                    edges.insert(0, other);
+                }
+            }
+            if closed {
+                break;
+            }
+        }
+
+        lines.push((edges.into_iter().map(crossing).collect(), closed));
+    }
+    lines
+}
+
+/// Smoothes a polyline with Chaikin's corner cutting, keeping the end points of open lines.
+fn smooth(mut points: Vec<Point>, closed: bool, iterations: usize) -> Vec<Point> {
+    for _ in 0..iterations {
+        if points.len() < 3 {
+            break;
+        }
+        let mut smoothed = Vec::with_capacity(points.len() * 2);
+        if !closed {
+            smoothed.push(points[0]);
+        }
+        let segments = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for i in 0..segments {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            smoothed.push(a.lerp(b, 0.25));
+            smoothed.push(a.lerp(b, 0.75));
+        }
+        if !closed {
+            smoothed.push(*points.last().unwrap());
+        }
+        points = smoothed;
+    }
+    points
+}
+
+#[test]
+fn cone_gives_closed_circles() {
+    let size = 21;
+    let values: Vec<f32> = (0..size * size)
+        .map(|i| {
+            let (x, y) = ((i % size) as f32 - 10.0, (i / size) as f32 - 10.0);
+            (x * x + y * y).sqrt()
+        })
+        .collect();
+
+    let contours = contour_lines(&values, size, size, 4.0, 2);
+    let levels: Vec<f32> = contours.iter().map(|c| c.level).collect();
+    // The corners are ~14.1 away, so the circles up to 8 are closed and 12 is cut into four arcs by the border.
+    assert_eq!(levels, vec![4.0, 8.0, 12.0, 12.0, 12.0, 12.0]);
+    for contour in &contours[..2] {
+        assert!(contour.closed);
+        for p in &contour.points {
+            let radius = (*p - point(10.0, 10.0)).length();
+            assert!((radius - contour.level).abs() < 0.5);
+        }
+    }
+    assert!(contours[2..].iter().all(|c| !c.closed));
+}
@@ -1,3 +1,4 @@
+mod contour;
 mod screen;
 mod tile_field;
 mod tile_id;
@@ -5,6 +6,7 @@ mod tile_id;
 use lyon::math::{point, vector, Point};
 use std::f32::consts::PI;
 
+pub use contour::*;
 pub use screen::*;
 pub use tile_field::*;
 pub use tile_id::*;
@@ -49,6 +49,28 @@ impl Screen {
         self.global_to_screen(z) * zoom * pos
     }
 
+    /// Converts a point in screen pixels to global space.
+    pub fn screen_to_global(&self, z: f32, screen_point: (f32, f32)) -> Point {
+        let matrix = glm::inverse(&self.global_to_screen(z));
+        let normalized_point = glm::vec4(
+            screen_point.0 / (self.width / 2) as f32 - 1.0,
+            screen_point.1 / (self.height / 2) as f32 - 1.0,
+            0.0,
+            1.0,
+        );
+        let global_point = matrix * normalized_point;
+        point(global_point.x, global_point.y)
+    }
+
+    /// Converts a point in global space to screen pixels.
+    pub fn global_to_screen_point(&self, z: f32, point: &Point) -> (f32, f32) {
+        let screen_point = self.global_to_screen(z) * glm::vec4(point.x, point.y, 0.0, 1.0);
+        (
+            (screen_point.x + 1.0) * (self.width / 2) as f32,
+            (screen_point.y + 1.0) * (self.height / 2) as f32,
+        )
+    }
+
     pub fn global_to_screen(&self, z: f32) -> glm::TMat4<f32> {
         let zoom_x = 2.0f32.powf(z) / (self.width as f32 / 2.0) * self.get_tile_size() as f32;
         let zoom_y = 2.0f32.powf(z) / (self.height as f32 / 2.0) * self.get_tile_size() as f32;
@@ -0,0 +1,99 @@
+use std::time::Instant;
+
+/// The reason an alarm was raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlarmKind {
+    /// The AIS target with the given MMSI violates the CPA/TCPA limits.
+    Collision(u32),
+    /// The vessel left the swing circle of the anchor.
+    AnchorDrag,
+    /// Charted hazards lie in the guard zone ahead of the vessel.
+    Hazard,
+    /// Someone went overboard.
+    ManOverboard,
+}
+
+/// A single active alarm.
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub kind: AlarmKind,
+    /// A human readable description that is updated while the alarm is active.
+    pub message: String,
+    /// When the alarm was first raised.
+    pub since: Instant,
+    /// Whether the user has acknowledged the alarm.
+    pub acknowledged: bool,
+}
+
+/// A callback which is invoked whenever a new alarm is raised, e.g. to play a sound.
+pub type AlarmHook = Box<dyn FnMut(&Alarm) + Send>;
+
+/// All currently active alarms.
+#[derive(Default)]
+pub struct Alarms {
+    active: Vec<Alarm>,
+    hooks: Vec<AlarmHook>,
+}
+
+impl Alarms {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a hook that is called for every newly raised alarm.
+    pub fn add_hook(&mut self, hook: impl FnMut(&Alarm) + Send + 'static) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Raises an alarm or updates the message of an already active alarm of the same kind.
+    ///
+    /// Returns `true` if the alarm was not active before.
+    pub fn raise(&mut self, kind: AlarmKind, message: String, now: Instant) -> bool {
+        if let Some(alarm) = self.active.iter_mut().find(|a| a.kind == kind) {
+            alarm.message = message;
+            false
+        } else {
+            log::warn!("Alarm: {}", message);
+            let alarm = Alarm {
+                kind,
+                message,
+                since: now,
+                acknowledged: false,
+            };
+            for hook in &mut self.hooks {
+                hook(&alarm);
+            }
+            self.active.push(alarm);
+            true
+        }
+    }
+
+    /// Clears an alarm if it is active.
+    pub fn clear(&mut self, kind: &AlarmKind) {
+        self.active.retain(|a| &a.kind != kind);
+    }
+
+    /// Only keeps the alarms for which `f` returns `true`.
+    pub fn retain(&mut self, mut f: impl FnMut(&AlarmKind) -> bool) {
+        self.active.retain(|a| f(&a.kind));
+    }
+
+    /// Acknowledges all active alarms.
+    pub fn acknowledge_all(&mut self) {
+        for alarm in &mut self.active {
+            alarm.acknowledged = true;
+        }
+    }
+
+    pub fn is_active(&self, kind: &AlarmKind) -> bool {
+        self.active.iter().any(|a| &a.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Alarm> {
+        self.active.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+}
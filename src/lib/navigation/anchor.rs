@@ -0,0 +1,219 @@
+use crate::*;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The number of fixes kept to draw the swing track around the anchor.
+const MAX_TRACK_LENGTH: usize = 4096;
+
+/// The part of the anchor watch which is persisted across restarts.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anchor {
+    // Plain values have to be serialized before tables in TOML.
+    /// The swing radius in meters.
+    pub radius: f64,
+    pub position: GeoPoint,
+}
+
+/// Watches our position while at anchor and raises an alarm when the anchor drags.
+pub struct AnchorWatch {
+    anchor: Option<Anchor>,
+    path: String,
+    debounce: Duration,
+    /// Since when the vessel is outside the swing circle.
+    outside_since: Option<Instant>,
+    /// The time of the last fix that was added to the track.
+    last_fix: Option<Instant>,
+    track: VecDeque<GeoPoint>,
+}
+
+impl AnchorWatch {
+    /// Creates a new anchor watch and restores a dropped anchor from `path` if there is one.
+    pub fn load(path: impl Into<String>, debounce: Duration) -> Self {
+        let path = path.into();
+        let anchor = match std::fs::read_to_string(&path) {
+            Ok(content) => match toml::from_str(&content) {
+                Ok(anchor) => {
+                    log::info!("Restored the anchor watch from {}.", path);
+                    Some(anchor)
+                }
+                Err(err) => {
+                    log::error!("Failed to parse the anchor watch file {}:", path);
+                    log::error!("{}", err);
+                    None
+                }
+            },
+            // No file just means we are not at anchor.
+            Err(_) => None,
+        };
+
+        Self {
+            anchor,
+            path,
+            debounce,
+            outside_since: None,
+            last_fix: None,
+            track: VecDeque::new(),
+        }
+    }
+
+    pub fn anchor(&self) -> Option<&Anchor> {
+        self.anchor.as_ref()
+    }
+
+    /// The fixes recorded since the anchor was dropped.
+    pub fn track(&self) -> impl Iterator<Item = &GeoPoint> {
+        self.track.iter()
+    }
+
+    /// The distance from the anchor in meters of the last fix.
+    pub fn distance(&self) -> Option<f64> {
+        match (&self.anchor, self.track.back()) {
+            (Some(anchor), Some(position)) => {
+                Some(anchor.position.distance_to(position) * METERS_PER_NM)
+            }
+            _ => None,
+        }
+    }
+
+    /// Drops the anchor at `position` with the given swing radius in meters.
+    pub fn drop_anchor(&mut self, position: GeoPoint, radius: f64) {
+        self.anchor = Some(Anchor { position, radius });
+        self.outside_since = None;
+        self.track.clear();
+        self.save();
+    }
+
+    /// Changes the swing radius of the dropped anchor.
+    pub fn set_radius(&mut self, radius: f64) {
+        if let Some(anchor) = &mut self.anchor {
+            anchor.radius = radius;
+            self.save();
+        }
+    }
+
+    /// Stops watching the anchor.
+    pub fn weigh_anchor(&mut self, alarms: &mut Alarms) {
+        self.anchor = None;
+        self.outside_since = None;
+        self.track.clear();
+        alarms.clear(&AlarmKind::AnchorDrag);
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                log::error!("Failed to remove the anchor watch file {}:", self.path);
+                log::error!("{}", err);
+            }
+        }
+    }
+
+    /// Records the latest fix and raises the drag alarm
+    /// once the vessel stayed outside the swing circle for longer than the debounce time.
+    pub fn update(&mut self, own_ship: &OwnShip, alarms: &mut Alarms, now: Instant) {
+        let anchor = match &self.anchor {
+            Some(anchor) => *anchor,
+            None => return,
+        };
+
+        let position = match (own_ship.position, own_ship.last_fix) {
+            (Some(position), Some(last_fix)) if Some(last_fix) != self.last_fix => {
+                self.last_fix = Some(last_fix);
+                position
+            }
+            // Nothing new to check.
+            _ => return,
+        };
+
+        self.track.push_back(position);
+        if self.track.len() > MAX_TRACK_LENGTH {
+            self.track.pop_front();
+        }
+
+        let distance = anchor.position.distance_to(&position) * METERS_PER_NM;
+        if distance > anchor.radius {
+            let outside_since = *self.outside_since.get_or_insert(now);
+            if now.duration_since(outside_since) >= self.debounce {
+                alarms.raise(
+                    AlarmKind::AnchorDrag,
+                    format!(
+                        "Anchor drag: {:.0}m from the anchor (radius {:.0}m)",
+                        distance, anchor.radius
+                    ),
+                    now,
+                );
+            }
+        } else {
+            self.outside_since = None;
+            alarms.clear(&AlarmKind::AnchorDrag);
+        }
+    }
+
+    fn save(&self) {
+        if let Some(anchor) = &self.anchor {
+            let result = toml::to_string(anchor)
+                .map_err(|err| err.to_string())
+                .and_then(|content| {
+                    std::fs::write(&self.path, content).map_err(|err| err.to_string())
+                });
+            if let Err(err) = result {
+                log::error!("Failed to save the anchor watch to {}:", self.path);
+                log::error!("{}", err);
+            }
+        }
+    }
+}
+
+#[test]
+fn anchor_drag_is_debounced() {
+    let path = std::env::temp_dir().join(format!(
+        "sailor_anchor_watch_test_{}.toml",
+        std::process::id()
+    ));
+    let path = path.to_str().unwrap().to_string();
+    let _ = std::fs::remove_file(&path);
+
+    let anchor = GeoPoint::new(47.3, 8.5);
+    let mut watch = AnchorWatch::load(&path, Duration::from_secs(10));
+    let mut alarms = Alarms::new();
+    let mut own_ship = OwnShip::new();
+    watch.drop_anchor(anchor, 50.0);
+
+    let start = Instant::now();
+    let fix = |own_ship: &mut OwnShip, meters: f64, seconds: u64| {
+        let now = start + Duration::from_secs(seconds);
+        own_ship.set_fix(
+            anchor.destination(90.0, meters / METERS_PER_NM),
+            None,
+            None,
+            now,
+        );
+        now
+    };
+
+    let now = fix(&mut own_ship, 30.0, 0);
+    watch.update(&own_ship, &mut alarms, now);
+    assert!(alarms.is_empty());
+
+    let now = fix(&mut own_ship, 80.0, 1);
+    watch.update(&own_ship, &mut alarms, now);
+    assert!(alarms.is_empty());
+
+    let now = fix(&mut own_ship, 80.0, 12);
+    watch.update(&own_ship, &mut alarms, now);
+    assert!(alarms.is_active(&AlarmKind::AnchorDrag));
+
+    // The anchor survives a restart.
+    let restored = AnchorWatch::load(&path, Duration::from_secs(10));
+    assert_eq!(
+        restored.anchor(),
+        Some(&Anchor {
+            position: anchor,
+            radius: 50.0
+        })
+    );
+
+    watch.weigh_anchor(&mut alarms);
+    assert!(alarms.is_empty());
+    assert!(AnchorWatch::load(&path, Duration::from_secs(10))
+        .anchor()
+        .is_none());
+}
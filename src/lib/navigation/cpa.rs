@@ -0,0 +1,176 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// The closest point of approach between our own ship and a target.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cpa {
+    /// The distance at the closest point of approach in nautical miles.
+    pub distance: f64,
+    /// The time until the closest point of approach in minutes.
+    /// Negative if the closest point of approach has already been passed.
+    pub tcpa: f64,
+    /// The current distance to the target in nautical miles.
+    pub range: f64,
+    /// The current bearing to the target in degrees true.
+    pub bearing: f64,
+}
+
+impl Cpa {
+    /// Computes the closest point of approach assuming both vessels keep their course and speed.
+    ///
+    /// Courses are in degrees true and speeds in knots.
+    pub fn compute(
+        own_position: &GeoPoint,
+        own_cog: f64,
+        own_sog: f64,
+        target_position: &GeoPoint,
+        target_cog: f64,
+        target_sog: f64,
+    ) -> Self {
+        let (x, y) = target_position.to_local(own_position);
+        let (own_vx, own_vy) = course_to_vector(own_cog, own_sog);
+        let (target_vx, target_vy) = course_to_vector(target_cog, target_sog);
+        let (vx, vy) = (target_vx - own_vx, target_vy - own_vy);
+
+        let range = (x * x + y * y).sqrt();
+        let v2 = vx * vx + vy * vy;
+        // Without relative motion the distance stays the same forever.
+        let tcpa = if v2 < 1e-9 {
+            0.0
+        } else {
+            -(x * vx + y * vy) / v2
+        };
+        let (cx, cy) = (x + vx * tcpa, y + vy * tcpa);
+
+        Self {
+            distance: (cx * cx + cy * cy).sqrt(),
+            tcpa: tcpa * 60.0,
+            range,
+            bearing: own_position.bearing_to(target_position),
+        }
+    }
+
+    /// Whether the target will get closer than `limits.cpa` within `limits.tcpa`.
+    pub fn is_dangerous(&self, limits: &CpaLimits) -> bool {
+        self.distance < limits.cpa && self.tcpa >= 0.0 && self.tcpa < limits.tcpa
+    }
+}
+
+/// The thresholds at which a target is considered dangerous.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CpaLimits {
+    /// The minimum allowed distance at the closest point of approach in nautical miles.
+    pub cpa: f64,
+    /// The time horizon in minutes.
+    pub tcpa: f64,
+}
+
+/// Continuously assesses all AIS targets for the risk of a collision.
+pub struct CollisionMonitor {
+    pub limits: CpaLimits,
+    assessments: BTreeMap<u32, Cpa>,
+}
+
+impl CollisionMonitor {
+    pub fn new(limits: CpaLimits) -> Self {
+        Self {
+            limits,
+            assessments: BTreeMap::new(),
+        }
+    }
+
+    /// Recomputes the CPA of every target and raises or clears the collision alarms accordingly.
+    pub fn update(
+        &mut self,
+        own_ship: &OwnShip,
+        targets: &AisTargetTable,
+        alarms: &mut Alarms,
+        now: Instant,
+    ) {
+        self.assessments.clear();
+
+        if let Some(own_position) = own_ship.position {
+            let own_cog = own_ship.cog.unwrap_or(0.0);
+            let own_sog = own_ship.sog.unwrap_or(0.0);
+            for target in targets.iter() {
+                // AtoNs are not moving and are already shown on the chart.
+                if target.class == AisTargetClass::AidToNavigation {
+                    continue;
+                }
+                if let Some(lat_lon) = target.position.lat_lon() {
+                    let cpa = Cpa::compute(
+                        &own_position,
+                        own_cog,
+                        own_sog,
+                        &lat_lon.into(),
+                        target.position.cog.unwrap_or(0.0),
+                        target.position.sog.unwrap_or(0.0),
+                    );
+                    self.assessments.insert(target.mmsi, cpa);
+                }
+            }
+        }
+
+        for (mmsi, cpa) in &self.assessments {
+            let kind = AlarmKind::Collision(*mmsi);
+            if cpa.is_dangerous(&self.limits) {
+                let name = targets
+                    .get(*mmsi)
+                    .map(|t| t.display_name())
+                    .unwrap_or_default();
+                alarms.raise(
+                    kind,
+                    format!("{}: CPA {:.2}nm in {:.1}min", name, cpa.distance, cpa.tcpa),
+                    now,
+                );
+            } else {
+                alarms.clear(&kind);
+            }
+        }
+
+        // Targets that were dropped or lost their position can't be dangerous anymore.
+        let assessments = &self.assessments;
+        alarms.retain(|kind| match kind {
+            AlarmKind::Collision(mmsi) => assessments.contains_key(mmsi),
+            _ => true,
+        });
+    }
+
+    /// Returns the last computed CPA of a target.
+    pub fn get(&self, mmsi: u32) -> Option<&Cpa> {
+        self.assessments.get(&mmsi)
+    }
+
+    pub fn is_dangerous(&self, mmsi: u32) -> bool {
+        matches!(self.assessments.get(&mmsi), Some(cpa) if cpa.is_dangerous(&self.limits))
+    }
+}
+
+#[test]
+fn head_on_cpa() {
+    let own = GeoPoint::new(47.0, 8.0);
+    // Two nautical miles north, heading south at 6 knots while we head north at 6 knots.
+    let target = own.destination(0.0, 2.0);
+    let cpa = Cpa::compute(&own, 0.0, 6.0, &target, 180.0, 6.0);
+    assert!(cpa.distance < 0.01);
+    assert!((cpa.tcpa - 10.0).abs() < 0.1);
+    assert!((cpa.range - 2.0).abs() < 0.01);
+
+    // A target passing one mile abeam in opposite direction.
+    let target = GeoPoint::from_local(&own, 1.0, 2.0);
+    let cpa = Cpa::compute(&own, 0.0, 6.0, &target, 180.0, 6.0);
+    assert!((cpa.distance - 1.0).abs() < 0.01);
+    assert!(!cpa.is_dangerous(&CpaLimits {
+        cpa: 0.5,
+        tcpa: 20.0
+    }));
+    assert!(cpa.is_dangerous(&CpaLimits {
+        cpa: 1.5,
+        tcpa: 20.0
+    }));
+    assert!(!cpa.is_dangerous(&CpaLimits {
+        cpa: 1.5,
+        tcpa: 5.0
+    }));
+}
@@ -0,0 +1,116 @@
+use lyon::math::{point, Point};
+use serde_derive::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// The mean earth radius in nautical miles.
+pub const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// The number of meters in a nautical mile.
+pub const METERS_PER_NM: f64 = 1852.0;
+
+/// A position on the earth in degrees.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl From<(f64, f64)> for GeoPoint {
+    fn from(value: (f64, f64)) -> Self {
+        Self::new(value.0, value.1)
+    }
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Self { lat, lon }
+    }
+
+    /// Returns the great circle distance to `other` in nautical miles.
+    pub fn distance_to(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_NM * a.sqrt().atan2((1.0 - a).sqrt())
+    }
+
+    /// Returns the initial great circle bearing to `other` in degrees true.
+    pub fn bearing_to(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlon = (other.lon - self.lon).to_radians();
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+        normalize_degrees(y.atan2(x).to_degrees())
+    }
+
+    /// Returns the point reached when travelling `distance` nautical miles on a great circle
+    /// with the initial `bearing` in degrees true.
+    pub fn destination(&self, bearing: f64, distance: f64) -> GeoPoint {
+        let lat1 = self.lat.to_radians();
+        let lon1 = self.lon.to_radians();
+        let bearing = bearing.to_radians();
+        let d = distance / EARTH_RADIUS_NM;
+        let lat2 = (lat1.sin() * d.cos() + lat1.cos() * d.sin() * bearing.cos()).asin();
+        let lon2 =
+            lon1 + (bearing.sin() * d.sin() * lat1.cos()).atan2(d.cos() - lat1.sin() * lat2.sin());
+        GeoPoint::new(lat2.to_degrees(), normalize_longitude(lon2.to_degrees()))
+    }
+
+    /// Projects the point onto a plane tangent to the earth at `origin`.
+    ///
+    /// Returns `(east, north)` in nautical miles. Only accurate for short distances.
+    pub fn to_local(&self, origin: &GeoPoint) -> (f64, f64) {
+        let east = normalize_longitude(self.lon - origin.lon).to_radians()
+            * origin.lat.to_radians().cos()
+            * EARTH_RADIUS_NM;
+        let north = (self.lat - origin.lat).to_radians() * EARTH_RADIUS_NM;
+        (east, north)
+    }
+
+    /// The inverse of `to_local`.
+    pub fn from_local(origin: &GeoPoint, east: f64, north: f64) -> GeoPoint {
+        let lat = origin.lat + (north / EARTH_RADIUS_NM).to_degrees();
+        let lon =
+            origin.lon + (east / (EARTH_RADIUS_NM * origin.lat.to_radians().cos())).to_degrees();
+        GeoPoint::new(lat, normalize_longitude(lon))
+    }
+
+    /// Returns the position in the Web Mercator global space the map is drawn in.
+    ///
+    /// The global space spans `[0, 1]` in both directions, see `num_to_global_space`.
+    pub fn to_global_space(&self) -> Point {
+        let lat = self.lat.to_radians();
+        let x = (self.lon + 180.0) / 360.0;
+        let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0;
+        point(x as f32, y as f32)
+    }
+
+    /// The inverse of `to_global_space`.
+    pub fn from_global_space(p: &Point) -> GeoPoint {
+        let lon = p.x as f64 * 360.0 - 180.0;
+        let lat = (PI * (1.0 - 2.0 * p.y as f64)).sinh().atan().to_degrees();
+        GeoPoint::new(lat, lon)
+    }
+}
+
+/// Wraps an angle into `[0, 360)`.
+pub fn normalize_degrees(degrees: f64) -> f64 {
+    let degrees = degrees % 360.0;
+    if degrees < 0.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    }
+}
+
+/// Wraps a longitude into `[-180, 180)`.
+pub fn normalize_longitude(lon: f64) -> f64 {
+    normalize_degrees(lon + 180.0) - 180.0
+}
+
+/// Returns the velocity vector for a course in degrees true and a speed as `(east, north)`.
+pub fn course_to_vector(course: f64, speed: f64) -> (f64, f64) {
+    let course = course.to_radians();
+    (course.sin() * speed, course.cos() * speed)
+}
@@ -0,0 +1,488 @@
+use crate::*;
+use lyon::math::{point, Point};
+use ncollide2d::math::Point as ColliderPoint;
+use std::time::Instant;
+
+/// The number of segments the arc of the guard zone is approximated with.
+const ARC_SEGMENTS: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// A comparison of a property of an object against a value, e.g. `depth<2.5`.
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    key: String,
+    operator: Operator,
+    value: String,
+}
+
+impl Condition {
+    fn parse(text: &str, draft: f64) -> Option<Self> {
+        // Two character operators have to be tried first.
+        let operators = [
+            ("!=", Operator::NotEqual),
+            ("<=", Operator::LessOrEqual),
+            (">=", Operator::GreaterOrEqual),
+            ("=", Operator::Equal),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+        ];
+        let (index, (symbol, operator)) = operators
+            .iter()
+            .filter_map(|operator| text.find(operator.0).map(|index| (index, operator)))
+            .min_by_key(|(index, _)| *index)?;
+        let key = text[..index].trim();
+        let value = text[index + symbol.len()..].trim();
+        if key.is_empty() || value.is_empty() {
+            return None;
+        }
+        Some(Self {
+            key: key.to_string(),
+            operator: *operator,
+            value: if value == "draft" {
+                draft.to_string()
+            } else {
+                value.to_string()
+            },
+        })
+    }
+
+    fn matches(&self, object: &Object) -> bool {
+        let selector = object.selector();
+        let values: Vec<&String> = match &self.key[..] {
+            "class" => selector.classes.iter().collect(),
+            "layer" => selector.any.get("name").into_iter().collect(),
+            key => selector
+                .any
+                .get(key)
+                .or_else(|| object.tags().get(key))
+                .into_iter()
+                .collect(),
+        };
+        // A missing property only satisfies an inequality.
+        if values.is_empty() {
+            return self.operator == Operator::NotEqual;
+        }
+
+        values.into_iter().any(|value| {
+            let numbers = (value.parse::<f64>(), self.value.parse::<f64>());
+            match (self.operator, numbers) {
+                (Operator::Equal, (Ok(a), Ok(b))) => a == b,
+                (Operator::Equal, _) => value == &self.value,
+                (Operator::NotEqual, (Ok(a), Ok(b))) => a != b,
+                (Operator::NotEqual, _) => value != &self.value,
+                (Operator::Less, (Ok(a), Ok(b))) => a < b,
+                (Operator::LessOrEqual, (Ok(a), Ok(b))) => a <= b,
+                (Operator::Greater, (Ok(a), Ok(b))) => a > b,
+                (Operator::GreaterOrEqual, (Ok(a), Ok(b))) => a >= b,
+                _ => false,
+            }
+        })
+    }
+}
+
+/// Selects the chart objects which are a hazard to the vessel.
+///
+/// The query is a list of alternatives, each of which is a `&` separated list of conditions like
+/// `class=rock` or `depth<2.5`. The key `class` matches any class of an object, `layer` its vector tile layer
+/// and all other keys the selector or the tags of the object. The value `draft` stands for the draft of the vessel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HazardQuery {
+    alternatives: Vec<Vec<Condition>>,
+}
+
+impl HazardQuery {
+    /// Parses the alternatives of a query.
+    ///
+    /// Returns `None` if any condition is malformed.
+    pub fn parse(alternatives: &[String], draft: f64) -> Option<Self> {
+        let mut query = Self::default();
+        for alternative in alternatives {
+            let conditions = alternative
+                .split('&')
+                .map(|condition| Condition::parse(condition, draft))
+                .collect::<Option<Vec<_>>>();
+            match conditions {
+                Some(conditions) => query.alternatives.push(conditions),
+                None => {
+                    log::error!("Unable to parse the hazard query {}.", alternative);
+                    return None;
+                }
+            }
+        }
+        Some(query)
+    }
+
+    pub fn matches(&self, object: &Object) -> bool {
+        self.alternatives
+            .iter()
+            .any(|conditions| conditions.iter().all(|c| c.matches(object)))
+    }
+}
+
+/// A sector ahead of the vessel which is watched for hazards.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GuardZone {
+    /// The radius of the sector in nautical miles.
+    pub distance: f64,
+    /// The opening angle of the sector centered on the course over ground in degrees.
+    pub width: f64,
+}
+
+impl GuardZone {
+    /// Returns the outline of the sector in global space.
+    ///
+    /// The outline starts at `position` and is not closed.
+    pub fn outline(&self, position: &GeoPoint, cog: f64) -> Vec<Point> {
+        let mut outline = vec![position.to_global_space()];
+        outline.extend((0..=ARC_SEGMENTS).map(|i| {
+            let bearing = cog - self.width / 2.0 + self.width * i as f64 / ARC_SEGMENTS as f64;
+            position
+                .destination(normalize_degrees(bearing), self.distance)
+                .to_global_space()
+        }));
+        outline
+    }
+}
+
+/// A charted object that reaches into the guard zone.
+#[derive(Debug, Clone)]
+pub struct Hazard {
+    pub object: Object,
+    /// The point of the object closest to the vessel.
+    pub position: GeoPoint,
+    /// The distance to the closest point in nautical miles.
+    pub distance: f64,
+}
+
+impl Hazard {
+    /// Describes the object by its name or otherwise its classes and layer.
+    pub fn description(&self) -> String {
+        if let Some(name) = self.object.tags().get("name") {
+            return name.clone();
+        }
+        let selector = self.object.selector();
+        let mut parts = selector.classes.clone();
+        if let Some(layer) = selector.any.get("name") {
+            parts.push(layer.clone());
+        }
+        if let Some(depth) = self.object.tags().get("depth") {
+            parts.push(format!("{}m", depth));
+        }
+        parts.join(" ")
+    }
+}
+
+/// Watches the guard zone ahead of the vessel and raises an alarm for charted hazards within.
+pub struct GuardZoneMonitor {
+    pub zone: GuardZone,
+    query: HazardQuery,
+    hazards: Vec<Hazard>,
+}
+
+impl GuardZoneMonitor {
+    pub fn new(zone: GuardZone, query: HazardQuery) -> Self {
+        Self {
+            zone,
+            query,
+            hazards: vec![],
+        }
+    }
+
+    /// Returns the outline of the guard zone in global space if we know our position and course.
+    pub fn outline(&self, own_ship: &OwnShip) -> Option<Vec<Point>> {
+        Some(self.zone.outline(&own_ship.position?, own_ship.cog?))
+    }
+
+    /// Intersects the guard zone with the objects of the loaded tiles and raises or clears the hazard alarm.
+    pub fn update<'a>(
+        &mut self,
+        own_ship: &OwnShip,
+        tiles: impl IntoIterator<Item = &'a VisibleTile>,
+        alarms: &mut Alarms,
+        now: Instant,
+    ) {
+        self.hazards.clear();
+        if let (Some(position), Some(outline)) = (own_ship.position, self.outline(own_ship)) {
+            for tile in tiles {
+                let hazards = self.tile_hazards(&outline, &position, tile);
+                self.hazards.extend(hazards);
+            }
+        }
+
+        // Tiles of several zoom levels can contain the same object.
+        self.hazards
+            .sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+        self.hazards.dedup_by(|a, b| {
+            a.description() == b.description() && a.position.distance_to(&b.position) < 0.01
+        });
+
+        if self.hazards.is_empty() {
+            alarms.clear(&AlarmKind::Hazard);
+        } else {
+            let list: Vec<String> = self
+                .hazards
+                .iter()
+                .map(|h| format!("{} {:.2}nm", h.description(), h.distance))
+                .collect();
+            alarms.raise(
+                AlarmKind::Hazard,
+                format!("Hazards ahead: {}", list.join(", ")),
+                now,
+            );
+        }
+    }
+
+    /// Returns the objects of a tile that match the query and reach into the outline.
+    ///
+    /// The collider of the tile narrows the lines and polygons down to those whose bounding boxes overlap the outline.
+    fn tile_hazards(
+        &self,
+        outline: &[Point],
+        position: &GeoPoint,
+        tile: &VisibleTile,
+    ) -> Vec<Hazard> {
+        let (tile_id, extent) = (tile.tile_id(), tile.extent());
+        let outline = to_tile_space(outline, &tile_id, extent);
+        let (min, max) = bounds(&outline);
+
+        let objects = tile.objects();
+        let objects = match objects.try_read() {
+            Ok(objects) => objects,
+            Err(_) => return vec![],
+        };
+        let collider = tile.collider();
+        let candidates = match collider.try_read() {
+            Ok(collider) => collider.get_objects_in_area(
+                &ColliderPoint::new(min.x, min.y),
+                &ColliderPoint::new(max.x, max.y),
+            ),
+            Err(_) => return vec![],
+        };
+
+        // Single points like rocks are not part of the collider, so they are checked directly.
+        let points = objects
+            .iter()
+            .filter(|object| object.points().len() == 1)
+            .filter(|object| {
+                let p = object.points()[0];
+                p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y
+            });
+        candidates
+            .into_iter()
+            .filter_map(|id| objects.get(id))
+            .chain(points)
+            .filter(|object| self.query.matches(object) && intersects(&outline, object))
+            .map(|object| to_hazard(object, position, &tile_id, extent))
+            .collect()
+    }
+
+    /// Returns the hazards found by the last update sorted by distance.
+    pub fn hazards(&self) -> &[Hazard] {
+        &self.hazards
+    }
+}
+
+/// Converts points from global space to the tile space of `tile_id`.
+fn to_tile_space(points: &[Point], tile_id: &TileId, extent: u16) -> Vec<Point> {
+    let scale = (1u64 << tile_id.z) as f32;
+    points
+        .iter()
+        .map(|p| {
+            point(
+                (p.x * scale - tile_id.x as f32) * extent as f32,
+                (p.y * scale - tile_id.y as f32) * extent as f32,
+            )
+        })
+        .collect()
+}
+
+fn bounds(points: &[Point]) -> (Point, Point) {
+    points.iter().fold(
+        (
+            point(f32::INFINITY, f32::INFINITY),
+            point(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    )
+}
+
+/// Whether an object touches the closed polygon `zone`.
+///
+/// The rings of a polygon are tested on their own, so holes are left out.
+fn intersects(zone: &[Point], object: &Object) -> bool {
+    if object.points().iter().any(|p| contains(zone, p)) {
+        return true;
+    }
+    let closed = matches!(object.object_type(), ObjectType::Polygon);
+    // We might be inside of a shallow area already.
+    if closed
+        && object.points().len() >= 3
+        && object
+            .rings()
+            .filter(|ring| contains(ring, &zone[0]))
+            .count()
+            % 2
+            == 1
+    {
+        return true;
+    }
+    let edges = |points: &[Point], closed: bool| {
+        let count = if closed {
+            points.len()
+        } else {
+            points.len().saturating_sub(1)
+        };
+        (0..count)
+            .map(|i| (points[i], points[(i + 1) % points.len()]))
+            .collect::<Vec<_>>()
+    };
+    let object_edges = object
+        .rings()
+        .flat_map(|ring| edges(ring, closed))
+        .collect::<Vec<_>>();
+    edges(zone, true)
+        .iter()
+        .any(|a| object_edges.iter().any(|b| segments_intersect(a, b)))
+}
+
+/// Whether a point lies within a polygon by the even-odd rule.
+fn contains(polygon: &[Point], p: &Point) -> bool {
+    if polygon.is_empty() {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = polygon.len() - 1;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn segments_intersect(a: &(Point, Point), b: &(Point, Point)) -> bool {
+    let side = |p: Point, q: Point, r: Point| (q - p).cross(r - p);
+    let (d1, d2) = (side(b.0, b.1, a.0), side(b.0, b.1, a.1));
+    let (d3, d4) = (side(a.0, a.1, b.0), side(a.0, a.1, b.1));
+    (d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0)
+}
+
+/// Finds the point of an object in the tile space of `tile_id` closest to `position`.
+fn to_hazard(object: &Object, position: &GeoPoint, tile_id: &TileId, extent: u16) -> Hazard {
+    let scale = 1.0 / (1u64 << tile_id.z) as f32;
+    let (closest, distance) = object
+        .points()
+        .iter()
+        .map(|p| {
+            let global = point(
+                (tile_id.x as f32 + p.x / extent as f32) * scale,
+                (tile_id.y as f32 + p.y / extent as f32) * scale,
+            );
+            let geo = GeoPoint::from_global_space(&global);
+            (geo, position.distance_to(&geo))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap_or((*position, 0.0));
+    Hazard {
+        object: object.clone(),
+        position: closest,
+        distance,
+    }
+}
+
+#[test]
+fn hazards_in_guard_zone() {
+    use std::collections::HashMap;
+
+    let query = HazardQuery::parse(
+        &[
+            "class=rock".to_string(),
+            "layer=depth & depth<draft".to_string(),
+        ],
+        2.0,
+    )
+    .unwrap();
+    assert!(HazardQuery::parse(&["depth".to_string()], 2.0).is_none());
+
+    let layer = |name: &str| Selector::new().with_type("layer").with_any("name", name);
+    let depth = |depth: &str| {
+        let mut tags = HashMap::new();
+        tags.insert("depth".to_string(), depth.to_string());
+        tags
+    };
+    let square = |x: f32, y: f32, size: f32| {
+        vec![
+            point(x, y),
+            point(x + size, y),
+            point(x + size, y + size),
+            point(x, y + size),
+        ]
+    };
+    let rock = Object::new(
+        layer("poi").with_class("rock"),
+        vec![point(50.0, 20.0)],
+        ObjectType::Point,
+    );
+    let shallow = Object::new_with_tags(
+        layer("depth"),
+        square(45.0, 45.0, 10.0),
+        depth("1.5"),
+        ObjectType::Polygon,
+    );
+    let deep = Object::new_with_tags(
+        layer("depth"),
+        square(45.0, 45.0, 10.0),
+        depth("5"),
+        ObjectType::Polygon,
+    );
+    assert!(query.matches(&rock));
+    assert!(query.matches(&shallow));
+    assert!(!query.matches(&deep));
+
+    // A zone pointing up from the bottom of the tile.
+    let zone = vec![point(50.0, 100.0), point(30.0, 30.0), point(70.0, 30.0)];
+    assert!(!intersects(&zone, &rock));
+    assert!(intersects(&zone, &shallow));
+    let crossing = Object::new(
+        layer("depth"),
+        vec![point(0.0, 60.0), point(100.0, 60.0)],
+        ObjectType::Line,
+    );
+    assert!(intersects(&zone, &crossing));
+    let around = Object::new(
+        layer("depth"),
+        square(-1000.0, -1000.0, 3000.0),
+        ObjectType::Polygon,
+    );
+    assert!(contains(around.points(), &zone[0]));
+    assert!(intersects(&zone, &around));
+    // The zone lies within a hole of the shallow area, e.g. a deep basin, and no edge may join the two rings.
+    let mut ring = square(-1000.0, -1000.0, 3000.0);
+    ring.rotate_left(3);
+    ring.extend(square(0.0, 0.0, 200.0));
+    let basin = Object::new(layer("depth"), ring, ObjectType::Polygon).with_ring_ends(vec![4, 8]);
+    assert_eq!(basin.rings().count(), 2);
+    assert!(!intersects(&zone, &basin));
+
+    let position = GeoPoint::new(50.0, 0.0);
+    let outline = GuardZone {
+        distance: 1.0,
+        width: 40.0,
+    }
+    .outline(&position, 90.0);
+    assert_eq!(outline.len(), ARC_SEGMENTS + 2);
+    let ahead = GeoPoint::from_global_space(&outline[ARC_SEGMENTS / 2 + 1]);
+    assert!((position.bearing_to(&ahead) - 90.0).abs() < 0.1);
+    assert!((position.distance_to(&ahead) - 1.0).abs() < 0.01);
+}
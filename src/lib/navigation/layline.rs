@@ -0,0 +1,115 @@
+use crate::*;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Averages the true wind over a sliding time window so the laylines don't jump with every gust.
+pub struct WindSmoother {
+    window: Duration,
+    samples: VecDeque<(Instant, Wind)>,
+}
+
+impl WindSmoother {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Adds a true wind direction and speed and forgets all samples older than the window.
+    pub fn update(&mut self, wind: Wind, now: Instant) {
+        self.samples.push_back((now, wind));
+        while let Some((time, _)) = self.samples.front() {
+            if now.duration_since(*time) <= self.window {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns the average wind of the window.
+    ///
+    /// The directions are averaged as unit vectors, so 350° and 10° give north and not south.
+    pub fn average(&self) -> Option<Wind> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let (east, north, speed) =
+            self.samples
+                .iter()
+                .fold((0.0, 0.0, 0.0), |(east, north, speed), (_, wind)| {
+                    let (x, y) = course_to_vector(wind.angle, 1.0);
+                    (east + x, north + y, speed + wind.speed)
+                });
+        Some(Wind {
+            angle: normalize_degrees(east.atan2(north).to_degrees()),
+            speed: speed / self.samples.len() as f64,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// The port and starboard laylines of an upwind mark.
+///
+/// A boat on a layline fetches the mark close hauled without another tack.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Laylines {
+    pub mark: GeoPoint,
+    /// The far end of the layline which is sailed on port tack.
+    pub port: GeoPoint,
+    /// The far end of the layline which is sailed on starboard tack.
+    pub starboard: GeoPoint,
+}
+
+impl Laylines {
+    /// Constructs the laylines for the true wind direction and the true wind angle sailed close hauled in degrees.
+    ///
+    /// Both lines are `length` nautical miles long.
+    pub fn new(mark: &GeoPoint, wind_direction: f64, tacking_angle: f64, length: f64) -> Self {
+        // On port tack the wind comes over the port bow, so the heading is to the right of the wind.
+        let port_heading = wind_direction + tacking_angle;
+        let starboard_heading = wind_direction - tacking_angle;
+        Self {
+            mark: *mark,
+            port: mark.destination(normalize_degrees(port_heading + 180.0), length),
+            starboard: mark.destination(normalize_degrees(starboard_heading + 180.0), length),
+        }
+    }
+}
+
+#[test]
+fn smoothed_laylines() {
+    let start = Instant::now();
+    let mut smoother = WindSmoother::new(Duration::from_secs(30));
+    assert!(smoother.average().is_none());
+    smoother.update(
+        Wind {
+            angle: 180.0,
+            speed: 30.0,
+        },
+        start,
+    );
+    for (seconds, angle) in &[(40, 350.0), (50, 10.0)] {
+        smoother.update(
+            Wind {
+                angle: *angle,
+                speed: 10.0,
+            },
+            start + Duration::from_secs(*seconds),
+        );
+    }
+    // The southerly gust is older than the window.
+    let wind = smoother.average().unwrap();
+    assert!(normalize_longitude(wind.angle).abs() < 1e-9);
+    assert!((wind.speed - 10.0).abs() < 1e-9);
+
+    let mark = GeoPoint::new(50.0, 0.0);
+    let laylines = Laylines::new(&mark, wind.angle, 45.0, 1.0);
+    // With a northerly the laylines run down to the south west and south east of the mark.
+    assert!((mark.bearing_to(&laylines.port) - 225.0).abs() < 0.1);
+    assert!((mark.bearing_to(&laylines.starboard) - 135.0).abs() < 0.1);
+    assert!((mark.distance_to(&laylines.port) - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,242 @@
+use crate::*;
+
+/// The semi-major axis of the WGS 84 ellipsoid in kilometers.
+const WGS84_A: f64 = 6378.137;
+
+/// The flattening of the WGS 84 ellipsoid.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// The reference radius of the geomagnetic models in kilometers.
+const EARTH_RADIUS: f64 = 6371.2;
+
+/// The magnetic field vector in nanotesla.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MagneticField {
+    pub north: f64,
+    pub east: f64,
+    pub down: f64,
+}
+
+impl MagneticField {
+    /// The angle between true and magnetic north in degrees, positive if magnetic north is east of true north.
+    pub fn declination(&self) -> f64 {
+        self.east.atan2(self.north).to_degrees()
+    }
+
+    /// The angle of the field below the horizontal plane in degrees.
+    pub fn inclination(&self) -> f64 {
+        self.down.atan2(self.north.hypot(self.east)).to_degrees()
+    }
+}
+
+/// A spherical harmonic model of the main geomagnetic field like the World Magnetic Model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagneticModel {
+    pub name: String,
+    /// The decimal year the coefficients refer to.
+    pub epoch: f64,
+    degree: usize,
+    /// The Gauss coefficients `g`, `h` and their yearly change, indexed by `n * (n + 1) / 2 + m`.
+    g: Vec<f64>,
+    h: Vec<f64>,
+    g_dot: Vec<f64>,
+    h_dot: Vec<f64>,
+}
+
+impl MagneticModel {
+    pub fn load(path: &str) -> Option<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                log::warn!("Unable to read the magnetic model from {}:", path);
+                log::warn!("{}", err);
+                return None;
+            }
+        };
+
+        let model = Self::parse(&content);
+        if model.is_none() {
+            log::error!("Unable to parse the magnetic model in {}.", path);
+        }
+        model
+    }
+
+    /// Parses a coefficient file in the `WMM.COF` format.
+    ///
+    /// The header holds the epoch and the name of the model, every following line the degree `n`, the order `m`,
+    /// `g`, `h` and their secular variation `g_dot` and `h_dot`. A line of nines ends the file.
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let mut header = lines.next()?.split_whitespace();
+        let epoch = header.next()?.parse().ok()?;
+        let name = header.next().unwrap_or_default().to_string();
+
+        let mut rows = vec![];
+        for line in lines.take_while(|line| !line.starts_with("9999")) {
+            let fields = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<f64>, _>>()
+                .ok()?;
+            if fields.len() < 6 || fields[1] > fields[0] || fields[0] < 1.0 {
+                return None;
+            }
+            rows.push(fields);
+        }
+
+        let degree = rows.iter().map(|row| row[0] as usize).max()?;
+        let size = index(degree, degree) + 1;
+        let mut model = Self {
+            name,
+            epoch,
+            degree,
+            g: vec![0.0; size],
+            h: vec![0.0; size],
+            g_dot: vec![0.0; size],
+            h_dot: vec![0.0; size],
+        };
+        for row in rows {
+            let i = index(row[0] as usize, row[1] as usize);
+            model.g[i] = row[2];
+            model.h[i] = row[3];
+            model.g_dot[i] = row[4];
+            model.h_dot[i] = row[5];
+        }
+        Some(model)
+    }
+
+    /// Computes the field at sea level for the given position and decimal year.
+    ///
+    /// The model is only accurate for a few years after its epoch, later dates are extrapolated linearly.
+    pub fn field(&self, position: &GeoPoint, year: f64) -> MagneticField {
+        let dt = year - self.epoch;
+        let lat = position.lat.to_radians();
+        let lon = position.lon.to_radians();
+
+        // The model is expanded around the center of the earth, so go from geodetic to geocentric coordinates.
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let rc = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        let p = rc * lat.cos();
+        let z = rc * (1.0 - e2) * lat.sin();
+        let r = p.hypot(z);
+        let lat_c = (z / r).asin();
+
+        // Schmidt semi-normalized associated Legendre functions of the colatitude and their derivatives.
+        let (cos_t, sin_t) = (lat_c.sin(), lat_c.cos());
+        let mut pnm = vec![0.0; self.g.len()];
+        let mut dpnm = vec![0.0; self.g.len()];
+        pnm[0] = 1.0;
+        for n in 1..=self.degree {
+            for m in 0..=n {
+                let i = index(n, m);
+                if m == n {
+                    let k = if n == 1 {
+                        1.0
+                    } else {
+                        (1.0 - 1.0 / (2.0 * n as f64)).sqrt()
+                    };
+                    let j = index(n - 1, n - 1);
+                    pnm[i] = k * sin_t * pnm[j];
+                    dpnm[i] = k * (cos_t * pnm[j] + sin_t * dpnm[j]);
+                } else {
+                    let j = index(n - 1, m);
+                    let (p2, dp2) = if n >= m + 2 {
+                        let k = (((n - 1) * (n - 1) - m * m) as f64).sqrt();
+                        (k * pnm[index(n - 2, m)], k * dpnm[index(n - 2, m)])
+                    } else {
+                        (0.0, 0.0)
+                    };
+                    let k = ((n * n - m * m) as f64).sqrt();
+                    let f = (2 * n - 1) as f64;
+                    pnm[i] = (f * cos_t * pnm[j] - p2) / k;
+                    dpnm[i] = (f * (cos_t * dpnm[j] - sin_t * pnm[j]) - dp2) / k;
+                }
+            }
+        }
+
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for n in 1..=self.degree {
+            let scale = (EARTH_RADIUS / r).powi(n as i32 + 2);
+            for m in 0..=n {
+                let i = index(n, m);
+                let g = self.g[i] + dt * self.g_dot[i];
+                let h = self.h[i] + dt * self.h_dot[i];
+                let (sin_ml, cos_ml) = (m as f64 * lon).sin_cos();
+                let a = g * cos_ml + h * sin_ml;
+                x += scale * a * dpnm[i];
+                y += scale * m as f64 * (g * sin_ml - h * cos_ml) * pnm[i];
+                z -= scale * (n + 1) as f64 * a * pnm[i];
+            }
+        }
+        // Avoid the division by zero right at the poles.
+        y /= lat_c.cos().max(1e-9);
+
+        // Rotate the vector back into the frame of the ellipsoid.
+        let (sin_d, cos_d) = (lat_c - lat).sin_cos();
+        MagneticField {
+            north: x * cos_d - z * sin_d,
+            east: y,
+            down: x * sin_d + z * cos_d,
+        }
+    }
+
+    /// The magnetic variation in degrees at a position and a time in seconds since the unix epoch, positive to the east.
+    pub fn declination(&self, position: &GeoPoint, time: f64) -> f64 {
+        self.field(position, UtcDateTime::from_unix(time).decimal_year())
+            .declination()
+    }
+}
+
+/// Formats a bearing in degrees true with three digits.
+///
+/// If the magnetic `variation` is given the bearing is converted to and marked as magnetic.
+pub fn format_bearing(bearing: f64, variation: Option<f64>) -> String {
+    match variation {
+        Some(variation) => format!(
+            "{:03.0}°M",
+            normalize_degrees(bearing - variation).round() % 360.0
+        ),
+        None => format!("{:03.0}°T", normalize_degrees(bearing).round() % 360.0),
+    }
+}
+
+/// The index of the coefficient of degree `n` and order `m`.
+fn index(n: usize, m: usize) -> usize {
+    n * (n + 1) / 2 + m
+}
+
+#[test]
+fn world_magnetic_model() {
+    let model = MagneticModel::parse(include_str!("../../../data/WMM.COF")).unwrap();
+    assert_eq!(model.name, "WMM-2020");
+
+    // The test values published with the WMM2020 report.
+    let field = model.field(&GeoPoint::new(80.0, 0.0), 2020.0);
+    assert!((field.north - 6570.4).abs() < 0.5);
+    assert!((field.east - -146.3).abs() < 0.5);
+    assert!((field.down - 54606.0).abs() < 0.5);
+    assert!((field.declination() - -1.28).abs() < 0.01);
+    assert!((field.inclination() - 83.14).abs() < 0.01);
+    let field = model.field(&GeoPoint::new(0.0, 120.0), 2020.0);
+    assert!((field.declination() - 0.16).abs() < 0.01);
+    let field = model.field(&GeoPoint::new(-80.0, 240.0), 2020.0);
+    assert!((field.declination() - 69.36).abs() < 0.01);
+
+    // The variation of the compass wins over the one of the model.
+    let mut own_ship = OwnShip::new();
+    own_ship.set_model_variation(-1.0);
+    let sentence = NmeaSentence::parse("$HCHDG,101.1,0.5,E,7.1,W*52").unwrap();
+    own_ship.update(
+        &NmeaMessage::parse(&sentence).unwrap(),
+        std::time::Instant::now(),
+    );
+    own_ship.set_model_variation(-1.0);
+    assert!((own_ship.magnetic_heading.unwrap() - 101.6).abs() < 1e-9);
+    assert!((own_ship.heading.unwrap() - 94.5).abs() < 1e-9);
+
+    assert_eq!(format_bearing(359.7, None), "000°T");
+    assert_eq!(format_bearing(10.0, Some(-2.4)), "012°M");
+}
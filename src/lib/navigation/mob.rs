@@ -0,0 +1,106 @@
+use crate::*;
+use std::time::Instant;
+
+/// A person overboard and where they went into the water.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ManOverboard {
+    pub position: GeoPoint,
+    /// The UTC time of the position in seconds since the unix epoch.
+    pub time: f64,
+    /// When the MOB was marked.
+    pub since: Instant,
+}
+
+impl ManOverboard {
+    /// Marks the position we had `lookback` seconds before the UTC time `utc`.
+    ///
+    /// The position is interpolated from the recorded track if it covers that time, otherwise the current fix is used.
+    /// Returns `None` if we have neither.
+    pub fn mark(
+        own_ship: &OwnShip,
+        track: &Track,
+        lookback: f64,
+        utc: f64,
+        now: Instant,
+    ) -> Option<Self> {
+        let time = utc - lookback;
+        let from_track = match track.time_range() {
+            Some((start, end)) if lookback > 0.0 && time >= start && time <= end => {
+                track.point_at(time)
+            }
+            _ => None,
+        };
+        let (position, time) = match from_track {
+            Some(point) => (point.position, point.time),
+            None => (own_ship.position?, utc),
+        };
+        Some(Self {
+            position,
+            time,
+            since: now,
+        })
+    }
+
+    /// Returns the bearing in degrees true and the distance in nautical miles from `position` back to the MOB.
+    pub fn course_from(&self, position: &GeoPoint) -> (f64, f64) {
+        (
+            position.bearing_to(&self.position),
+            position.distance_to(&self.position),
+        )
+    }
+
+    /// Describes the course back to the MOB and the time since it was marked.
+    ///
+    /// The bearing is magnetic if a `variation` is given, see `format_bearing`.
+    pub fn summary(&self, own_ship: &OwnShip, variation: Option<f64>, now: Instant) -> String {
+        let elapsed = now.duration_since(self.since).as_secs();
+        let elapsed = format!("{:02}:{:02}", elapsed / 60, elapsed % 60);
+        match own_ship.position {
+            Some(position) => {
+                let (bearing, distance) = self.course_from(&position);
+                format!(
+                    "MOB {} {:.2}nm {}",
+                    format_bearing(bearing, variation),
+                    distance,
+                    elapsed
+                )
+            }
+            None => format!("MOB no fix {}", elapsed),
+        }
+    }
+}
+
+#[test]
+fn mob_from_track_buffer() {
+    use std::time::Duration;
+
+    let mut own_ship = OwnShip::new();
+    let now = Instant::now();
+    own_ship.set_fix(GeoPoint::new(50.01, 0.0), Some(0.0), Some(6.0), now);
+    let mut track = Track::new();
+    for (time, lat) in &[(100.0, 50.0), (110.0, 50.002)] {
+        track.push(TrackPoint {
+            time: *time,
+            position: GeoPoint::new(*lat, 0.0),
+            cog: Some(0.0),
+            sog: Some(6.0),
+            depth: None,
+            wind: None,
+        });
+    }
+
+    let mob = ManOverboard::mark(&own_ship, &track, 5.0, 110.0, now).unwrap();
+    assert!((mob.position.lat - 50.001).abs() < 1e-9);
+    assert_eq!(mob.time, 105.0);
+    // The track does not reach back far enough, so the current fix is taken.
+    let mob = ManOverboard::mark(&own_ship, &track, 60.0, 110.0, now).unwrap();
+    assert_eq!(mob.position, GeoPoint::new(50.01, 0.0));
+
+    let (bearing, distance) = mob.course_from(&GeoPoint::new(50.0, 0.0));
+    assert!(bearing.abs() < 1e-6);
+    assert!((distance - 0.6).abs() < 0.01);
+    assert_eq!(
+        mob.summary(&own_ship, None, now + Duration::from_secs(125)),
+        "MOB 000°T 0.00nm 02:05"
+    );
+}
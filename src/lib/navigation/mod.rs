@@ -0,0 +1,33 @@
+mod alarm;
+mod anchor;
+mod cpa;
+mod geo;
+mod guard_zone;
+mod layline;
+mod magnetic;
+mod mob;
+mod own_ship;
+mod polar;
+mod recorder;
+mod replay;
+mod routing;
+mod track;
+mod utc;
+mod water;
+
+pub use alarm::*;
+pub use anchor::*;
+pub use cpa::*;
+pub use geo::*;
+pub use guard_zone::*;
+pub use layline::*;
+pub use magnetic::*;
+pub use mob::*;
+pub use own_ship::*;
+pub use polar::*;
+pub use recorder::*;
+pub use replay::*;
+pub use routing::*;
+pub use track::*;
+pub use utc::*;
+pub use water::*;
@@ -0,0 +1,198 @@
+use crate::*;
+use std::time::Instant;
+
+/// Wind speed and direction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Wind {
+    /// The angle the wind is coming from in degrees.
+    pub angle: f64,
+    /// The wind speed in knots.
+    pub speed: f64,
+}
+
+/// The current navigation state of our own vessel.
+#[derive(Debug, Clone, Default)]
+pub struct OwnShip {
+    pub position: Option<GeoPoint>,
+    /// Course over ground in degrees true.
+    pub cog: Option<f64>,
+    /// Speed over ground in knots.
+    pub sog: Option<f64>,
+    /// True heading in degrees.
+    pub heading: Option<f64>,
+    /// Magnetic heading in degrees corrected for the deviation of the compass.
+    pub magnetic_heading: Option<f64>,
+    /// The magnetic variation in degrees, positive to the east.
+    ///
+    /// A variation reported by the compass takes precedence over the one of the magnetic model.
+    pub variation: Option<f64>,
+    /// Whether the variation was reported by the compass.
+    reported_variation: bool,
+    /// When the last position fix was received.
+    pub last_fix: Option<Instant>,
+    /// The UTC time of the last fix in seconds since the unix epoch as reported by the GNSS.
+    pub utc: Option<f64>,
+    /// Depth of water below the surface in meters.
+    pub depth: Option<f64>,
+    /// The apparent wind relative to the bow.
+    pub apparent_wind: Option<Wind>,
+    /// The true wind relative to the bow.
+    pub true_wind: Option<Wind>,
+    /// The direction in degrees true and speed of the true wind.
+    pub true_wind_direction: Option<Wind>,
+    /// Speed through water in knots.
+    pub stw: Option<f64>,
+}
+
+impl OwnShip {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Merges a NMEA message into the own ship state.
+    pub fn update(&mut self, message: &NmeaMessage, now: Instant) {
+        match message {
+            NmeaMessage::Rmc {
+                time,
+                date,
+                position,
+                sog,
+                cog,
+            } => {
+                if let Some(position) = position {
+                    self.set_fix((*position).into(), *cog, *sog, now);
+                    if let (Some(date), Some(time)) = (date, time) {
+                        self.utc = UtcDateTime::from_nmea(date, time).map(|utc| utc.to_unix());
+                    }
+                }
+            }
+            NmeaMessage::Vtg { cog, sog } => {
+                self.cog = cog.or(self.cog);
+                self.sog = sog.or(self.sog);
+            }
+            NmeaMessage::Hdt { heading } => self.heading = Some(*heading),
+            NmeaMessage::Hdg {
+                heading,
+                deviation,
+                variation,
+            } => {
+                if let Some(variation) = variation {
+                    self.variation = Some(*variation);
+                    self.reported_variation = true;
+                }
+                let magnetic = normalize_degrees(heading + deviation.unwrap_or(0.0));
+                self.magnetic_heading = Some(magnetic);
+                if let Some(variation) = self.variation {
+                    self.heading = Some(normalize_degrees(magnetic + variation));
+                }
+            }
+            NmeaMessage::Dpt { depth, offset } => {
+                // Only a positive offset gives us the depth below the surface.
+                self.depth = Some(depth + offset.unwrap_or(0.0).max(0.0));
+            }
+            NmeaMessage::Dbt { depth } => self.depth = Some(*depth),
+            NmeaMessage::Mwv {
+                angle,
+                speed,
+                true_wind,
+            } => {
+                let wind = Some(Wind {
+                    angle: *angle,
+                    speed: *speed,
+                });
+                if *true_wind {
+                    self.true_wind = wind;
+                } else {
+                    self.apparent_wind = wind;
+                }
+            }
+            NmeaMessage::Mwd { direction, speed } => {
+                self.true_wind_direction = Some(Wind {
+                    angle: *direction,
+                    speed: *speed,
+                })
+            }
+            NmeaMessage::Vhw { speed } => self.stw = Some(*speed),
+        }
+    }
+
+    /// Merges a report of our own AIS transponder into the own ship state.
+    pub fn update_from_ais(&mut self, report: &AisReport, now: Instant) {
+        if !report.own_vessel {
+            return;
+        }
+        match &report.message {
+            AisMessage::PositionReport { position, .. }
+            | AisMessage::ClassBPositionReport { position, .. }
+            | AisMessage::ExtendedClassBPositionReport { position, .. } => {
+                if let Some(lat_lon) = position.lat_lon() {
+                    self.set_fix(lat_lon.into(), position.cog, position.sog, now);
+                    if let Some(heading) = position.heading {
+                        self.heading = Some(heading as f64);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn set_fix(
+        &mut self,
+        position: GeoPoint,
+        cog: Option<f64>,
+        sog: Option<f64>,
+        now: Instant,
+    ) {
+        self.position = Some(position);
+        self.cog = cog;
+        self.sog = sog;
+        self.last_fix = Some(now);
+        // Only RMC sentences carry the time, so it is set after the fix.
+        self.utc = None;
+    }
+
+    /// Sets the variation at our position as computed by a magnetic model.
+    ///
+    /// It is ignored once the compass reported a variation.
+    pub fn set_model_variation(&mut self, variation: f64) {
+        if !self.reported_variation {
+            self.variation = Some(variation);
+        }
+    }
+
+    /// Returns the true wind relative to the bow.
+    ///
+    /// It is derived from the true wind direction and our heading if no instrument reports it directly.
+    pub fn true_wind_angle(&self) -> Option<Wind> {
+        self.true_wind.or_else(|| {
+            let direction = self.true_wind_direction?;
+            Some(Wind {
+                angle: normalize_degrees(direction.angle - self.heading_or_cog()?),
+                speed: direction.speed,
+            })
+        })
+    }
+
+    /// Returns the true wind direction in degrees true.
+    ///
+    /// It is derived from the true wind angle and our heading if no instrument reports it directly.
+    pub fn true_wind_direction(&self) -> Option<Wind> {
+        self.true_wind_direction.or_else(|| {
+            let wind = self.true_wind?;
+            Some(Wind {
+                angle: normalize_degrees(wind.angle + self.heading_or_cog()?),
+                speed: wind.speed,
+            })
+        })
+    }
+
+    /// Returns the speed through water if known and the speed over ground otherwise.
+    pub fn stw_or_sog(&self) -> Option<f64> {
+        self.stw.or(self.sog)
+    }
+
+    /// Returns the heading if known and the course over ground otherwise.
+    pub fn heading_or_cog(&self) -> Option<f64> {
+        self.heading.or(self.cog)
+    }
+}
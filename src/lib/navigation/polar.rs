@@ -0,0 +1,284 @@
+use crate::*;
+
+/// The boat speed in knots over true wind angle and true wind speed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polar {
+    /// The true wind angles of the rows in degrees, ascending from 0 to at most 180.
+    angles: Vec<f64>,
+    /// The true wind speeds of the columns in knots, ascending from 0.
+    speeds: Vec<f64>,
+    /// The boat speeds in knots indexed by `[angle][speed]`.
+    boat_speeds: Vec<Vec<f64>>,
+}
+
+/// How the boat performs compared to its polar.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PolarPerformance {
+    /// The boat speed the polar gives for the current wind in knots.
+    pub polar_speed: f64,
+    /// The boat speed in percent of the polar speed.
+    pub percentage: f64,
+    /// The true wind angle with the best VMG on the current point of sail.
+    pub target_angle: f64,
+    /// The boat speed at the target angle in knots.
+    pub target_speed: f64,
+}
+
+impl Polar {
+    /// Loads a polar from a table or an Expedition style POL file.
+    pub fn load(path: &str) -> Option<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                log::warn!("Unable to read the polar from {}:", path);
+                log::warn!("{}", err);
+                return None;
+            }
+        };
+
+        let polar = Self::parse(&content);
+        if polar.is_none() {
+            log::error!("Unable to parse the polar in {}.", path);
+        }
+        polar
+    }
+
+    /// Parses a polar in one of the common text formats.
+    ///
+    /// Fields may be separated by `;`, `,`, tabs or spaces. If the first line starts with text like
+    /// `twa/tws` it is a table with the wind speeds in the header and one row per wind angle.
+    /// Otherwise every line holds a wind speed followed by pairs of wind angle and boat speed.
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split(|c: char| c == ';' || c == ',' || c.is_whitespace())
+                    .filter(|field| !field.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .peekable();
+
+        let header = lines.peek()?;
+        if header.first()?.parse::<f64>().is_err() {
+            let speeds = header[1..]
+                .iter()
+                .map(|s| parse_number(s))
+                .collect::<Option<Vec<f64>>>()?;
+            lines.next();
+            let mut rows = vec![];
+            for line in lines {
+                let row = line
+                    .iter()
+                    .map(|s| parse_number(s))
+                    .collect::<Option<Vec<f64>>>()?;
+                if row.len() != speeds.len() + 1 {
+                    return None;
+                }
+                rows.push((row[0], row[1..].to_vec()));
+            }
+            Self::from_rows(speeds, rows)
+        } else {
+            let mut curves = vec![];
+            for line in lines {
+                let numbers = line
+                    .iter()
+                    .map(|s| parse_number(s))
+                    .collect::<Option<Vec<f64>>>()?;
+                if numbers.len() < 3 || numbers.len() % 2 == 0 {
+                    return None;
+                }
+                let points = numbers[1..].chunks(2).map(|p| (p[0], p[1])).collect();
+                curves.push((numbers[0], points));
+            }
+            Self::from_curves(curves)
+        }
+    }
+
+    /// Builds a polar from rows of boat speeds per wind angle for the given wind speeds.
+    fn from_rows(speeds: Vec<f64>, mut rows: Vec<(f64, Vec<f64>)>) -> Option<Self> {
+        if speeds.is_empty() || rows.is_empty() {
+            return None;
+        }
+        rows.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let mut polar = Self {
+            angles: rows.iter().map(|r| r.0.abs().min(180.0)).collect(),
+            speeds,
+            boat_speeds: rows.into_iter().map(|r| r.1).collect(),
+        };
+
+        // No wind and head to wind the boat does not move.
+        if polar.speeds[0] > 0.0 {
+            polar.speeds.insert(0, 0.0);
+            for row in &mut polar.boat_speeds {
+                row.insert(0, 0.0);
+            }
+        }
+        if polar.angles[0] > 0.0 {
+            polar.angles.insert(0, 0.0);
+            polar.boat_speeds.insert(0, vec![0.0; polar.speeds.len()]);
+        }
+        Some(polar)
+    }
+
+    /// Builds a polar from curves of `(angle, boat speed)` points per wind speed.
+    ///
+    /// The curves are resampled onto the union of all their wind angles.
+    fn from_curves(mut curves: Vec<(f64, Vec<(f64, f64)>)>) -> Option<Self> {
+        curves.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let mut angles: Vec<f64> = curves
+            .iter()
+            .flat_map(|(_, points)| points.iter().map(|p| p.0))
+            .collect();
+        angles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        angles.dedup();
+
+        let rows = angles
+            .iter()
+            .map(|angle| {
+                let speeds = curves
+                    .iter()
+                    .map(|(_, points)| interpolate_curve(points, *angle))
+                    .collect();
+                (*angle, speeds)
+            })
+            .collect();
+        Self::from_rows(curves.iter().map(|c| c.0).collect(), rows)
+    }
+
+    /// Returns the boat speed in knots for a true wind angle in degrees and a true wind speed in knots.
+    ///
+    /// The table is interpolated bilinearly, wind speeds above the table are clamped.
+    pub fn boat_speed(&self, angle: f64, wind_speed: f64) -> f64 {
+        let angle = normalize_longitude(angle).abs();
+        let (a0, a1, ta) = bracket(&self.angles, angle);
+        let (s0, s1, ts) = bracket(&self.speeds, wind_speed);
+        let row = |a: usize| self.boat_speeds[a][s0] * (1.0 - ts) + self.boat_speeds[a][s1] * ts;
+        row(a0) * (1.0 - ta) + row(a1) * ta
+    }
+
+    /// Returns the true wind angle and boat speed with the best velocity made good towards the wind.
+    pub fn best_upwind(&self, wind_speed: f64) -> (f64, f64) {
+        self.best_vmg(wind_speed, 0.0, 90.0, 1.0)
+    }
+
+    /// Returns the true wind angle and boat speed with the best velocity made good away from the wind.
+    pub fn best_downwind(&self, wind_speed: f64) -> (f64, f64) {
+        self.best_vmg(wind_speed, 90.0, 180.0, -1.0)
+    }
+
+    fn best_vmg(&self, wind_speed: f64, from: f64, to: f64, sign: f64) -> (f64, f64) {
+        const STEPS: usize = 900;
+        // Don't extrapolate beyond the deepest angle of the table.
+        let to = to.min(*self.angles.last().unwrap()).max(from);
+        (0..=STEPS)
+            .map(|i| {
+                let angle = from + (to - from) * i as f64 / STEPS as f64;
+                (angle, self.boat_speed(angle, wind_speed))
+            })
+            .max_by(|a, b| {
+                let vmg = |(angle, speed): &(f64, f64)| sign * speed * angle.to_radians().cos();
+                // A NaN wind speed from a broken sensor must not panic.
+                vmg(a)
+                    .partial_cmp(&vmg(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap()
+    }
+
+    /// Compares the boat speed in knots to the polar for the given true wind.
+    pub fn performance(&self, wind: &Wind, boat_speed: f64) -> PolarPerformance {
+        let polar_speed = self.boat_speed(wind.angle, wind.speed);
+        let (target_angle, target_speed) = if normalize_longitude(wind.angle).abs() < 90.0 {
+            self.best_upwind(wind.speed)
+        } else {
+            self.best_downwind(wind.speed)
+        };
+        PolarPerformance {
+            polar_speed,
+            percentage: if polar_speed > 0.0 {
+                boat_speed / polar_speed * 100.0
+            } else {
+                0.0
+            },
+            target_angle,
+            target_speed,
+        }
+    }
+}
+
+/// Parses a number of a polar file.
+///
+/// NaN and infinite values are rejected so the angles and speeds can be sorted.
+fn parse_number(field: &str) -> Option<f64> {
+    field
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+/// Returns the indices enclosing `value` in the ascending `values` and the weight of the upper one.
+fn bracket(values: &[f64], value: f64) -> (usize, usize, f64) {
+    match values.iter().position(|v| *v > value) {
+        Some(0) => (0, 0, 0.0),
+        Some(i) => (
+            i - 1,
+            i,
+            (value - values[i - 1]) / (values[i] - values[i - 1]),
+        ),
+        None => (values.len() - 1, values.len() - 1, 0.0),
+    }
+}
+
+/// Interpolates linearly between the `(angle, speed)` points of a single wind speed.
+fn interpolate_curve(points: &[(f64, f64)], angle: f64) -> f64 {
+    let angles: Vec<f64> = points.iter().map(|p| p.0).collect();
+    let (i0, i1, t) = bracket(&angles, angle);
+    if i0 == 0 && angle < angles[0] {
+        // Fade out towards head to wind.
+        return points[0].1 * angle.max(0.0) / angles[0];
+    }
+    points[i0].1 * (1.0 - t) + points[i1].1 * t
+}
+
+#[test]
+fn polar_formats_and_vmg() {
+    let table = Polar::parse(
+        "twa/tws;6;12\n\
+         45;5.0;6.6\n\
+         90;5.5;7.5\n\
+         150;3.8;6.4\n",
+    )
+    .unwrap();
+    let pol = Polar::parse(
+        "6\t45\t5.0\t90\t5.5\t150\t3.8\n\
+         12\t45\t6.6\t90\t7.5\t150\t6.4\n",
+    )
+    .unwrap();
+    assert_eq!(table, pol);
+
+    assert!((table.boat_speed(90.0, 9.0) - 6.5).abs() < 1e-9);
+    assert!((table.boat_speed(-67.5, 6.0) - 5.25).abs() < 1e-9);
+    assert!((table.boat_speed(90.0, 3.0) - 2.75).abs() < 1e-9);
+    assert!((table.boat_speed(90.0, 30.0) - 7.5).abs() < 1e-9);
+
+    let (angle, speed) = table.best_upwind(12.0);
+    assert!((angle - 45.0).abs() < 0.5);
+    assert!((speed - 6.6).abs() < 0.05);
+    let (angle, _) = table.best_downwind(12.0);
+    assert!((angle - 150.0).abs() < 0.5);
+
+    let performance = table.performance(
+        &Wind {
+            angle: 90.0,
+            speed: 12.0,
+        },
+        6.0,
+    );
+    assert!((performance.percentage - 80.0).abs() < 1e-9);
+    table.best_upwind(f64::NAN);
+
+    assert_eq!(Polar::parse("twa/tws;6\nNaN;5.0\n45;5.0\n"), None);
+    assert_eq!(Polar::parse("6;45;5.0;inf;5.5\n"), None);
+}
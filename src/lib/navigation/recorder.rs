@@ -0,0 +1,198 @@
+use crate::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Thresholds which decide which fixes are logged.
+#[derive(Debug, Copy, Clone)]
+pub struct TrackThinning {
+    /// The minimum distance in meters since the last logged point.
+    pub min_distance: f64,
+    /// The minimum time in seconds since the last logged point.
+    pub min_interval: f64,
+    /// After this time in seconds a point is logged even if we did not move.
+    pub max_interval: f64,
+}
+
+/// Records the fixes of our own vessel into a track log which is rotated every day.
+///
+/// The logs are written to `<directory>/track-<yyyy-mm-dd>.csv`.
+pub struct TrackRecorder {
+    directory: PathBuf,
+    thinning: TrackThinning,
+    /// The track of the current day.
+    track: Track,
+    day: Option<String>,
+    last_fix: Option<Instant>,
+}
+
+impl TrackRecorder {
+    /// Creates a new recorder and continues the log of today if there already is one.
+    pub fn new(directory: impl Into<PathBuf>, thinning: TrackThinning) -> Self {
+        let mut recorder = Self {
+            directory: directory.into(),
+            thinning,
+            track: Track::new(),
+            day: None,
+            last_fix: None,
+        };
+        recorder.rotate(&UtcDateTime::from_unix(unix_now()).date_string());
+        recorder
+    }
+
+    /// The track recorded today.
+    pub fn track(&self) -> &Track {
+        &self.track
+    }
+
+    /// The path of the CSV log of the current day.
+    pub fn log_path(&self) -> PathBuf {
+        Self::log_path_for_day(&self.directory, self.day.as_deref().unwrap_or_default())
+    }
+
+    pub fn log_path_for_day(directory: &Path, day: &str) -> PathBuf {
+        directory.join(format!("track-{}.csv", day))
+    }
+
+    /// Logs the current fix if it passes the thinning thresholds.
+    ///
+    /// Returns `true` if a point was logged.
+    pub fn update(&mut self, own_ship: &OwnShip) -> bool {
+        match own_ship.last_fix {
+            Some(last_fix) if Some(last_fix) != self.last_fix => self.last_fix = Some(last_fix),
+            // Nothing new to log.
+            _ => return false,
+        }
+
+        let time = own_ship.utc.unwrap_or_else(unix_now);
+        let point = match TrackPoint::from_own_ship(own_ship, time) {
+            Some(point) => point,
+            None => return false,
+        };
+
+        let day = UtcDateTime::from_unix(time).date_string();
+        if self.day.as_ref() != Some(&day) {
+            self.rotate(&day);
+        }
+
+        if let Some(last) = self.track.points().last() {
+            let elapsed = point.time - last.time;
+            let distance = last.position.distance_to(&point.position) * METERS_PER_NM;
+            if elapsed < self.thinning.min_interval
+                || (distance < self.thinning.min_distance && elapsed < self.thinning.max_interval)
+            {
+                return false;
+            }
+        }
+
+        self.append(&point);
+        self.track.push(point);
+        true
+    }
+
+    /// Exports the track of the current day as GPX next to the CSV log.
+    pub fn export_gpx(&self) -> Option<PathBuf> {
+        let path = self.log_path().with_extension("gpx");
+        let name = format!("Track {}", self.day.as_deref().unwrap_or_default());
+        self.export(path, self.track.to_gpx(&name))
+    }
+
+    /// Exports the track of the current day as CSV.
+    ///
+    /// The log itself is already a CSV file, this writes a cleaned up copy with all points sorted by time.
+    pub fn export_csv(&self) -> Option<PathBuf> {
+        let path = self.log_path().with_extension("export.csv");
+        self.export(path, self.track.to_csv())
+    }
+
+    fn export(&self, path: PathBuf, content: String) -> Option<PathBuf> {
+        match std::fs::write(&path, content) {
+            Ok(_) => {
+                log::info!("Exported the track to {}.", path.display());
+                Some(path)
+            }
+            Err(err) => {
+                log::error!("Unable to export the track to {}:", path.display());
+                log::error!("{}", err);
+                None
+            }
+        }
+    }
+
+    /// Starts the log of a new day.
+    fn rotate(&mut self, day: &str) {
+        self.day = Some(day.to_string());
+        let path = self.log_path();
+        self.track = if path.exists() {
+            Track::load_csv(&path.to_string_lossy()).unwrap_or_default()
+        } else {
+            Track::new()
+        };
+        log::info!("Logging the track to {}.", path.display());
+    }
+
+    fn append(&self, point: &TrackPoint) {
+        let path = self.log_path();
+        let result = std::fs::create_dir_all(&self.directory).and_then(|_| {
+            let new_file = !path.exists();
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            if new_file {
+                writeln!(file, "{}", TRACK_CSV_HEADER)?;
+            }
+            writeln!(file, "{}", point.to_csv())
+        });
+        if let Err(err) = result {
+            log::error!("Unable to write to the track log {}:", path.display());
+            log::error!("{}", err);
+        }
+    }
+}
+
+#[test]
+fn track_thinning_and_daily_rotation() {
+    let directory = std::env::temp_dir().join(format!("sailor_track_test_{}", std::process::id()));
+    let mut recorder = TrackRecorder::new(
+        &directory,
+        TrackThinning {
+            min_distance: 10.0,
+            min_interval: 5.0,
+            max_interval: 60.0,
+        },
+    );
+
+    // Two minutes before midnight of 2020-06-01.
+    const START: f64 = 1_591_055_880.0;
+    let started = Instant::now();
+    let log = |recorder: &mut TrackRecorder, seconds: u64, lon: f64| {
+        let mut own_ship = OwnShip::new();
+        own_ship.position = Some(GeoPoint::new(47.0, lon));
+        own_ship.utc = Some(START + seconds as f64);
+        own_ship.last_fix = Some(started + std::time::Duration::from_secs(seconds));
+        recorder.update(&own_ship)
+    };
+
+    assert!(log(&mut recorder, 0, 8.0));
+    // The same fix again.
+    assert!(!log(&mut recorder, 0, 8.0));
+    // Too soon although we moved far.
+    assert!(!log(&mut recorder, 2, 8.01));
+    // Less than a meter away.
+    assert!(!log(&mut recorder, 10, 8.00001));
+    assert!(log(&mut recorder, 20, 8.001));
+    // Not moving but the maximum interval passed.
+    assert!(log(&mut recorder, 90, 8.001));
+    assert_eq!(recorder.track().len(), 3);
+
+    // The next fix is after midnight and starts the log of the new day.
+    assert!(log(&mut recorder, 130, 8.001));
+    assert_eq!(recorder.track().len(), 1);
+    assert!(recorder.log_path().ends_with("track-2020-06-02.csv"));
+
+    let first_day = TrackRecorder::log_path_for_day(&directory, "2020-06-01");
+    let first_day = Track::load_csv(&first_day.to_string_lossy()).unwrap();
+    assert_eq!(first_day.len(), 3);
+    assert_eq!(first_day.points()[2].time, START + 90.0);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
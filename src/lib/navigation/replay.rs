@@ -0,0 +1,67 @@
+use crate::*;
+use std::time::Instant;
+
+/// Replays a recorded track as if it came from a live NMEA source.
+pub struct TrackReplay {
+    track: Track,
+    /// The replayed time in seconds since the unix epoch.
+    time: f64,
+    /// How many seconds of the track are replayed per second.
+    pub speed: f64,
+    pub playing: bool,
+    last_update: Option<Instant>,
+}
+
+impl TrackReplay {
+    /// Creates a new replay which starts at the beginning of the track.
+    ///
+    /// Returns `None` for empty tracks.
+    pub fn new(track: Track, speed: f64) -> Option<Self> {
+        let (start, _) = track.time_range()?;
+        Some(Self {
+            track,
+            time: start,
+            speed,
+            playing: false,
+            last_update: None,
+        })
+    }
+
+    pub fn track(&self) -> &Track {
+        &self.track
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Jumps to `time`. The time is clamped to the track.
+    pub fn set_time(&mut self, time: f64) {
+        if let Some((start, end)) = self.track.time_range() {
+            self.time = time.max(start).min(end);
+        }
+    }
+
+    /// Advances the replay time if the replay is playing.
+    ///
+    /// Returns the NMEA messages describing the replayed state.
+    pub fn update(&mut self, now: Instant) -> Vec<NmeaMessage> {
+        if let Some(last_update) = self.last_update {
+            if self.playing {
+                let elapsed = now.duration_since(last_update).as_secs_f64();
+                self.set_time(self.time + elapsed * self.speed);
+                if let Some((_, end)) = self.track.time_range() {
+                    if self.time >= end {
+                        self.playing = false;
+                    }
+                }
+            }
+        }
+        self.last_update = Some(now);
+
+        self.track
+            .point_at(self.time)
+            .map(|point| point.to_nmea_messages())
+            .unwrap_or_default()
+    }
+}
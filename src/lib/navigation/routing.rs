@@ -0,0 +1,282 @@
+use crate::*;
+use std::collections::BTreeMap;
+
+/// The parameters of the isochrone search.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RoutingOptions {
+    /// The time between two isochrones in seconds.
+    pub time_step: f64,
+    /// The angle between two headings tried from every point in degrees.
+    pub heading_step: f64,
+    /// The width of the fan of headings around the bearing to the destination in degrees.
+    pub heading_fan: f64,
+    /// Only the point farthest from the start is kept in every sector of this width in degrees.
+    pub sector_width: f64,
+    /// A leg passing the destination closer than this many nautical miles arrives.
+    pub arrival_radius: f64,
+    /// The number of isochrones after which the search gives up.
+    pub max_steps: usize,
+}
+
+impl Default for RoutingOptions {
+    fn default() -> Self {
+        Self {
+            time_step: 3600.0,
+            heading_step: 5.0,
+            heading_fan: 180.0,
+            sector_width: 1.0,
+            arrival_radius: 0.5,
+            max_steps: 240,
+        }
+    }
+}
+
+/// A point of a computed route.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RoutePoint {
+    pub position: GeoPoint,
+    /// The time the point is reached in seconds since the unix epoch.
+    pub time: f64,
+    /// The heading in degrees true on the leg to this point, `None` for the start.
+    pub heading: Option<f64>,
+    /// The boat speed in knots on the leg to this point.
+    pub speed: f64,
+    /// The true wind direction and speed at the start of the leg to this point.
+    pub wind: Option<Wind>,
+    /// The index of the previous point in the search.
+    parent: Option<usize>,
+}
+
+/// The fastest route found by the isochrone search, one point per time step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub points: Vec<RoutePoint>,
+}
+
+impl Route {
+    /// The time of departure in seconds since the unix epoch.
+    pub fn departure(&self) -> f64 {
+        self.points[0].time
+    }
+
+    /// The time of arrival at the destination in seconds since the unix epoch.
+    pub fn arrival(&self) -> f64 {
+        self.points[self.points.len() - 1].time
+    }
+
+    /// The length of the route in nautical miles.
+    pub fn distance(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|w| w[0].position.distance_to(&w[1].position))
+            .sum()
+    }
+}
+
+/// Computes routes with the isochrone method from boat polars and a wind forecast.
+///
+/// From every point of the current isochrone all headings of the fan are sailed for one time step.
+/// The new points are thinned out to the farthest one per sector around the start, which gives the next isochrone.
+/// Everything is evaluated in a fixed order, so the same inputs always give the same route.
+pub struct Router<'a> {
+    polar: &'a Polar,
+    weather: &'a GribFile,
+    water: &'a WaterMask,
+    options: RoutingOptions,
+}
+
+impl<'a> Router<'a> {
+    pub fn new(
+        polar: &'a Polar,
+        weather: &'a GribFile,
+        water: &'a WaterMask,
+        options: RoutingOptions,
+    ) -> Self {
+        Self {
+            polar,
+            weather,
+            water,
+            options,
+        }
+    }
+
+    /// Returns the fastest route from `start` to `destination` leaving at `departure` in seconds since the unix epoch.
+    ///
+    /// Returns `None` if the destination can't be reached within the configured number of steps.
+    pub fn route(&self, start: &GeoPoint, destination: &GeoPoint, departure: f64) -> Option<Route> {
+        let options = &self.options;
+        let hours = options.time_step / 3600.0;
+        let fan = (options.heading_fan / 2.0 / options.heading_step).floor() as i64;
+
+        let mut points = vec![RoutePoint {
+            position: *start,
+            time: departure,
+            heading: None,
+            speed: 0.0,
+            wind: None,
+            parent: None,
+        }];
+        let mut isochrone = vec![0];
+        for _ in 0..options.max_steps {
+            let mut arrival: Option<RoutePoint> = None;
+            let mut sectors: BTreeMap<i64, (f64, RoutePoint)> = BTreeMap::new();
+            for &index in &isochrone {
+                let from = points[index];
+                let wind = match self.weather.wind_at(&from.position, from.time) {
+                    Some(wind) => wind,
+                    None => continue,
+                };
+                let (east, north) = destination.to_local(&from.position);
+                let bearing = from.position.bearing_to(destination);
+
+                for k in -fan..=fan {
+                    let heading = normalize_degrees(bearing + k as f64 * options.heading_step);
+                    let speed = self.polar.boat_speed(wind.angle - heading, wind.speed);
+                    if speed <= 0.0 {
+                        continue;
+                    }
+                    let leg = RoutePoint {
+                        position: from.position,
+                        time: from.time,
+                        heading: Some(heading),
+                        speed,
+                        wind: Some(wind),
+                        parent: Some(index),
+                    };
+
+                    // Check whether the leg passes the destination before the end of the step.
+                    let (x, y) = course_to_vector(heading, 1.0);
+                    let along = east * x + north * y;
+                    let across = (east * y - north * x).abs();
+                    if along >= 0.0
+                        && along <= speed * hours
+                        && across <= options.arrival_radius
+                        && self.water.is_clear(&from.position, destination)
+                    {
+                        let time = from.time + along / speed * 3600.0;
+                        if arrival.iter().all(|a| time < a.time) {
+                            arrival = Some(RoutePoint {
+                                position: *destination,
+                                time,
+                                ..leg
+                            });
+                        }
+                        continue;
+                    }
+
+                    let position = from.position.destination(heading, speed * hours);
+                    if !self.water.is_clear(&from.position, &position) {
+                        continue;
+                    }
+                    let sector =
+                        (start.bearing_to(&position) / options.sector_width).floor() as i64;
+                    let distance = start.distance_to(&position);
+                    if sectors.get(&sector).iter().all(|(d, _)| distance > *d) {
+                        let point = RoutePoint {
+                            position,
+                            time: from.time + options.time_step,
+                            ..leg
+                        };
+                        sectors.insert(sector, (distance, point));
+                    }
+                }
+            }
+
+            if let Some(arrival) = arrival {
+                points.push(arrival);
+                return Some(trace_back(&points, points.len() - 1));
+            }
+            if sectors.is_empty() {
+                log::warn!("The route search is stuck, no point could be advanced.");
+                return None;
+            }
+            isochrone = sectors
+                .into_iter()
+                .map(|(_, (_, point))| {
+                    points.push(point);
+                    points.len() - 1
+                })
+                .collect();
+        }
+
+        log::warn!(
+            "No route to the destination found within {} steps.",
+            options.max_steps
+        );
+        None
+    }
+}
+
+/// Follows the parents from the point at `index` back to the start.
+fn trace_back(points: &[RoutePoint], mut index: usize) -> Route {
+    let mut route = vec![points[index]];
+    while let Some(parent) = points[index].parent {
+        route.push(points[parent]);
+        index = parent;
+    }
+    route.reverse();
+    Route { points: route }
+}
+
+#[test]
+fn isochrones_tack_upwind_and_avoid_land() {
+    let polar = Polar::parse(include_str!("../../../data/polar.csv")).unwrap();
+    let weather = GribFile::parse(include_bytes!("../../../data/routing.grib2"));
+    let departure = weather.fields()[0].reference_time;
+    let options = RoutingOptions::default();
+
+    // The fixture has a steady northerly of 12 knots, so going north means beating.
+    let water = WaterMask::new();
+    let router = Router::new(&polar, &weather, &water, options);
+    let start = GeoPoint::new(50.0, 0.0);
+    let destination = GeoPoint::new(50.5, 0.0);
+    let route = router.route(&start, &destination, departure).unwrap();
+    assert_eq!(
+        route,
+        router.route(&start, &destination, departure).unwrap()
+    );
+    assert_eq!(route.points.last().unwrap().position, destination);
+    let (angle, speed) = polar.best_upwind(12.0);
+    let vmg = speed * angle.to_radians().cos();
+    // The arrival radius lets the last leg cut the corner a little.
+    let hours = (route.arrival() - route.departure()) / 3600.0;
+    assert!(hours > 30.0 / vmg * 0.95 && hours < 30.0 / vmg * 1.1);
+    for point in &route.points[1..] {
+        let twa = normalize_longitude(point.wind.unwrap().angle - point.heading.unwrap());
+        assert!(twa.abs() >= angle - options.heading_step);
+    }
+
+    // An island right on the rhumb line of a beam reach has to be sailed around.
+    let island = [
+        GeoPoint::new(50.2, -0.2),
+        GeoPoint::new(50.2, 0.2),
+        GeoPoint::new(50.8, 0.2),
+        GeoPoint::new(50.8, -0.2),
+    ];
+    let mut water = WaterMask::new();
+    water.add_area(
+        GeoPoint::new(52.0, -3.0).to_global_space(),
+        GeoPoint::new(49.0, 3.0).to_global_space(),
+        vec![
+            vec![
+                GeoPoint::new(52.0, -3.0).to_global_space(),
+                GeoPoint::new(52.0, 3.0).to_global_space(),
+                GeoPoint::new(49.0, 3.0).to_global_space(),
+                GeoPoint::new(49.0, -3.0).to_global_space(),
+            ],
+            island.iter().map(GeoPoint::to_global_space).collect(),
+        ],
+    );
+    assert!(!water.is_water(&GeoPoint::new(50.5, 0.0)));
+    assert!(water.is_water(&GeoPoint::new(50.5, 1.0)));
+
+    let router = Router::new(&polar, &weather, &water, options);
+    let start = GeoPoint::new(50.5, -1.5);
+    let destination = GeoPoint::new(50.5, 1.5);
+    let route = router.route(&start, &destination, departure).unwrap();
+    assert_eq!(route.points.last().unwrap().position, destination);
+    assert!(route.distance() > start.distance_to(&destination));
+    for leg in route.points.windows(2) {
+        assert!(water.is_clear(&leg[0].position, &leg[1].position));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::*;
+
+/// The header of the CSV track logs.
+pub const TRACK_CSV_HEADER: &str = "time,lat,lon,cog,sog,depth,wind_angle,wind_speed";
+
+/// A single logged fix of our own vessel.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrackPoint {
+    /// UTC time in seconds since the unix epoch.
+    pub time: f64,
+    pub position: GeoPoint,
+    pub cog: Option<f64>,
+    pub sog: Option<f64>,
+    /// Depth of water in meters.
+    pub depth: Option<f64>,
+    /// The true wind.
+    pub wind: Option<Wind>,
+}
+
+impl TrackPoint {
+    /// Creates a track point from the current own ship state.
+    ///
+    /// Returns `None` if we don't have a position fix.
+    pub fn from_own_ship(own_ship: &OwnShip, time: f64) -> Option<Self> {
+        Some(Self {
+            time,
+            position: own_ship.position?,
+            cog: own_ship.cog,
+            sog: own_ship.sog,
+            depth: own_ship.depth,
+            wind: own_ship.true_wind,
+        })
+    }
+
+    /// Formats the point as a line of a CSV track log.
+    pub fn to_csv(&self) -> String {
+        let optional = |value: Option<f64>, precision: usize| {
+            value
+                .map(|v| format!("{:.*}", precision, v))
+                .unwrap_or_default()
+        };
+        format!(
+            "{},{:.6},{:.6},{},{},{},{},{}",
+            UtcDateTime::from_unix(self.time).to_iso8601(),
+            self.position.lat,
+            self.position.lon,
+            optional(self.cog, 1),
+            optional(self.sog, 1),
+            optional(self.depth, 1),
+            optional(self.wind.map(|w| w.angle), 0),
+            optional(self.wind.map(|w| w.speed), 1),
+        )
+    }
+
+    /// Parses a line of a CSV track log.
+    pub fn from_csv(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.trim().split(',').collect();
+        if fields.len() != 8 {
+            return None;
+        }
+        let optional = |i: usize| fields[i].parse::<f64>().ok();
+
+        let wind = match (optional(6), optional(7)) {
+            (Some(angle), Some(speed)) => Some(Wind { angle, speed }),
+            _ => None,
+        };
+        Some(Self {
+            time: UtcDateTime::from_iso8601(fields[0])?.to_unix(),
+            position: GeoPoint::new(fields[1].parse().ok()?, fields[2].parse().ok()?),
+            cog: optional(3),
+            sog: optional(4),
+            depth: optional(5),
+            wind,
+        })
+    }
+
+    /// Returns the NMEA messages which would have led to this track point.
+    ///
+    /// This allows to feed a replayed track through the same code paths as a live NMEA source.
+    pub fn to_nmea_messages(&self) -> Vec<NmeaMessage> {
+        let utc = UtcDateTime::from_unix(self.time);
+        let mut messages = vec![NmeaMessage::Rmc {
+            time: Some(utc.nmea_time()),
+            date: Some(utc.nmea_date()),
+            position: Some((self.position.lat, self.position.lon)),
+            sog: self.sog,
+            cog: self.cog,
+        }];
+        if let Some(depth) = self.depth {
+            messages.push(NmeaMessage::Dpt {
+                depth,
+                offset: None,
+            });
+        }
+        if let Some(wind) = self.wind {
+            messages.push(NmeaMessage::Mwv {
+                angle: wind.angle,
+                speed: wind.speed,
+                true_wind: true,
+            });
+        }
+        messages
+    }
+}
+
+/// A recorded track of our own vessel ordered by time.
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    points: Vec<TrackPoint>,
+}
+
+impl Track {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Loads a CSV track log. Lines which can't be parsed are skipped.
+    pub fn load_csv(path: &str) -> Option<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Some(Self::from_csv(&content)),
+            Err(err) => {
+                log::error!("Unable to read the track log {}:", path);
+                log::error!("{}", err);
+                None
+            }
+        }
+    }
+
+    pub fn from_csv(content: &str) -> Self {
+        let mut points: Vec<TrackPoint> = content
+            .lines()
+            .filter(|line| *line != TRACK_CSV_HEADER && !line.trim().is_empty())
+            .filter_map(|line| {
+                let point = TrackPoint::from_csv(line);
+                if point.is_none() {
+                    log::warn!("Skipping the malformed track log line '{}'.", line);
+                }
+                point
+            })
+            .collect();
+        points.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        Self { points }
+    }
+
+    pub fn push(&mut self, point: TrackPoint) {
+        self.points.push(point);
+    }
+
+    pub fn points(&self) -> &[TrackPoint] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns the time of the first and the last point.
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        Some((self.points.first()?.time, self.points.last()?.time))
+    }
+
+    /// Returns the interpolated state at `time`.
+    ///
+    /// Times outside of the track are clamped to its first or last point.
+    pub fn point_at(&self, time: f64) -> Option<TrackPoint> {
+        let next = self.points.iter().position(|p| p.time >= time);
+        match next {
+            None => self.points.last().copied(),
+            Some(0) => self.points.first().copied(),
+            Some(i) => {
+                let a = &self.points[i - 1];
+                let b = &self.points[i];
+                let t = (time - a.time) / (b.time - a.time);
+                let lerp = |a: Option<f64>, b: Option<f64>| match (a, b) {
+                    (Some(a), Some(b)) => Some(a + (b - a) * t),
+                    _ => a.or(b),
+                };
+                Some(TrackPoint {
+                    time,
+                    position: GeoPoint::new(
+                        a.position.lat + (b.position.lat - a.position.lat) * t,
+                        a.position.lon + (b.position.lon - a.position.lon) * t,
+                    ),
+                    // Interpolating angles would need care around north, so keep the last known values.
+                    cog: a.cog,
+                    sog: lerp(a.sog, b.sog),
+                    depth: lerp(a.depth, b.depth),
+                    wind: a.wind,
+                })
+            }
+        }
+    }
+
+    /// Formats the track as CSV track log.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(TRACK_CSV_HEADER);
+        csv.push('\n');
+        for point in &self.points {
+            csv.push_str(&point.to_csv());
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Formats the track as GPX 1.1 document.
+    pub fn to_gpx(&self, name: &str) -> String {
+        let mut gpx = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <gpx version=\"1.1\" creator=\"sailor\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+        );
+        gpx.push_str(&format!(
+            "  <trk>\n    <name>{}</name>\n    <trkseg>\n",
+            name
+        ));
+        for point in &self.points {
+            gpx.push_str(&format!(
+                "      <trkpt lat=\"{:.6}\" lon=\"{:.6}\"><time>{}</time>",
+                point.position.lat,
+                point.position.lon,
+                UtcDateTime::from_unix(point.time).to_iso8601()
+            ));
+            if let Some(cog) = point.cog {
+                gpx.push_str(&format!("<course>{:.1}</course>", cog));
+            }
+            if let Some(sog) = point.sog {
+                // GPX wants the speed in meters per second.
+                gpx.push_str(&format!(
+                    "<speed>{:.2}</speed>",
+                    sog * METERS_PER_NM / 3600.0
+                ));
+            }
+            gpx.push_str("</trkpt>\n");
+        }
+        gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+        gpx
+    }
+}
+
+#[test]
+fn track_csv_roundtrip() {
+    let mut track = Track::new();
+    track.push(TrackPoint {
+        time: 1_591_014_600.0,
+        position: GeoPoint::new(47.3769, 8.5417),
+        cog: Some(90.0),
+        sog: Some(5.5),
+        depth: Some(12.3),
+        wind: Some(Wind {
+            angle: 270.0,
+            speed: 12.5,
+        }),
+    });
+    track.push(TrackPoint {
+        time: 1_591_014_660.0,
+        position: GeoPoint::new(47.3769, 8.5437),
+        cog: Some(90.0),
+        sog: Some(6.5),
+        depth: None,
+        wind: None,
+    });
+
+    let csv = track.to_csv();
+    assert!(csv.contains("2020-06-01T12:30:00Z,47.376900,8.541700,90.0,5.5,12.3,270,12.5"));
+    let parsed = Track::from_csv(&csv);
+    assert_eq!(parsed.points(), track.points());
+
+    let middle = parsed.point_at(1_591_014_630.0).unwrap();
+    assert!((middle.position.lon - 8.5427).abs() < 1e-9);
+    assert_eq!(middle.sog, Some(6.0));
+}
@@ -0,0 +1,138 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date and time in UTC.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: f64,
+}
+
+impl UtcDateTime {
+    /// Converts seconds since the unix epoch to a calendar date.
+    pub fn from_unix(time: f64) -> Self {
+        let days = (time / 86_400.0).floor();
+        let seconds = time - days * 86_400.0;
+        let (year, month, day) = civil_from_days(days as i64);
+        Self {
+            year,
+            month,
+            day,
+            hour: (seconds / 3600.0) as u32,
+            minute: (seconds % 3600.0 / 60.0) as u32,
+            second: seconds % 60.0,
+        }
+    }
+
+    /// Returns the seconds since the unix epoch.
+    pub fn to_unix(&self) -> f64 {
+        days_from_civil(self.year, self.month, self.day) as f64 * 86_400.0
+            + self.hour as f64 * 3600.0
+            + self.minute as f64 * 60.0
+            + self.second
+    }
+
+    /// Returns the year with the elapsed part of it as a fraction, e.g. `2020.5` in the beginning of July.
+    pub fn decimal_year(&self) -> f64 {
+        let start = days_from_civil(self.year, 1, 1) as f64 * 86_400.0;
+        let end = days_from_civil(self.year + 1, 1, 1) as f64 * 86_400.0;
+        self.year as f64 + (self.to_unix() - start) / (end - start)
+    }
+
+    /// Parses the `ddmmyy` date and `hhmmss.ss` time fields of NMEA sentences.
+    pub fn from_nmea(date: &str, time: &str) -> Option<Self> {
+        if date.len() != 6 || time.len() < 6 || !date.is_ascii() || !time.is_ascii() {
+            return None;
+        }
+        let year: i64 = date[4..6].parse().ok()?;
+        Some(Self {
+            // NMEA only has two digit years, assume the current century.
+            year: 2000 + year,
+            month: date[2..4].parse().ok()?,
+            day: date[0..2].parse().ok()?,
+            hour: time[0..2].parse().ok()?,
+            minute: time[2..4].parse().ok()?,
+            second: time[4..].parse().ok()?,
+        })
+    }
+
+    /// Parses a date and time in the `2020-06-01T12:30:00Z` form written by `to_iso8601`.
+    pub fn from_iso8601(value: &str) -> Option<Self> {
+        let value = value.strip_suffix('Z')?;
+        if value.len() < 19 || !value.is_ascii() || &value[10..11] != "T" {
+            return None;
+        }
+        Some(Self {
+            year: value[0..4].parse().ok()?,
+            month: value[5..7].parse().ok()?,
+            day: value[8..10].parse().ok()?,
+            hour: value[11..13].parse().ok()?,
+            minute: value[14..16].parse().ok()?,
+            second: value[17..].parse().ok()?,
+        })
+    }
+
+    /// Formats the date as `ddmmyy` like in NMEA sentences.
+    pub fn nmea_date(&self) -> String {
+        format!("{:02}{:02}{:02}", self.day, self.month, self.year % 100)
+    }
+
+    /// Formats the time as `hhmmss.ss` like in NMEA sentences.
+    pub fn nmea_time(&self) -> String {
+        format!("{:02}{:02}{:05.2}", self.hour, self.minute, self.second)
+    }
+
+    /// Formats the date as `yyyy-mm-dd`.
+    pub fn date_string(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Formats the date and time as ISO 8601 like `2020-06-01T12:30:00Z`.
+    pub fn to_iso8601(&self) -> String {
+        format!(
+            "{}T{:02}:{:02}:{:02}Z",
+            self.date_string(),
+            self.hour,
+            self.minute,
+            self.second.floor() as u32
+        )
+    }
+}
+
+/// Returns the current time in seconds since the unix epoch.
+pub fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Returns the number of days since the unix epoch of a date in the proleptic gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,188 @@
+use crate::*;
+use lyon::math::{point, Point};
+
+/// The largest distance between two samples when checking a leg against the mask in nautical miles.
+const SAMPLE_DISTANCE: f64 = 0.1;
+
+/// The most samples taken along a single leg.
+const MAX_SAMPLES: usize = 200;
+
+/// A ring in global space with its bounding box.
+#[derive(Debug, Clone)]
+struct Ring {
+    points: Vec<Point>,
+    min: Point,
+    max: Point,
+}
+
+impl Ring {
+    fn new(points: Vec<Point>) -> Self {
+        let (min, max) = points.iter().fold(
+            (
+                point(f32::INFINITY, f32::INFINITY),
+                point(f32::NEG_INFINITY, f32::NEG_INFINITY),
+            ),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        Self { points, min, max }
+    }
+
+    /// Whether a horizontal ray from `p` crosses the ring an odd number of times.
+    fn crossed_odd(&self, p: &Point) -> bool {
+        if p.x < self.min.x || p.x > self.max.x || p.y < self.min.y || p.y > self.max.y {
+            return false;
+        }
+        let mut odd = false;
+        let mut j = self.points.len() - 1;
+        for i in 0..self.points.len() {
+            let (a, b) = (self.points[i], self.points[j]);
+            if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+                odd = !odd;
+            }
+            j = i;
+        }
+        odd
+    }
+}
+
+/// A rectangular area in global space and the water rings within it.
+#[derive(Debug, Clone)]
+struct WaterArea {
+    min: Point,
+    max: Point,
+    rings: Vec<Ring>,
+}
+
+impl WaterArea {
+    fn contains(&self, p: &Point) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    /// Combines the rings with the even-odd rule, so a ring inside another one cuts out an island.
+    fn is_water(&self, p: &Point) -> bool {
+        self.rings
+            .iter()
+            .fold(false, |inside, ring| inside != ring.crossed_odd(p))
+    }
+}
+
+/// The navigable water as known from the `water` layer of the vector tiles.
+///
+/// Only the areas of the added tiles are checked, everywhere else is assumed to be water
+/// since we know nothing about it. Where areas overlap, e.g. tiles of different zoom levels,
+/// a position is water if any of them says so.
+#[derive(Debug, Clone, Default)]
+pub struct WaterMask {
+    areas: Vec<WaterArea>,
+}
+
+impl WaterMask {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the water polygons of a tile as a new area covering the whole tile.
+    ///
+    /// Every ring of a polygon is added on its own, so islands cut out of the water stay land.
+    ///
+    /// The points of the objects are in the tile space of `tile_id` spanning `[0, extent]`.
+    pub fn add_tile(&mut self, tile_id: &TileId, extent: u16, objects: &[Object]) {
+        let scale = 1.0 / (1u64 << tile_id.z) as f32;
+        let extent = extent as f32;
+        let to_global = |x: f32, y: f32| {
+            point(
+                (tile_id.x as f32 + x / extent) * scale,
+                (tile_id.y as f32 + y / extent) * scale,
+            )
+        };
+
+        let rings = objects
+            .iter()
+            .filter(|object| {
+                object.selector().any.get("name").map(String::as_str) == Some("water")
+                    && object.points().len() >= 3
+            })
+            .flat_map(|object| object.rings())
+            .map(|ring| ring.iter().map(|p| to_global(p.x, p.y)).collect())
+            .collect();
+        self.add_area(to_global(0.0, 0.0), to_global(extent, extent), rings);
+    }
+
+    /// Adds the rectangle between the `min` and `max` corners in global space with its water rings.
+    ///
+    /// Within the area everything outside of the rings is land.
+    pub fn add_area(&mut self, min: Point, max: Point, rings: Vec<Vec<Point>>) {
+        self.areas.push(WaterArea {
+            min,
+            max,
+            rings: rings
+                .into_iter()
+                .filter(|ring| ring.len() >= 3)
+                .map(Ring::new)
+                .collect(),
+        });
+    }
+
+    /// Whether the mask knows nothing about any area.
+    pub fn is_empty(&self) -> bool {
+        self.areas.is_empty()
+    }
+
+    /// Whether a position is in the water or outside of all areas.
+    pub fn is_water(&self, position: &GeoPoint) -> bool {
+        let p = position.to_global_space();
+        let mut covering = self
+            .areas
+            .iter()
+            .filter(|area| area.contains(&p))
+            .peekable();
+        covering.peek().is_none() || covering.any(|area| area.is_water(&p))
+    }
+
+    /// Whether the straight line between two positions stays in the water.
+    ///
+    /// The line is sampled, so land narrower than the sample distance can be missed.
+    pub fn is_clear(&self, from: &GeoPoint, to: &GeoPoint) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let (east, north) = to.to_local(from);
+        let samples =
+            ((from.distance_to(to) / SAMPLE_DISTANCE).ceil() as usize).clamp(1, MAX_SAMPLES);
+        (0..=samples).all(|i| {
+            let t = i as f64 / samples as f64;
+            self.is_water(&GeoPoint::from_local(from, east * t, north * t))
+        })
+    }
+}
+
+#[test]
+fn water_polygon_with_island() {
+    let square = |x: f32, y: f32, size: f32| {
+        vec![
+            point(x, y),
+            point(x + size, y),
+            point(x + size, y + size),
+            point(x, y + size),
+        ]
+    };
+    let mut points = square(0.0, 0.0, 4096.0);
+    points.extend(square(1024.0, 1024.0, 2048.0));
+    let water = Object::new(
+        Selector::new().with_type("layer").with_any("name", "water"),
+        points,
+        ObjectType::Polygon,
+    )
+    .with_ring_ends(vec![4, 8]);
+
+    let tile_id = TileId::new(1, 0, 0);
+    let mut mask = WaterMask::new();
+    mask.add_tile(&tile_id, 4096, &[water]);
+
+    let at = |x: f32, y: f32| GeoPoint::from_global_space(&point(x, y));
+    assert!(mask.is_water(&at(0.05, 0.05)));
+    // Next to the island where an edge joining both rings would pass.
+    assert!(mask.is_water(&at(900.0 / 8192.0, 1500.0 / 8192.0)));
+    assert!(!mask.is_water(&at(0.25, 0.25)));
+    assert!(!mask.is_clear(&at(0.05, 0.25), &at(0.45, 0.25)));
+}
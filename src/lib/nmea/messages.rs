@@ -0,0 +1,156 @@
+use super::*;
+
+/// A decoded NMEA 0183 sentence that carries own ship data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NmeaMessage {
+    /// Recommended minimum navigation data.
+    Rmc {
+        /// UTC time of the fix as `hhmmss.ss`.
+        time: Option<String>,
+        /// UTC date of the fix as `ddmmyy`.
+        date: Option<String>,
+        /// `None` if the receiver reports the fix as invalid.
+        position: Option<(f64, f64)>,
+        /// Speed over ground in knots.
+        sog: Option<f64>,
+        /// Course over ground in degrees true.
+        cog: Option<f64>,
+    },
+    /// Course and speed over ground.
+    Vtg { cog: Option<f64>, sog: Option<f64> },
+    /// True heading.
+    Hdt { heading: f64 },
+    /// Magnetic sensor heading in degrees with the deviation and variation, both positive to the east.
+    Hdg {
+        heading: f64,
+        deviation: Option<f64>,
+        variation: Option<f64>,
+    },
+    /// Depth of water in meters below the transducer.
+    ///
+    /// A positive `offset` is the distance from the transducer to the waterline,
+    /// a negative one the distance to the keel.
+    Dpt { depth: f64, offset: Option<f64> },
+    /// Depth below transducer in meters.
+    Dbt { depth: f64 },
+    /// Wind speed in knots and angle in degrees.
+    Mwv {
+        angle: f64,
+        speed: f64,
+        /// `true` for the true wind, `false` for the apparent wind relative to the bow.
+        true_wind: bool,
+    },
+    /// True wind direction in degrees true and speed in knots.
+    Mwd { direction: f64, speed: f64 },
+    /// Speed through water in knots.
+    Vhw { speed: f64 },
+}
+
+impl NmeaMessage {
+    /// Decodes the data of a sentence.
+    ///
+    /// Returns `None` if the sentence type is not supported or the sentence is malformed.
+    pub fn parse(sentence: &NmeaSentence) -> Option<Self> {
+        if sentence.encapsulated {
+            return None;
+        }
+
+        match &sentence.kind[..] {
+            "RMC" => Some(NmeaMessage::Rmc {
+                time: sentence.field(0).map(str::to_string),
+                date: sentence.field(8).map(str::to_string),
+                position: if sentence.field(1) == Some("A") {
+                    parse_position(sentence, 2)
+                } else {
+                    None
+                },
+                sog: sentence.number(6),
+                cog: sentence.number(7),
+            }),
+            "VTG" => Some(NmeaMessage::Vtg {
+                cog: sentence.number(0),
+                sog: sentence.number(4),
+            }),
+            "HDT" => Some(NmeaMessage::Hdt {
+                heading: sentence.number(0)?,
+            }),
+            "HDG" => Some(NmeaMessage::Hdg {
+                heading: sentence.number(0)?,
+                deviation: parse_magnetic_angle(sentence, 1),
+                variation: parse_magnetic_angle(sentence, 3),
+            }),
+            "DPT" => Some(NmeaMessage::Dpt {
+                depth: sentence.number(0)?,
+                offset: sentence.number(1),
+            }),
+            "DBT" => Some(NmeaMessage::Dbt {
+                depth: sentence.number(2)?,
+            }),
+            "MWV" => {
+                if sentence.field(4) != Some("A") {
+                    return None;
+                }
+                let speed: f64 = sentence.number(2)?;
+                Some(NmeaMessage::Mwv {
+                    angle: sentence.number(0)?,
+                    speed: match sentence.field(3)? {
+                        "N" => speed,
+                        "K" => speed / 1.852,
+                        "M" => speed * 3.6 / 1.852,
+                        _ => return None,
+                    },
+                    true_wind: sentence.field(1)? == "T",
+                })
+            }
+            "MWD" => Some(NmeaMessage::Mwd {
+                direction: sentence.number(0)?,
+                speed: match sentence.number(4) {
+                    Some(knots) => knots,
+                    None => sentence.number::<f64>(6)? * 3.6 / 1.852,
+                },
+            }),
+            "VHW" => Some(NmeaMessage::Vhw {
+                speed: sentence.number(4)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a `ddmm.mmmm,N,dddmm.mmmm,E` position starting at field `index`.
+///
+/// Returns the position as `(lat, lon)` in degrees.
+pub fn parse_position(sentence: &NmeaSentence, index: usize) -> Option<(f64, f64)> {
+    let lat = parse_angle(sentence.field(index)?, 2)?;
+    let lat = match sentence.field(index + 1)? {
+        "N" => lat,
+        "S" => -lat,
+        _ => return None,
+    };
+    let lon = parse_angle(sentence.field(index + 2)?, 3)?;
+    let lon = match sentence.field(index + 3)? {
+        "E" => lon,
+        "W" => -lon,
+        _ => return None,
+    };
+    Some((lat, lon))
+}
+
+/// Parses an angle at field `index` followed by `E` or `W`, returning it positive to the east.
+fn parse_magnetic_angle(sentence: &NmeaSentence, index: usize) -> Option<f64> {
+    let angle: f64 = sentence.number(index)?;
+    match sentence.field(index + 1)? {
+        "E" => Some(angle),
+        "W" => Some(-angle),
+        _ => None,
+    }
+}
+
+/// Parses a `dddmm.mmmm` angle where the degrees have `degree_digits` digits.
+fn parse_angle(value: &str, degree_digits: usize) -> Option<f64> {
+    if value.len() < degree_digits || !value.is_ascii() {
+        return None;
+    }
+    let (degrees, minutes) = value.split_at(degree_digits);
+    Some(degrees.parse::<f64>().ok()? + minutes.parse::<f64>().ok()? / 60.0)
+}
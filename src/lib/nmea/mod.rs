@@ -0,0 +1,7 @@
+mod messages;
+mod sentence;
+mod source;
+
+pub use messages::*;
+pub use sentence::*;
+pub use source::*;
@@ -0,0 +1,97 @@
+/// A single checksummed NMEA 0183 sentence.
+///
+/// E.g. `$GPRMC,...*6A` or `!AIVDM,...*4A`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NmeaSentence {
+    /// Whether the sentence starts with `!` (encapsulated, used by AIS) instead of `$`.
+    pub encapsulated: bool,
+    /// The talker id.
+    /// E.g. `"GP"` or `"AI"`.
+    pub talker: String,
+    /// The sentence formatter.
+    /// E.g. `"RMC"` or `"VDM"`.
+    pub kind: String,
+    /// All the comma separated data fields following the address field.
+    pub fields: Vec<String>,
+}
+
+impl NmeaSentence {
+    /// Tries to parse a single line into a sentence.
+    ///
+    /// Returns `None` if the line is not framed correctly or the checksum does not match.
+    /// Sentences without a checksum are accepted.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let encapsulated = match line.chars().next()? {
+            '$' => false,
+            '!' => true,
+            _ => return None,
+        };
+
+        let (data, checksum) = match line[1..].rfind('*') {
+            Some(i) => (&line[1..i + 1], Some(&line[i + 2..])),
+            None => (&line[1..], None),
+        };
+
+        if let Some(checksum) = checksum {
+            let expected = u8::from_str_radix(checksum, 16).ok()?;
+            let actual = nmea_checksum(data);
+            if expected != actual {
+                log::debug!(
+                    "Dropping NMEA sentence with bad checksum {:02X} (expected {:02X}): {}",
+                    actual,
+                    expected,
+                    line
+                );
+                return None;
+            }
+        }
+
+        let mut fields = data.split(',');
+        let address = fields.next()?;
+        if address.len() < 5 || !address.is_ascii() {
+            return None;
+        }
+        // Proprietary sentences (`$P...`) have no talker id.
+        let (talker, kind) = match address.strip_prefix('P') {
+            Some(kind) => ("P", kind),
+            None => address.split_at(2),
+        };
+
+        Some(Self {
+            encapsulated,
+            talker: talker.to_string(),
+            kind: kind.to_string(),
+            fields: fields.map(|f| f.to_string()).collect(),
+        })
+    }
+
+    /// Returns the field at `index` if it is present and not empty.
+    pub fn field(&self, index: usize) -> Option<&str> {
+        self.fields
+            .get(index)
+            .map(|f| &f[..])
+            .filter(|f| !f.is_empty())
+    }
+
+    /// Parses the field at `index` as a number.
+    pub fn number<T: std::str::FromStr>(&self, index: usize) -> Option<T> {
+        self.field(index)?.parse().ok()
+    }
+}
+
+/// Calculates the NMEA checksum of everything between the start delimiter and the `*`.
+pub fn nmea_checksum(data: &str) -> u8 {
+    data.bytes().fold(0, |acc, b| acc ^ b)
+}
+
+#[test]
+fn parse_checksummed_sentence() {
+    let sentence = NmeaSentence::parse("!AIVDM,1,1,,A,15RTgt0PAso;90TKcjM8h6g208CQ,0*4A").unwrap();
+    assert!(sentence.encapsulated);
+    assert_eq!(sentence.talker, "AI");
+    assert_eq!(sentence.kind, "VDM");
+    assert_eq!(sentence.field(2), None);
+    assert_eq!(sentence.field(4), Some("15RTgt0PAso;90TKcjM8h6g208CQ"));
+    assert!(NmeaSentence::parse("!AIVDM,1,1,,A,15RTgt0PAso;90TKcjM8h6g208CQ,0*4B").is_none());
+}
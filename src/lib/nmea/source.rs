@@ -0,0 +1,102 @@
+use super::*;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    net::TcpStream,
+    thread::{spawn, JoinHandle},
+    time::Duration,
+};
+
+/// A stream of NMEA sentences read on a background thread.
+///
+/// The source can either be a TCP connection (`tcp://host:port`) to a multiplexer
+/// or a recorded NMEA log file which is replayed line by line.
+pub struct NmeaSource {
+    address: String,
+    rx: Receiver<NmeaSentence>,
+    _loader: JoinHandle<()>,
+}
+
+impl NmeaSource {
+    /// Opens a new NMEA source.
+    ///
+    /// `line_delay` throttles the replay of log files. It is ignored for TCP sources.
+    pub fn open(address: impl Into<String>, line_delay: Duration) -> Option<Self> {
+        let address = address.into();
+        let (tx, rx) = unbounded();
+
+        let reader: Box<dyn Read + Send> = if let Some(host) = address.strip_prefix("tcp://") {
+            match TcpStream::connect(host) {
+                Ok(stream) => Box::new(stream),
+                Err(e) => {
+                    log::error!("Unable to connect to {}. Reason:\r\n{}", address, e);
+                    return None;
+                }
+            }
+        } else {
+            match File::open(&address) {
+                Ok(file) => Box::new(file),
+                Err(e) => {
+                    log::error!("Unable to open {}. Reason:\r\n{}", address, e);
+                    return None;
+                }
+            }
+        };
+        let line_delay = if address.starts_with("tcp://") {
+            Duration::from_secs(0)
+        } else {
+            line_delay
+        };
+
+        let address_clone = address.clone();
+        let loader = spawn(move || Self::read_lines(reader, tx, line_delay, address_clone));
+
+        Some(Self {
+            address,
+            rx,
+            _loader: loader,
+        })
+    }
+
+    fn read_lines(
+        reader: Box<dyn Read + Send>,
+        tx: Sender<NmeaSentence>,
+        line_delay: Duration,
+        address: String,
+    ) {
+        for line in BufReader::new(reader).lines() {
+            match line {
+                Ok(line) => {
+                    if let Some(sentence) = NmeaSentence::parse(&line) {
+                        if tx.send(sentence).is_err() {
+                            log::debug!(
+                                "The NMEA source {} was dropped. Stopping to read.",
+                                address
+                            );
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to read from {}. Reason:\r\n{}", address, e);
+                    return;
+                }
+            }
+            if line_delay.as_nanos() > 0 {
+                std::thread::sleep(line_delay);
+            }
+        }
+        log::info!("Reached the end of the NMEA source {}.", address);
+    }
+
+    /// Returns the address the source was opened with.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Returns all the sentences which were received since the last call.
+    pub fn try_iter(&self) -> impl Iterator<Item = NmeaSentence> + '_ {
+        self.rx.try_iter()
+    }
+}
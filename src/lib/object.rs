@@ -20,6 +20,8 @@ pub struct Object {
     /// If this is a line, the points describe the line in order.
     /// For a point there is only one point contained.
     points: Vec<Point>,
+    /// The index in `points` after the last point of each ring of a polygon.
+    ring_ends: Vec<usize>,
     /// All the OSM tags that are attached to this object.
     tags: HashMap<String, String>,
     /// The object type.
@@ -31,6 +33,7 @@ impl Object {
     pub fn new(selector: Selector, points: Vec<Point>, object_type: ObjectType) -> Self {
         Self {
             selector,
+            ring_ends: vec![points.len()],
             points,
             tags: HashMap::new(),
             object_type,
@@ -46,17 +49,34 @@ impl Object {
     ) -> Self {
         Self {
             selector,
+            ring_ends: vec![points.len()],
             points,
             tags,
             object_type,
         }
     }
 
+    /// Splits the points into several rings, e.g. the outline and the holes of a polygon.
+    ///
+    /// Every ring ends before the given index in the points.
+    pub fn with_ring_ends(mut self, ring_ends: Vec<usize>) -> Self {
+        self.ring_ends = ring_ends;
+        self
+    }
+
     /// Returns the set of points contained in the object.
     pub fn points(&self) -> &Vec<Point> {
         &self.points
     }
 
+    /// Returns the points of every ring of the object.
+    pub fn rings(&self) -> impl Iterator<Item = &[Point]> {
+        let starts = std::iter::once(0).chain(self.ring_ends.iter().copied());
+        starts
+            .zip(self.ring_ends.iter())
+            .filter_map(move |(start, end)| self.points.get(start..*end))
+    }
+
     /// Returns the set of tags contained in the object.
     pub fn tags(&self) -> &HashMap<String, String> {
         &self.tags
@@ -66,4 +86,9 @@ impl Object {
     pub fn selector(&self) -> &Selector {
         &self.selector
     }
+
+    /// Returns the type of the object.
+    pub fn object_type(&self) -> &ObjectType {
+        &self.object_type
+    }
 }
@@ -0,0 +1,176 @@
+/// The rates of change of the astronomical arguments in degrees per hour.
+///
+/// In order: hour angle of the mean sun, mean longitude of the moon, mean longitude of the sun,
+/// longitude of the lunar perigee, longitude of the lunar ascending node and longitude of the solar perigee.
+const ARGUMENT_SPEEDS: [f64; 6] = [
+    15.0,
+    0.549_016_5,
+    0.041_068_6,
+    0.004_641_8,
+    -0.002_206_4,
+    0.000_002_0,
+];
+
+/// The astronomical arguments in degrees at a given time.
+#[derive(Debug, Copy, Clone)]
+pub struct AstronomicalArguments {
+    values: [f64; 6],
+}
+
+impl AstronomicalArguments {
+    /// Computes the arguments at `time` in seconds since the unix epoch.
+    pub fn at(time: f64) -> Self {
+        // Julian centuries since J2000.0.
+        let t = (time / 86_400.0 + 2_440_587.5 - 2_451_545.0) / 36_525.0;
+        let hours = (time / 3600.0).rem_euclid(24.0);
+        Self {
+            values: [
+                180.0 + 15.0 * hours,
+                218.316_4 + 481_267.881_2 * t,
+                280.466_1 + 36_000.769_8 * t,
+                83.353_5 + 4_069.013_7 * t,
+                125.044_5 - 1_934.136_3 * t,
+                282.938_4 + 1.719_5 * t,
+            ],
+        }
+    }
+
+    /// The longitude of the lunar ascending node in radians, which drives the nodal corrections.
+    fn node(&self) -> f64 {
+        self.values[4].to_radians()
+    }
+}
+
+/// How the amplitude and phase of a constituent are modulated over the 18.6 year nodal cycle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Nodal {
+    None,
+    M2,
+    K1,
+    O1,
+    K2,
+    J1,
+    Mf,
+    Mm,
+    /// The correction of a compound tide which is the product of `n` M2 tides.
+    M2Power(i32),
+}
+
+/// A tidal constituent from the Doodson expansion of the tide generating potential.
+#[derive(Debug, Copy, Clone)]
+pub struct Constituent {
+    pub name: &'static str,
+    /// The multiples of the astronomical arguments.
+    doodson: [i8; 6],
+    /// An additional constant phase in degrees.
+    phase: f64,
+    nodal: Nodal,
+}
+
+impl Constituent {
+    /// Looks up a constituent by its name, e.g. `M2`.
+    pub fn by_name(name: &str) -> Option<&'static Constituent> {
+        CONSTITUENTS
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The angular speed in degrees per hour.
+    pub fn speed(&self) -> f64 {
+        self.doodson
+            .iter()
+            .zip(ARGUMENT_SPEEDS.iter())
+            .map(|(n, speed)| *n as f64 * speed)
+            .sum()
+    }
+
+    /// The equilibrium argument `V` in degrees.
+    pub fn equilibrium_argument(&self, arguments: &AstronomicalArguments) -> f64 {
+        self.doodson
+            .iter()
+            .zip(arguments.values.iter())
+            .map(|(n, value)| *n as f64 * value)
+            .sum::<f64>()
+            + self.phase
+    }
+
+    /// The nodal amplitude factor `f` and phase correction `u` in degrees.
+    pub fn nodal_correction(&self, arguments: &AstronomicalArguments) -> (f64, f64) {
+        let n = arguments.node();
+        let (cos1, cos2, cos3) = (n.cos(), (2.0 * n).cos(), (3.0 * n).cos());
+        let (sin1, sin2, sin3) = (n.sin(), (2.0 * n).sin(), (3.0 * n).sin());
+        let m2 = (1.0004 - 0.0373 * cos1 + 0.0002 * cos2, -2.14 * sin1);
+        match self.nodal {
+            Nodal::None => (1.0, 0.0),
+            Nodal::M2 => m2,
+            Nodal::M2Power(power) => (m2.0.powi(power), m2.1 * power as f64),
+            Nodal::K1 => (
+                1.0060 + 0.1150 * cos1 - 0.0088 * cos2 + 0.0006 * cos3,
+                -8.86 * sin1 + 0.68 * sin2 - 0.07 * sin3,
+            ),
+            Nodal::O1 => (
+                1.0089 + 0.1871 * cos1 - 0.0147 * cos2 + 0.0014 * cos3,
+                10.80 * sin1 - 1.34 * sin2 + 0.19 * sin3,
+            ),
+            Nodal::K2 => (
+                1.0241 + 0.2863 * cos1 + 0.0083 * cos2 - 0.0015 * cos3,
+                -17.74 * sin1 + 0.68 * sin2 - 0.04 * sin3,
+            ),
+            Nodal::J1 => (
+                1.0129 + 0.1676 * cos1 - 0.0170 * cos2 + 0.0016 * cos3,
+                -12.94 * sin1 + 1.34 * sin2 - 0.19 * sin3,
+            ),
+            Nodal::Mf => (1.043 + 0.414 * cos1, -23.7 * sin1 + 2.7 * sin2 - 0.4 * sin3),
+            Nodal::Mm => (1.0 - 0.130 * cos1, 0.0),
+        }
+    }
+}
+
+macro_rules! constituent {
+    ($name:expr, [$($d:expr),*], $phase:expr, $nodal:expr) => {
+        Constituent {
+            name: $name,
+            doodson: [$($d),*],
+            phase: $phase,
+            nodal: $nodal,
+        }
+    };
+}
+
+/// All supported constituents.
+pub static CONSTITUENTS: &[Constituent] = &[
+    // Semi-diurnal
+    constituent!("M2", [2, -2, 2, 0, 0, 0], 0.0, Nodal::M2),
+    constituent!("S2", [2, 0, 0, 0, 0, 0], 0.0, Nodal::None),
+    constituent!("N2", [2, -3, 2, 1, 0, 0], 0.0, Nodal::M2),
+    constituent!("K2", [2, 0, 2, 0, 0, 0], 0.0, Nodal::K2),
+    constituent!("2N2", [2, -4, 2, 2, 0, 0], 0.0, Nodal::M2),
+    constituent!("MU2", [2, -4, 4, 0, 0, 0], 0.0, Nodal::M2),
+    constituent!("NU2", [2, -3, 4, -1, 0, 0], 0.0, Nodal::M2),
+    constituent!("L2", [2, -1, 2, -1, 0, 0], 180.0, Nodal::M2),
+    constituent!("T2", [2, 0, -1, 0, 0, 1], 0.0, Nodal::None),
+    // Diurnal
+    constituent!("K1", [1, 0, 1, 0, 0, 0], 90.0, Nodal::K1),
+    constituent!("O1", [1, -2, 1, 0, 0, 0], -90.0, Nodal::O1),
+    constituent!("P1", [1, 0, -1, 0, 0, 0], -90.0, Nodal::None),
+    constituent!("Q1", [1, -3, 1, 1, 0, 0], -90.0, Nodal::O1),
+    constituent!("J1", [1, 1, 1, -1, 0, 0], 90.0, Nodal::J1),
+    // Shallow water
+    constituent!("M4", [4, -4, 4, 0, 0, 0], 0.0, Nodal::M2Power(2)),
+    constituent!("MS4", [4, -2, 2, 0, 0, 0], 0.0, Nodal::M2),
+    constituent!("M6", [6, -6, 6, 0, 0, 0], 0.0, Nodal::M2Power(3)),
+    // Long period
+    constituent!("Mf", [0, 2, 0, 0, 0, 0], 0.0, Nodal::Mf),
+    constituent!("Mm", [0, 1, 0, -1, 0, 0], 0.0, Nodal::Mm),
+    constituent!("Ssa", [0, 0, 2, 0, 0, 0], 0.0, Nodal::None),
+    constituent!("Sa", [0, 0, 1, 0, 0, 0], 0.0, Nodal::None),
+];
+
+#[test]
+fn constituent_speeds() {
+    let speed = |name| Constituent::by_name(name).unwrap().speed();
+    assert!((speed("M2") - 28.984_104).abs() < 1e-4);
+    assert!((speed("S2") - 30.0).abs() < 1e-9);
+    assert!((speed("K1") - 15.041_069).abs() < 1e-4);
+    assert!((speed("O1") - 13.943_036).abs() < 1e-4);
+}
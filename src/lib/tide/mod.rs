@@ -0,0 +1,9 @@
+mod constituent;
+mod station;
+mod stream;
+
+pub use constituent::*;
+pub use station::*;
+pub use stream::*;
+
+use crate::*;
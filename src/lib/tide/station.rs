@@ -0,0 +1,226 @@
+use super::*;
+use serde_derive::Deserialize;
+
+/// The amplitude and phase of a single constituent at a station.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HarmonicConstant {
+    /// The name of the constituent, e.g. `M2`.
+    pub name: String,
+    /// The amplitude in meters.
+    pub amplitude: f64,
+    /// The phase lag in degrees relative to Greenwich (UTC).
+    pub phase: f64,
+}
+
+/// A tide station as it is stored in the station file.
+#[derive(Debug, Clone, Deserialize)]
+struct StationDefinition {
+    name: String,
+    lat: f64,
+    lon: f64,
+    /// The mean water level above chart datum in meters.
+    datum_offset: f64,
+    #[serde(default)]
+    constituent: Vec<HarmonicConstant>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StationFile {
+    #[serde(default)]
+    station: Vec<StationDefinition>,
+}
+
+/// A high or low water.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TideExtreme {
+    /// UTC time in seconds since the unix epoch.
+    pub time: f64,
+    /// Height above chart datum in meters.
+    pub height: f64,
+    pub high: bool,
+}
+
+/// A place for which the tide can be predicted from its harmonic constants.
+#[derive(Debug, Clone)]
+pub struct TideStation {
+    pub name: String,
+    pub position: GeoPoint,
+    /// The mean water level above chart datum in meters.
+    pub datum_offset: f64,
+    constituents: Vec<(&'static Constituent, HarmonicConstant)>,
+}
+
+impl TideStation {
+    pub fn new(
+        name: impl Into<String>,
+        position: GeoPoint,
+        datum_offset: f64,
+        constants: Vec<HarmonicConstant>,
+    ) -> Self {
+        let name = name.into();
+        let constituents = constants
+            .into_iter()
+            .filter_map(|constant| match Constituent::by_name(&constant.name) {
+                Some(constituent) => Some((constituent, constant)),
+                None => {
+                    log::warn!(
+                        "The constituent {} of the tide station {} is not supported.",
+                        constant.name,
+                        name
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            name,
+            position,
+            datum_offset,
+            constituents,
+        }
+    }
+
+    /// Predicts the height above chart datum in meters at `time` in seconds since the unix epoch.
+    pub fn height(&self, time: f64) -> f64 {
+        let arguments = AstronomicalArguments::at(time);
+        self.datum_offset
+            + self
+                .constituents
+                .iter()
+                .map(|(constituent, constant)| {
+                    let (f, u) = constituent.nodal_correction(&arguments);
+                    let v = constituent.equilibrium_argument(&arguments);
+                    f * constant.amplitude * (v + u - constant.phase).to_radians().cos()
+                })
+                .sum::<f64>()
+    }
+
+    /// Samples the height from `start` to `end` in steps of `step` seconds.
+    pub fn heights(&self, start: f64, end: f64, step: f64) -> Vec<f64> {
+        let count = ((end - start) / step).floor() as usize + 1;
+        (0..count)
+            .map(|i| self.height(start + i as f64 * step))
+            .collect()
+    }
+
+    /// Finds all high and low waters between `start` and `end`.
+    pub fn extremes(&self, start: f64, end: f64) -> Vec<TideExtreme> {
+        // Sample densely enough so we don't miss any turn of the tide.
+        const STEP: f64 = 600.0;
+        let rate = |time: f64| self.height(time + 1.0) - self.height(time - 1.0);
+
+        let mut extremes = vec![];
+        let mut t0 = start;
+        let mut r0 = rate(t0);
+        while t0 < end {
+            let t1 = (t0 + STEP).min(end);
+            let r1 = rate(t1);
+            if r0 > 0.0 && r1 <= 0.0 || r0 < 0.0 && r1 >= 0.0 {
+                // Bisect the zero of the rate of change down to a second.
+                let (mut a, mut b) = (t0, t1);
+                while b - a > 1.0 {
+                    let m = (a + b) / 2.0;
+                    if (rate(m) > 0.0) == (r0 > 0.0) {
+                        a = m;
+                    } else {
+                        b = m;
+                    }
+                }
+                let time = (a + b) / 2.0;
+                extremes.push(TideExtreme {
+                    time,
+                    height: self.height(time),
+                    high: r0 > 0.0,
+                });
+            }
+            t0 = t1;
+            r0 = r1;
+        }
+        extremes
+    }
+}
+
+/// All the known tide stations.
+#[derive(Debug, Clone, Default)]
+pub struct TideStations {
+    stations: Vec<TideStation>,
+}
+
+impl TideStations {
+    /// Loads the stations from a TOML file.
+    pub fn load(path: &str) -> Self {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                log::warn!("Unable to read the tide stations from {}:", path);
+                log::warn!("{}", err);
+                return Default::default();
+            }
+        };
+
+        match toml::from_str::<StationFile>(&content) {
+            Ok(file) => Self {
+                stations: file
+                    .station
+                    .into_iter()
+                    .map(|s| {
+                        TideStation::new(
+                            s.name,
+                            GeoPoint::new(s.lat, s.lon),
+                            s.datum_offset,
+                            s.constituent,
+                        )
+                    })
+                    .collect(),
+            },
+            Err(err) => {
+                log::error!("Unable to parse the tide stations in {}:", path);
+                log::error!("{}", err);
+                Default::default()
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TideStation> {
+        self.stations.iter()
+    }
+
+    /// Returns the station which is closest to `position`.
+    pub fn nearest(&self, position: &GeoPoint) -> Option<&TideStation> {
+        self.stations.iter().min_by(|a, b| {
+            a.position
+                .distance_to(position)
+                .partial_cmp(&b.position.distance_to(position))
+                .unwrap()
+        })
+    }
+}
+
+#[test]
+fn semidiurnal_extremes() {
+    let station = TideStation::new(
+        "Test",
+        GeoPoint::new(48.38, -4.5),
+        4.0,
+        vec![HarmonicConstant {
+            name: "M2".to_string(),
+            amplitude: 2.0,
+            phase: 140.0,
+        }],
+    );
+
+    // 2020-06-01 00:00 UTC
+    let start = 1_590_969_600.0;
+    let extremes = station.extremes(start, start + 86_400.0);
+    assert!(extremes.len() == 3 || extremes.len() == 4);
+    for pair in extremes.windows(2) {
+        assert_ne!(pair[0].high, pair[1].high);
+        // Half the M2 period.
+        assert!((pair[1].time - pair[0].time - 22_357.0).abs() < 60.0);
+    }
+    for extreme in &extremes {
+        let expected = if extreme.high { 6.0 } else { 2.0 };
+        assert!((extreme.height - expected).abs() < 0.1);
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+use serde_derive::Deserialize;
+
+/// The number of hourly entries of a tidal stream point, from 6 hours before to 6 hours after high water.
+const HOURS: usize = 13;
+
+/// The tidal stream at a single position, given per hour relative to high water at the reference port.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TidalStreamPoint {
+    pub lat: f64,
+    pub lon: f64,
+    /// The direction the stream is setting to in degrees true for HW-6 to HW+6.
+    pub directions: Vec<f64>,
+    /// The rate in knots for HW-6 to HW+6.
+    pub rates: Vec<f64>,
+}
+
+impl TidalStreamPoint {
+    pub fn position(&self) -> GeoPoint {
+        GeoPoint::new(self.lat, self.lon)
+    }
+
+    /// Returns the direction and rate `hours` after high water.
+    ///
+    /// The hourly values are interpolated as vectors so directions wrap around north correctly.
+    pub fn stream_at(&self, hours: f64) -> (f64, f64) {
+        let index = hours.clamp(-6.0, 6.0) + 6.0;
+        let i0 = index.floor() as usize;
+        let i1 = (i0 + 1).min(HOURS - 1);
+        let t = index - i0 as f64;
+
+        let (e0, n0) = course_to_vector(self.directions[i0], self.rates[i0]);
+        let (e1, n1) = course_to_vector(self.directions[i1], self.rates[i1]);
+        let east = e0 + (e1 - e0) * t;
+        let north = n0 + (n1 - n0) * t;
+        (
+            normalize_degrees(east.atan2(north).to_degrees()),
+            (east * east + north * north).sqrt(),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AtlasFile {
+    reference_port: String,
+    #[serde(default)]
+    point: Vec<TidalStreamPoint>,
+}
+
+/// A tidal stream atlas which gives the streams relative to high water at a reference port.
+#[derive(Debug, Clone, Default)]
+pub struct TidalStreamAtlas {
+    /// The name of the tide station the hours are relative to.
+    pub reference_port: String,
+    points: Vec<TidalStreamPoint>,
+}
+
+impl TidalStreamAtlas {
+    /// Loads the atlas from a TOML file.
+    pub fn load(path: &str) -> Self {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                log::warn!("Unable to read the tidal streams from {}:", path);
+                log::warn!("{}", err);
+                return Default::default();
+            }
+        };
+
+        match toml::from_str::<AtlasFile>(&content) {
+            Ok(file) => {
+                let points = file
+                    .point
+                    .into_iter()
+                    .filter(|p| {
+                        let valid = p.directions.len() == HOURS && p.rates.len() == HOURS;
+                        if !valid {
+                            log::warn!(
+                                "The tidal stream at {} {} needs {} hourly values.",
+                                p.lat,
+                                p.lon,
+                                HOURS
+                            );
+                        }
+                        valid
+                    })
+                    .collect();
+                Self {
+                    reference_port: file.reference_port,
+                    points,
+                }
+            }
+            Err(err) => {
+                log::error!("Unable to parse the tidal streams in {}:", path);
+                log::error!("{}", err);
+                Default::default()
+            }
+        }
+    }
+
+    pub fn points(&self) -> &[TidalStreamPoint] {
+        &self.points
+    }
+
+    /// Returns the hours since the closest high water at the reference port.
+    pub fn hours_from_high_water(&self, stations: &TideStations, time: f64) -> Option<f64> {
+        let station = stations
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(&self.reference_port))?;
+        // A tidal cycle is about 12.4 hours, so there is always a high water within this window.
+        station
+            .extremes(time - 8.0 * 3600.0, time + 8.0 * 3600.0)
+            .into_iter()
+            .filter(|e| e.high)
+            .map(|e| (time - e.time) / 3600.0)
+            .min_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap())
+    }
+}
+
+#[test]
+fn stream_interpolation_wraps_north() {
+    let mut directions = vec![0.0; HOURS];
+    let mut rates = vec![1.0; HOURS];
+    directions[6] = 350.0;
+    directions[7] = 10.0;
+    rates[7] = 3.0;
+    let point = TidalStreamPoint {
+        lat: 0.0,
+        lon: 0.0,
+        directions,
+        rates,
+    };
+
+    let (direction, rate) = point.stream_at(0.0);
+    assert!((direction - 350.0).abs() < 1e-9);
+    assert!((rate - 1.0).abs() < 1e-9);
+
+    let (direction, rate) = point.stream_at(0.5);
+    assert!(!(10.0..=350.0).contains(&direction));
+    assert!(rate > 1.0 && rate < 3.0);
+}
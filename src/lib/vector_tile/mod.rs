@@ -12,7 +12,7 @@ use super::*;
 use core::ops::Range;
 use lyon::{
     math::*,
-    path::Path,
+    path::{Path, PathEvent},
     tessellation::{FillOptions, FillTessellator},
 };
 use varint::ZigZag;
@@ -117,6 +117,33 @@ pub fn geometry_commands_to_paths(geometry_type: GeomType, geometry: &[u32]) ->
     paths
 }
 
+/// Returns the index after the last point of each sub path of a path.
+pub fn path_ring_ends(path: &Path) -> Vec<usize> {
+    let mut ring_ends: Vec<usize> = vec![];
+    let mut count = 0;
+    for event in path.iter() {
+        match event {
+            PathEvent::MoveTo(_) => {
+                count += 1;
+                ring_ends.push(count);
+            }
+            // Curves store their control points too.
+            PathEvent::Line(_) | PathEvent::Quadratic(_) | PathEvent::Cubic(_) => {
+                count += match event {
+                    PathEvent::Quadratic(_) => 2,
+                    PathEvent::Cubic(_) => 3,
+                    _ => 1,
+                };
+                if let Some(end) = ring_ends.last_mut() {
+                    *end = count;
+                }
+            }
+            PathEvent::Close(_) => {}
+        }
+    }
+    ring_ends
+}
+
 pub fn paths_to_drawable(
     builder: &mut MeshBuilder,
     geometry_type: GeomType,
@@ -112,6 +112,9 @@ impl Tile {
         features.push((current_feature_id, range));
         objects.push(object);
 
+        // The filters are evaluated for every feature, so don't hold the lock meanwhile.
+        let filters = feature_collection.read().unwrap().filters().to_vec();
+
         // Transform all features of the tile.
         for layer in tile.layers {
             let mut map: std::collections::HashMap<Selector, Vec<(GeomType, Vec<Path>)>> =
@@ -119,7 +122,7 @@ impl Tile {
 
             // Preevaluate the selectors and group features by the selector they belong to.
             for feature in &layer.features {
-                let (selector, tags) = Self::classify(&layer, &feature, &selection_tags);
+                let (selector, tags) = Self::classify(&layer, &feature, &selection_tags, &filters);
 
                 let paths = geometry_commands_to_paths(feature.type_pb, &feature.geometry);
 
@@ -141,12 +144,17 @@ impl Tile {
                 };
 
                 if let Some(ot) = object_type {
-                    objects.push(Object::new_with_tags(
+                    let object = Object::new_with_tags(
                         selector.clone(),
                         paths[0].points().to_vec(),
                         tags,
                         ot,
-                    ));
+                    );
+                    objects.push(if feature.type_pb == GeomType::POLYGON {
+                        object.with_ring_ends(path_ring_ends(&paths[0]))
+                    } else {
+                        object
+                    });
                 }
 
                 if let Some(value) = map.get_mut(&selector) {
@@ -324,34 +332,65 @@ impl Tile {
         layer: &vector_tile::mod_Tile::Layer,
         feature: &vector_tile::mod_Tile::Feature,
         selection_tags: &[String],
+        filters: &[Selector],
     ) -> (Selector, HashMap<String, String>) {
-        let mut selector = Selector::new()
-            .with_type("layer".to_string())
-            .with_any("name".to_string(), layer.name.to_string());
-
-        let mut tags = HashMap::new();
-
-        for tag in feature.tags.chunks(2) {
-            let key = layer.keys[tag[0] as usize].to_string();
-            let value = layer.values[tag[1] as usize].clone();
-            match &key[..] {
-                "class" => {
-                    selector
-                        .classes
-                        .push(value.string_value.clone().unwrap().to_string());
-                }
-                _ => {
-                    if selection_tags.contains(&key) {
-                        selector = selector.with_any(key.clone(), value.to_string());
-                    } else {
-                        tags.insert(key.clone(), value.to_string());
-                    }
-                }
-            }
+        let tags = feature
+            .tags
+            .chunks(2)
+            .map(|tag| {
+                (
+                    layer.keys[tag[0] as usize].to_string(),
+                    layer.values[tag[1] as usize].to_string(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        classify_tags(&layer.name, tags, selection_tags, filters)
+    }
+}
+
+/// Create the selector of a feature in the layer `layer_name` and return the tags which did not go into it.
+///
+/// `filters` are the selectors of the rules with a filter expression, see `RulesCache::filters`.
+/// Only the filters of the rules that may match the feature are evaluated, so the other ones don't split its features.
+/// They see all the tags, also the class and the selection tags which go into the selector.
+pub fn classify_tags(
+    layer_name: &str,
+    mut tags: HashMap<String, String>,
+    selection_tags: &[String],
+    filters: &[Selector],
+) -> (Selector, HashMap<String, String>) {
+    let mut selector = Selector::new()
+        .with_type("layer".to_string())
+        .with_any("name".to_string(), layer_name.to_string());
+
+    if let Some(class) = tags.get("class") {
+        selector.classes.push(class.clone());
+    }
+    for key in selection_tags {
+        if let Some(value) = tags.get(key) {
+            selector = selector.with_any(key.clone(), value.clone());
+        }
+    }
+
+    let mut satisfied: Vec<Filter> = vec![];
+    for filter in filters
+        .iter()
+        .filter(|rule| selector.may_match(rule))
+        .filter_map(|rule| rule.filter.as_ref())
+    {
+        if !satisfied.contains(filter) && filter.matches(&tags) {
+            satisfied.push(filter.clone());
         }
+    }
+    selector.satisfied = satisfied;
 
-        (selector, tags)
+    tags.remove("class");
+    for key in selection_tags {
+        tags.remove(key);
     }
+
+    (selector, tags)
 }
 
 impl<'a> std::string::ToString for vector_tile::mod_Tile::Value<'a> {